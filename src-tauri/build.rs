@@ -1,7 +1,33 @@
 use std::env;
 use std::path::Path;
+#[cfg(feature = "python")]
+use std::process::Command;
+
+/// Linuxのパッケージ（deb/AppImage）で同梱したlibpythonの場所（実行ファイルからの相対パス）
+#[cfg(feature = "python")]
+const LINUX_BUNDLED_PYTHON_LIB_DIRS: [&str; 2] = [
+    "$ORIGIN/../lib/Vinsert/python/lib",
+    "$ORIGIN/python/lib",
+];
+
+/// リンカーが標準で探すため、rpathを付ける必要がないディレクトリ
+#[cfg(feature = "python")]
+const LINUX_SYSTEM_LIB_DIRS: [&str; 4] = ["/lib", "/lib64", "/usr/lib", "/usr/lib64"];
 
 fn setup_python() {
+    // pythonフィーチャーが無効な場合はPythonを探さない
+    if env::var_os("CARGO_FEATURE_PYTHON").is_none() {
+        println!("cargo:warning=Python support disabled (python feature is off)");
+        return;
+    }
+    
+    // LinuxはPyO3が選んだPythonに合わせるため、PYO3_PYTHONの指定にかかわらず設定する
+    #[cfg(feature = "python")]
+    if cfg!(target_os = "linux") {
+        setup_linux_python();
+        return;
+    }
+    
     // 環境変数でPythonパスが指定されている場合はそれを使用
     if env::var("PYO3_PYTHON").is_ok() {
         println!("cargo:warning=Using PYO3_PYTHON from environment");
@@ -82,9 +108,116 @@ fn setup_macos_python() {
     println!("cargo:warning=Please install Python 3.11 via Homebrew or set PYO3_PYTHON environment variable");
 }
 
+/**
+ * PyO3がリンクするPython（pyo3-build-configの設定）に合わせてrpathと実行時の情報を設定する
+ * PyO3はPYO3_PYTHON（未設定ならPATH上のpython3）を使うため、ここで別のPythonを探すと
+ * リンクされるlibpythonとVINSERT_PYTHON_PATHが食い違う
+ */
+#[cfg(feature = "python")]
+fn setup_linux_python() {
+    println!("cargo:warning=Setting up Python for Linux");
+    
+    let config = pyo3_build_config::get();
+    let python_path = match &config.executable {
+        Some(python_path) => python_path.clone(),
+        None => {
+            println!("cargo:warning=WARNING: PyO3 did not report the Python interpreter it links against");
+            println!("cargo:warning=Please set PYO3_PYTHON to the python3 to build with");
+            return;
+        }
+    };
+    let lib_dir = config
+        .lib_dir
+        .clone()
+        .or_else(|| query_linux_python(&python_path).map(|(_, lib_dir)| lib_dir))
+        .unwrap_or_default();
+    
+    let home = env::var("HOME").unwrap_or_default();
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    
+    // python-build-standalone（アプリに同梱するPython）はPYO3_PYTHONで指定する
+    let standalone_roots = [
+        format!("{}/python-standalone/python", manifest_dir),
+        "/opt/python-build-standalone/python".to_string(),
+        format!("{}/.local/share/python-build-standalone/python", home),
+    ];
+    let embedded = standalone_roots
+        .iter()
+        .any(|root| Path::new(&python_path).starts_with(root));
+    
+    if embedded {
+        // パッケージに同梱したlibpythonを優先し、開発時はビルドに使ったものを使う
+        for bundled in &LINUX_BUNDLED_PYTHON_LIB_DIRS {
+            println!("cargo:rustc-link-arg=-Wl,-rpath,{}", bundled);
+        }
+    } else if let Some(root) = standalone_roots
+        .iter()
+        .find(|root| Path::new(&format!("{}/bin/python3", root)).exists())
+    {
+        println!(
+            "cargo:warning=python-build-standalone found at {}; set PYO3_PYTHON={}/bin/python3 to bundle it",
+            root, root
+        );
+    }
+    emit_linux_link_args(&lib_dir);
+    emit_python_env(&python_path, embedded);
+}
+
+/**
+ * インタープリターに実行ファイルの実体とlibpythonのディレクトリを問い合わせる
+ */
+#[cfg(feature = "python")]
+fn query_linux_python(python: &str) -> Option<(String, String)> {
+    let output = Command::new(python)
+        .args(["-c", "import sys, sysconfig; print(sys.executable); print(sysconfig.get_config_var('LIBDIR') or '')"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut lines = stdout.lines();
+    let executable = lines.next()?.trim().to_string();
+    let lib_dir = lines.next().unwrap_or_default().trim().to_string();
+    if executable.is_empty() {
+        return None;
+    }
+    Some((executable, lib_dir))
+}
+
+/**
+ * libpythonのディレクトリをリンク対象に加え、標準以外の場所であればrpathを付ける
+ */
+#[cfg(feature = "python")]
+fn emit_linux_link_args(lib_dir: &str) {
+    if lib_dir.is_empty() {
+        return;
+    }
+    println!("cargo:rustc-link-search=native={}", lib_dir);
+    
+    let is_system_dir = LINUX_SYSTEM_LIB_DIRS.contains(&lib_dir)
+        || (lib_dir.starts_with("/usr/lib/") && lib_dir.ends_with("-linux-gnu"));
+    if !is_system_dir {
+        println!("cargo:rustc-link-arg=-Wl,-rpath,{}", lib_dir);
+    }
+}
+
+/**
+ * 実行時にdetect_python_environment()が参照するビルド情報を埋め込む
+ */
+#[cfg(feature = "python")]
+fn emit_python_env(python_path: &str, embedded: bool) {
+    println!("cargo:rustc-env=VINSERT_EMBEDDED_PYTHON={}", if embedded { 1 } else { 0 });
+    println!("cargo:rustc-env=VINSERT_PYTHON_PATH={}", python_path);
+    println!("cargo:rustc-env=PYO3_PYTHON={}", python_path);
+    println!("cargo:warning=Using Python at: {}", python_path);
+}
+
 fn main() {
     println!("cargo:rerun-if-changed=build.rs");
     println!("cargo:rerun-if-env-changed=PYO3_PYTHON");
+    println!("cargo:rerun-if-env-changed=HOME");
     
     // PyO3用のPython設定
     setup_python();
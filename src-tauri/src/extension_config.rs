@@ -0,0 +1,419 @@
+/*
+ * =====================================================
+ * Vinsert Editor - 拡張機能の設定
+ * setting.jsonのconfigurationで宣言された設定項目の検証と保存を行う
+ * ユーザーが変更した値はextension_config.jsonに保存し、
+ * 拡張機能からは vinsert.get_config() で参照する
+ * =====================================================
+ */
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tauri::Manager;
+
+use crate::extension_events;
+use crate::extension_host::{self, ExtensionHost};
+use crate::extension_manifest::{self, ExtensionManifest, LocalizedText};
+use crate::python_exec::PythonJobs;
+
+/**
+ * 設定項目の型
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigType {
+    String,
+    Number,
+    Integer,
+    Boolean,
+    /// itemsで指定した型の配列
+    Array,
+}
+
+impl ConfigType {
+    fn name(&self) -> &'static str {
+        match self {
+            ConfigType::String => "a string",
+            ConfigType::Number => "a number",
+            ConfigType::Integer => "an integer",
+            ConfigType::Boolean => "a boolean",
+            ConfigType::Array => "an array",
+        }
+    }
+
+    fn matches(&self, value: &Value) -> bool {
+        match self {
+            ConfigType::String => value.is_string(),
+            ConfigType::Number => value.is_number(),
+            ConfigType::Integer => value.is_i64() || value.is_u64(),
+            ConfigType::Boolean => value.is_boolean(),
+            ConfigType::Array => value.is_array(),
+        }
+    }
+}
+
+/**
+ * setting.jsonのconfigurationの1項目
+ * 設定ダイアログはこの内容から入力欄を組み立てる
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigProperty {
+    pub key: String,
+    #[serde(rename = "type")]
+    pub kind: ConfigType,
+    /// 省略時は型ごとの空の値（enumがあれば最初の選択肢）
+    #[serde(default)]
+    pub default: Option<Value>,
+    /// 選択肢（arrayの場合は各要素の選択肢）
+    #[serde(default, rename = "enum")]
+    pub choices: Vec<Value>,
+    /// arrayの要素の型（省略時はstring）
+    #[serde(default)]
+    pub items: Option<ConfigType>,
+    #[serde(default)]
+    pub minimum: Option<f64>,
+    #[serde(default)]
+    pub maximum: Option<f64>,
+    #[serde(default)]
+    pub title: Option<LocalizedText>,
+    #[serde(default)]
+    pub description: Option<LocalizedText>,
+}
+
+impl ConfigProperty {
+    fn item_type(&self) -> ConfigType {
+        self.items.unwrap_or(ConfigType::String)
+    }
+
+    /**
+     * 既定値（defaultが無い場合は型ごとの空の値）
+     */
+    pub fn default_value(&self) -> Value {
+        if let Some(default) = &self.default {
+            return default.clone();
+        }
+        if self.kind != ConfigType::Array {
+            if let Some(first) = self.choices.first() {
+                return first.clone();
+            }
+        }
+        match self.kind {
+            ConfigType::String => Value::String(String::new()),
+            ConfigType::Number | ConfigType::Integer => Value::from(0),
+            ConfigType::Boolean => Value::Bool(false),
+            ConfigType::Array => Value::Array(Vec::new()),
+        }
+    }
+
+    /**
+     * 値が型・選択肢・範囲に合っているか確認する
+     */
+    pub fn validate(&self, value: &Value) -> Result<(), String> {
+        if !self.kind.matches(value) {
+            return Err(format!("Setting '{}' must be {}", self.key, self.kind.name()));
+        }
+
+        match value.as_array() {
+            Some(items) => {
+                let item_type = self.item_type();
+                items.iter().try_for_each(|item| {
+                    if item_type.matches(item) {
+                        self.validate_scalar(item)
+                    } else {
+                        Err(format!("Items of setting '{}' must be {}", self.key, item_type.name()))
+                    }
+                })
+            }
+            None => self.validate_scalar(value),
+        }
+    }
+
+    fn validate_scalar(&self, value: &Value) -> Result<(), String> {
+        if !self.choices.is_empty() && !self.choices.contains(value) {
+            return Err(format!(
+                "Setting '{}' must be one of: {}",
+                self.key,
+                self.choices.iter().map(Value::to_string).collect::<Vec<_>>().join(", ")
+            ));
+        }
+
+        if let Some(number) = value.as_f64() {
+            if self.minimum.is_some_and(|minimum| number < minimum) {
+                return Err(format!("Setting '{}' must be at least {}", self.key, self.minimum.unwrap_or_default()));
+            }
+            if self.maximum.is_some_and(|maximum| number > maximum) {
+                return Err(format!("Setting '{}' must be at most {}", self.key, self.maximum.unwrap_or_default()));
+            }
+        }
+        Ok(())
+    }
+}
+
+/**
+ * configurationの宣言に誤りがないか確認する（マニフェストの検証用）
+ */
+pub fn check_schema(properties: &[ConfigProperty]) -> Vec<String> {
+    let mut errors = Vec::new();
+    let mut keys = HashSet::new();
+
+    for property in properties {
+        if property.key.trim().is_empty() {
+            errors.push("Setting key must not be empty".to_string());
+            continue;
+        }
+        if !keys.insert(property.key.as_str()) {
+            errors.push(format!("Duplicate setting key '{}'", property.key));
+        }
+
+        match (property.kind, property.items) {
+            (ConfigType::Array, Some(ConfigType::Array)) => {
+                errors.push(format!("Setting '{}' cannot be an array of arrays", property.key));
+            }
+            (ConfigType::Array, _) | (_, None) => {}
+            (_, Some(_)) => errors.push(format!("items is only allowed for array setting '{}'", property.key)),
+        }
+
+        if let (Some(minimum), Some(maximum)) = (property.minimum, property.maximum) {
+            if minimum > maximum {
+                errors.push(format!("Setting '{}' has minimum greater than maximum", property.key));
+            }
+        }
+
+        let choice_type = if property.kind == ConfigType::Array { property.item_type() } else { property.kind };
+        if property.choices.iter().any(|choice| !choice_type.matches(choice)) {
+            errors.push(format!("enum of setting '{}' contains a value that is not {}", property.key, choice_type.name()));
+        } else if let Err(e) = property.validate(&property.default_value()) {
+            errors.push(format!("Invalid default: {}", e));
+        }
+    }
+
+    errors
+}
+
+/**
+ * 既定値に保存済みの値を重ねた設定値（宣言されていない項目や不正な値は無視する）
+ */
+pub fn effective_values(properties: &[ConfigProperty], stored: &Map<String, Value>) -> Map<String, Value> {
+    properties
+        .iter()
+        .map(|property| {
+            let value = stored
+                .get(&property.key)
+                .filter(|value| property.validate(value).is_ok())
+                .cloned()
+                .unwrap_or_else(|| property.default_value());
+            (property.key.clone(), value)
+        })
+        .collect()
+}
+
+// =====================================================
+// 保存（extension_config.json）と読み込み済み拡張機能の設定値
+// =====================================================
+
+/// 拡張機能IDごとの、ユーザーが変更した設定値
+type StoredConfig = BTreeMap<String, Map<String, Value>>;
+
+static CONFIG_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+// 設定ファイルの同時書き込みを防ぐ
+static CONFIG_LOCK: Mutex<()> = Mutex::new(());
+
+// 読み込み済み拡張機能の設定値（vinsert.get_config()が参照する）
+static ACTIVE_VALUES: OnceLock<Mutex<HashMap<String, Map<String, Value>>>> = OnceLock::new();
+
+fn active_values() -> &'static Mutex<HashMap<String, Map<String, Value>>> {
+    ACTIVE_VALUES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/**
+ * アプリケーション起動時に設定ファイルの場所を登録する
+ */
+pub fn initialize(app: &tauri::AppHandle) {
+    match config_path(app) {
+        Ok(path) => {
+            let _ = CONFIG_PATH.set(path);
+        }
+        Err(e) => println!("⚠️ Extension settings will not be saved: {}", e),
+    }
+}
+
+fn config_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    Ok(app_data.join("vinsert").join("extension_config.json"))
+}
+
+/**
+ * 保存済みの設定値を読む（ファイルが無ければ空）
+ * 読めない・壊れている場合に空として扱うと、次の保存で他の拡張機能の設定まで消えるためエラーにする
+ */
+fn load_stored(path: &Path) -> Result<StoredConfig, String> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(StoredConfig::default()),
+        Err(e) => return Err(format!("Failed to read '{}': {}", path.display(), e)),
+    };
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse '{}': {}", path.display(), e))
+}
+
+fn save_stored(path: &Path, stored: &StoredConfig) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create '{}': {}", parent.display(), e))?;
+    }
+    let content = serde_json::to_string_pretty(stored)
+        .map_err(|e| format!("Failed to serialize extension settings: {}", e))?;
+    std::fs::write(path, content).map_err(|e| format!("Failed to write '{}': {}", path.display(), e))
+}
+
+/**
+ * 拡張機能を読み込んだときに設定値を用意する（起動前・保存先が無い場合は既定値）
+ */
+pub fn activate(manifest: &ExtensionManifest) {
+    let stored = match CONFIG_PATH.get().map(|path| load_stored(path)) {
+        Some(Ok(mut stored)) => stored.remove(&manifest.id).unwrap_or_default(),
+        Some(Err(e)) => {
+            println!("⚠️ Extension {} uses default settings: {}", manifest.id, e);
+            Map::new()
+        }
+        None => Map::new(),
+    };
+    let values = effective_values(&manifest.configuration, &stored);
+    if let Ok(mut active) = active_values().lock() {
+        active.insert(manifest.id.clone(), values);
+    }
+}
+
+/**
+ * アンロードした拡張機能の設定値を取り除く
+ */
+pub fn deactivate(extension_id: &str) {
+    if let Ok(mut active) = active_values().lock() {
+        active.remove(extension_id);
+    }
+}
+
+/**
+ * 読み込み済み拡張機能の設定値（vinsert.get_config()用）
+ */
+pub fn current_values(extension_id: &str) -> Option<Map<String, Value>> {
+    active_values()
+        .lock()
+        .ok()
+        .and_then(|active| active.get(extension_id).cloned())
+}
+
+/**
+ * 拡張機能の保存済みの設定値を削除する（アンインストール時）
+ */
+pub fn forget_config(app: &tauri::AppHandle, extension_id: &str) -> Result<(), String> {
+    let _guard = CONFIG_LOCK.lock().map_err(|e| e.to_string())?;
+    let path = config_path(app)?;
+    let mut stored = load_stored(&path)?;
+    if stored.remove(extension_id).is_some() {
+        save_stored(&path, &stored)?;
+    }
+    Ok(())
+}
+
+// =====================================================
+// Tauriコマンド
+// =====================================================
+
+/**
+ * get_extension_config/set_extension_configの戻り値
+ * schemaは設定ダイアログの入力欄、valuesは既定値を含む現在の値
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct ExtensionConfig {
+    pub schema: Vec<ConfigProperty>,
+    pub values: Map<String, Value>,
+}
+
+fn installed_manifest(app: &tauri::AppHandle, extension_id: &str) -> Result<ExtensionManifest, String> {
+    extension_manifest::validate_extension_id(extension_id)?;
+    let extension_dir = extension_host::extensions_directory(app)?.join(extension_id);
+    extension_manifest::load_manifest(extension_id, &extension_dir)
+}
+
+/**
+ * 拡張機能の設定項目と現在の値を返す
+ */
+#[tauri::command]
+pub async fn get_extension_config(app: tauri::AppHandle, extension_id: String) -> Result<ExtensionConfig, String> {
+    let manifest = installed_manifest(&app, &extension_id)?;
+    let stored = load_stored(&config_path(&app)?)?.remove(&extension_id).unwrap_or_default();
+    let values = effective_values(&manifest.configuration, &stored);
+    Ok(ExtensionConfig {
+        schema: manifest.configuration,
+        values,
+    })
+}
+
+/**
+ * 拡張機能の設定値を変更して保存する
+ * valuesに含めた項目だけを変更し、nullを指定した項目は既定値に戻す
+ * 読み込み済みでconfig_changedを購読している拡張機能には変更後の値を通知する
+ */
+#[tauri::command]
+pub async fn set_extension_config(
+    app: tauri::AppHandle,
+    host: tauri::State<'_, ExtensionHost>,
+    jobs: tauri::State<'_, PythonJobs>,
+    extension_id: String,
+    values: Map<String, Value>,
+) -> Result<ExtensionConfig, String> {
+    let manifest = installed_manifest(&app, &extension_id)?;
+
+    let stored = {
+        let _guard = CONFIG_LOCK.lock().map_err(|e| e.to_string())?;
+        let path = config_path(&app)?;
+        let mut stored = load_stored(&path)?;
+        let entry = stored.entry(extension_id.clone()).or_default();
+
+        for (key, value) in values {
+            let property = manifest
+                .configuration
+                .iter()
+                .find(|property| property.key == key)
+                .ok_or_else(|| format!("Unknown setting '{}' for extension '{}'", key, extension_id))?;
+            if value.is_null() {
+                entry.remove(&key);
+            } else {
+                property.validate(&value)?;
+                entry.insert(key, value);
+            }
+        }
+
+        // 宣言から無くなった項目は保存しない
+        entry.retain(|key, _| manifest.configuration.iter().any(|property| &property.key == key));
+        let entry = entry.clone();
+        if entry.is_empty() {
+            stored.remove(&extension_id);
+        }
+        save_stored(&path, &stored)?;
+        entry
+    };
+
+    let values = effective_values(&manifest.configuration, &stored);
+    println!("⚙️ Extension settings saved: {}", extension_id);
+
+    if let Ok(mut active) = active_values().lock() {
+        if let Some(active) = active.get_mut(&extension_id) {
+            *active = values.clone();
+        }
+    }
+
+    extension_events::config_changed(&host, &jobs, &extension_id, &values).await;
+
+    Ok(ExtensionConfig {
+        schema: manifest.configuration,
+        values,
+    })
+}
@@ -0,0 +1,248 @@
+/*
+ * =====================================================
+ * Vinsert Editor - 拡張機能のライフサイクルイベント
+ * setting.jsonのeventsで購読した拡張機能へ、ファイルの読み書きや
+ * アプリ終了などのイベントをバックエンドから配信する
+ * =====================================================
+ */
+
+use serde_json::json;
+use std::time::Duration;
+
+use crate::extension_host::{ExtensionHost, EXTENSION_TIMEOUT_MS};
+use crate::extension_permissions::Permission;
+use crate::python_exec::PythonJobs;
+use crate::vinsert_api::{self, EditorSnapshot};
+
+pub const ON_ACTIVATE: &str = "on_activate";
+pub const ON_DEACTIVATE: &str = "on_deactivate";
+pub const FILE_OPENED: &str = "file_opened";
+/// 戻り値の {"content": "..."} で保存内容を書き換えられる
+pub const BEFORE_SAVE: &str = "before_save";
+pub const AFTER_SAVE: &str = "after_save";
+pub const SELECTION_CHANGED: &str = "selection_changed";
+pub const LANGUAGE_CHANGED: &str = "language_changed";
+/// set_extension_configで設定が変更された（ペイロードは {"values": {...}}）
+pub const CONFIG_CHANGED: &str = "config_changed";
+pub const APP_EXIT: &str = "app_exit";
+
+/// setting.jsonのeventsに指定できるイベント
+pub const LIFECYCLE_EVENTS: [&str; 9] = [
+    ON_ACTIVATE,
+    ON_DEACTIVATE,
+    FILE_OPENED,
+    BEFORE_SAVE,
+    AFTER_SAVE,
+    SELECTION_CHANGED,
+    LANGUAGE_CHANGED,
+    CONFIG_CHANGED,
+    APP_EXIT,
+];
+
+/// 終了処理を長く止めないよう、app_exitは短い制限時間で打ち切る
+const APP_EXIT_TIMEOUT_MS: u64 = 1_000;
+/// 購読している拡張機能の数にかかわらず、app_exitの通知全体をこの時間で打ち切る
+const APP_EXIT_DEADLINE_MS: u64 = 3_000;
+
+pub fn is_lifecycle_event(event_type: &str) -> bool {
+    LIFECYCLE_EVENTS.contains(&event_type)
+}
+
+/**
+ * on_eventの戻り値にactionがあればWebViewへ転送する
+ */
+pub fn forward_response(extension_id: &str, response: serde_json::Value) {
+    if response.get("action").is_none() {
+        return;
+    }
+    if let Err(e) = vinsert_api::forward_action(extension_id, response) {
+        println!("⚠️ Failed to forward action from {}: {}", extension_id, e);
+    }
+}
+
+/**
+ * 購読しているすべての拡張機能へイベントを配信する
+ * 拡張機能ごとに制限時間を設け、失敗しても残りの拡張機能へは配信を続ける
+ */
+pub async fn broadcast(
+    host: &ExtensionHost,
+    jobs: &PythonJobs,
+    event_type: &str,
+    payload: &serde_json::Value,
+    editor: &EditorSnapshot,
+    timeout_ms: u64,
+) {
+    for id in host.subscribers(event_type) {
+        let result = {
+            let host = host.clone();
+            let id = id.clone();
+            let event_type = event_type.to_string();
+            let payload = payload.clone();
+            let editor = editor.clone();
+            jobs.run(None, Some(timeout_ms), move |_py| {
+                host.dispatch(&id, &event_type, &payload, editor)
+            })
+            .await
+        };
+
+        match result {
+            Ok(Some(response)) => forward_response(&id, response),
+            Ok(None) => {}
+            Err(e) => println!("⚠️ Extension {} failed on {}: {}", id, event_type, e),
+        }
+    }
+}
+
+/**
+ * 設定が変更されたことをその拡張機能に通知する（config_changedを購読している場合のみ）
+ */
+pub async fn config_changed(
+    host: &ExtensionHost,
+    jobs: &PythonJobs,
+    extension_id: &str,
+    values: &serde_json::Map<String, serde_json::Value>,
+) {
+    if !host.is_subscribed(extension_id, CONFIG_CHANGED) {
+        return;
+    }
+
+    let result = {
+        let host = host.clone();
+        let id = extension_id.to_string();
+        let payload = json!({ "values": values });
+        jobs.run(None, Some(EXTENSION_TIMEOUT_MS), move |_py| {
+            host.dispatch(&id, CONFIG_CHANGED, &payload, EditorSnapshot::default())
+        })
+        .await
+    };
+
+    match result {
+        Ok(Some(response)) => forward_response(extension_id, response),
+        Ok(None) => {}
+        Err(e) => println!("⚠️ Extension {} failed on {}: {}", extension_id, CONFIG_CHANGED, e),
+    }
+}
+
+/**
+ * ファイルを開いたことを通知する
+ */
+pub async fn file_opened(host: &ExtensionHost, jobs: &PythonJobs, path: &str, content: &str) {
+    let editor = EditorSnapshot {
+        text: content.to_string(),
+        ..Default::default()
+    };
+    broadcast(host, jobs, FILE_OPENED, &json!({ "path": path }), &editor, EXTENSION_TIMEOUT_MS).await;
+}
+
+/**
+ * 保存前の内容を購読している拡張機能に順番に渡す
+ * 拡張機能が {"content": "..."} を返した場合はその内容を次の拡張機能と保存に使う
+ * 内容はread_documentを許可された拡張機能にのみ渡し、書き換えはwrite_documentを許可された拡張機能のみ受け付ける
+ * 戻り値は書き換え後の内容（どの拡張機能も変更しなければNone）
+ */
+pub async fn before_save(host: &ExtensionHost, jobs: &PythonJobs, path: &str, original: &str) -> Option<String> {
+    let mut content = original.to_string();
+
+    for id in host.subscribers(BEFORE_SAVE) {
+        let editor = EditorSnapshot {
+            text: content.clone(),
+            ..Default::default()
+        };
+        let payload = if host.allows(&id, &Permission::ReadDocument) {
+            json!({ "path": path, "content": content })
+        } else {
+            json!({ "path": path })
+        };
+        let result = {
+            let host = host.clone();
+            let id = id.clone();
+            jobs.run(None, Some(EXTENSION_TIMEOUT_MS), move |_py| {
+                host.dispatch(&id, BEFORE_SAVE, &payload, editor)
+            })
+            .await
+        };
+
+        match result {
+            Ok(Some(response)) => {
+                if let Some(modified) = response.get("content").and_then(|value| value.as_str()) {
+                    if !host.allows(&id, &Permission::WriteDocument) {
+                        println!(
+                            "⚠️ Extension {} returned content on {} without {}, ignoring",
+                            id,
+                            BEFORE_SAVE,
+                            Permission::WriteDocument.as_string()
+                        );
+                    } else if modified != content {
+                        println!("✏️ Extension {} modified content before save", id);
+                        content = modified.to_string();
+                    }
+                }
+                forward_response(&id, response);
+            }
+            Ok(None) => {}
+            Err(e) => println!("⚠️ Extension {} failed on {}: {}", id, BEFORE_SAVE, e),
+        }
+    }
+
+    if content == original {
+        None
+    } else {
+        Some(content)
+    }
+}
+
+/**
+ * 保存が完了したことを通知する
+ */
+pub async fn after_save(host: &ExtensionHost, jobs: &PythonJobs, path: &str, content: &str) {
+    let editor = EditorSnapshot {
+        text: content.to_string(),
+        ..Default::default()
+    };
+    broadcast(host, jobs, AFTER_SAVE, &json!({ "path": path }), &editor, EXTENSION_TIMEOUT_MS).await;
+}
+
+/**
+ * アプリ終了を通知する（全体でAPP_EXIT_DEADLINE_MSまで）
+ */
+pub async fn app_exit(host: &ExtensionHost, jobs: &PythonJobs) {
+    let notified = tokio::time::timeout(
+        Duration::from_millis(APP_EXIT_DEADLINE_MS),
+        broadcast(host, jobs, APP_EXIT, &json!({}), &EditorSnapshot::default(), APP_EXIT_TIMEOUT_MS),
+    )
+    .await;
+    if notified.is_err() {
+        println!("⚠️ {} notification exceeded {}ms, exiting anyway", APP_EXIT, APP_EXIT_DEADLINE_MS);
+    }
+}
+
+// =====================================================
+// Tauriコマンド
+// =====================================================
+
+/**
+ * WebView側で発生したイベント（選択範囲・言語の変更）を拡張機能へ配信する
+ */
+#[tauri::command]
+pub async fn emit_extension_event(
+    host: tauri::State<'_, ExtensionHost>,
+    jobs: tauri::State<'_, PythonJobs>,
+    event_type: String,
+    payload: serde_json::Value,
+    editor: Option<EditorSnapshot>,
+) -> Result<(), String> {
+    if event_type != SELECTION_CHANGED && event_type != LANGUAGE_CHANGED {
+        return Err(format!("Event '{}' cannot be emitted from the editor", event_type));
+    }
+
+    broadcast(
+        &host,
+        &jobs,
+        &event_type,
+        &payload,
+        &editor.unwrap_or_default(),
+        EXTENSION_TIMEOUT_MS,
+    )
+    .await;
+    Ok(())
+}
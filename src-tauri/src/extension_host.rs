@@ -0,0 +1,892 @@
+/*
+ * =====================================================
+ * Vinsert Editor - 拡張機能ホスト
+ * 有効な拡張機能を一度だけ読み込み、拡張機能ごとの
+ * Pythonモジュール名前空間を常駐させて再利用する
+ * setting.jsonのhostで別プロセスを指定した拡張機能はextension_processで、
+ * runtime: "wasm"の拡張機能はextension_wasmで実行する
+ * =====================================================
+ */
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3::types::{PyModule, PyTuple};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tauri::Manager;
+
+use crate::extension_config;
+use crate::extension_events;
+use crate::extension_manifest::{self, ExtensionCommand, ExtensionManifest, HostMode, RuntimeKind};
+use crate::extension_menu;
+use crate::extension_process::{self, ProcessHostConfig, ProcessHosts};
+use crate::extension_stats::{self, ExtensionStats, SlowExtensionEvent, SlowVerdict};
+#[cfg(feature = "wasm")]
+use crate::extension_wasm::WasmExtension;
+use crate::extension_permissions::{self, Permission, PermissionSet};
+use crate::extension_signing;
+use crate::python_exec::{self, PythonExecError, PythonJobs};
+use crate::vinsert_api::{self, EditorSnapshot};
+
+/// 拡張機能の読み込み・イベント処理の既定の制限時間
+pub const EXTENSION_TIMEOUT_MS: u64 = 5_000;
+
+/**
+ * 拡張機能の実行先
+ */
+enum ExtensionRuntime {
+    /// エディタのプロセス内（moduleは拡張機能専用のモジュール名前空間、アンロードまで保持）
+    #[cfg(feature = "python")]
+    InProcess { module_name: String, module: Py<PyModule> },
+    /// 別プロセスのホスト（host_keyはextension_process::host_key）
+    Process { host_key: String },
+    /// WebAssemblyモジュールのインスタンス
+    #[cfg(feature = "wasm")]
+    Wasm(Arc<Mutex<WasmExtension>>),
+}
+
+/**
+ * 読み込み済みの拡張機能
+ */
+struct LoadedExtension {
+    runtime: ExtensionRuntime,
+    /// プロセス内の拡張機能の呼び出し時にvinsert_apiのコンテキストへ渡し、戻り値の操作の確認にも使う
+    permissions: Arc<PermissionSet>,
+    /// setting.jsonのeventsで購読しているライフサイクルイベント
+    events: Vec<String>,
+    /// setting.jsonのcommandsで追加するメニューコマンド
+    commands: Vec<ExtensionCommand>,
+}
+
+/**
+ * 拡張機能ホスト（Tauriのmanaged stateとして保持）
+ * ワーカースレッドへ渡せるようにcloneで同じ状態を共有する
+ */
+#[derive(Clone)]
+pub struct ExtensionHost {
+    extensions: Arc<Mutex<HashMap<String, LoadedExtension>>>,
+    processes: ProcessHosts,
+    stats: ExtensionStats,
+}
+
+impl ExtensionHost {
+    pub fn new() -> Self {
+        ExtensionHost {
+            extensions: Arc::new(Mutex::new(HashMap::new())),
+            processes: ProcessHosts::new(),
+            stats: ExtensionStats::new(),
+        }
+    }
+
+    /**
+     * 拡張機能の実行時間の記録
+     */
+    pub fn stats(&self) -> &ExtensionStats {
+        &self.stats
+    }
+
+    /**
+     * 別プロセスのホストで使うPythonを設定する（起動時）
+     */
+    pub fn configure_process_hosts(&self, config: ProcessHostConfig) {
+        self.processes.configure(config);
+    }
+
+    /**
+     * 拡張機能を読み込む（読み込み済みの場合は再読み込み）
+     * permissionsはgrant_permissions()でユーザーが許可した権限
+     */
+    pub fn load(&self, id: &str, extension_dir: PathBuf, permissions: PermissionSet) -> Result<(), String> {
+        let manifest = extension_manifest::load_manifest(id, &extension_dir)?;
+        extension_signing::check(id, &extension_dir)?;
+        let permissions = Arc::new(permissions);
+
+        let (reloaded, previous) = match (manifest.runtime, manifest.host) {
+            (RuntimeKind::Wasm, _) => self.load_wasm(id, &extension_dir, &manifest, permissions)?,
+            (RuntimeKind::Python, HostMode::InProcess) => {
+                self.load_in_process(id, &extension_dir, &manifest, permissions)?
+            }
+            (RuntimeKind::Python, HostMode::Process | HostMode::Shared) => {
+                self.load_out_of_process(id, &extension_dir, &manifest, permissions)?
+            }
+        };
+
+        // hostを変更して再読み込みした場合は以前の実行先から取り除く
+        if let Some(previous) = previous {
+            self.release(id, previous);
+        }
+
+        extension_config::activate(&manifest);
+        println!("🧩 Extension loaded: {}", id);
+        self.notify(id, extension_events::ON_ACTIVATE, &serde_json::json!({ "reload": reloaded }));
+        Ok(())
+    }
+
+    /**
+     * エディタのプロセス内に読み込む
+     * 戻り値は（再読み込みかどうか, 取り除く必要がある以前の実行先）
+     */
+    #[cfg(feature = "python")]
+    fn load_in_process(
+        &self,
+        id: &str,
+        extension_dir: &Path,
+        manifest: &ExtensionManifest,
+        permissions: Arc<PermissionSet>,
+    ) -> Result<(bool, Option<ExtensionRuntime>), String> {
+        let main_path = extension_dir.join(&manifest.main_file);
+        let code = std::fs::read_to_string(&main_path)
+            .map_err(|e| format!("Failed to read '{}': {}", main_path.display(), e))?;
+
+        let module_name = module_name_for(id);
+
+        Python::with_gil(|py| -> Result<(bool, Option<ExtensionRuntime>), String> {
+            extension_permissions::install_audit_hook(py)?;
+            // 署名した拡張機能に__pycache__があると改ざんとして扱うため、バイトコードを書き込まない（extension_signing.rs）
+            py.import_bound("sys")
+                .and_then(|sys| sys.setattr("dont_write_bytecode", true))
+                .map_err(|e| format!("Failed to disable bytecode writing: {}", e))?;
+
+            // 読み込み済みの場合、新しいコードの読み込みに失敗したら古いモジュールを使い続ける
+            let (reloaded, previous) = match self.extensions.lock().map_err(|e| e.to_string())?.get(id) {
+                Some(extension) => match &extension.runtime {
+                    ExtensionRuntime::InProcess { module, .. } => (true, Some(module.clone_ref(py))),
+                    _ => (true, None),
+                },
+                None => (false, None),
+            };
+            let restore_previous = |py: Python<'_>| match &previous {
+                Some(previous) => restore_sys_module(py, &module_name, previous.bind(py)),
+                None => remove_from_sys_modules(py, &module_name),
+            };
+
+            // sys.modulesに同名のモジュールがあると古い名前空間で実行されるため取り除いておく
+            remove_from_sys_modules(py, &module_name);
+
+            // モジュール読み込み時のトップレベルコードからもvinsert APIを使えるようにする
+            let module = match vinsert_api::with_context(permissions.clone(), EditorSnapshot::default(), || {
+                PyModule::from_code_bound(py, &code, &main_path.to_string_lossy(), &module_name)
+            }) {
+                Ok(module) => module,
+                Err(e) => {
+                    restore_previous(py);
+                    return Err(format!("Failed to load extension '{}': {}", id, e));
+                }
+            };
+
+            if !module.hasattr("on_event").unwrap_or(false) {
+                restore_previous(py);
+                return Err(format!("on_event function not found in extension '{}'", id));
+            }
+
+            // 差し替える前に、以前のモジュールへon_deactivateを通知する
+            if reloaded {
+                self.notify(id, extension_events::ON_DEACTIVATE, &serde_json::json!({ "reload": true }));
+            }
+
+            let mut extensions = self.extensions.lock().map_err(|e| e.to_string())?;
+            let replaced = extensions.insert(
+                id.to_string(),
+                LoadedExtension {
+                    runtime: ExtensionRuntime::InProcess {
+                        module_name,
+                        module: module.unbind(),
+                    },
+                    permissions,
+                    events: manifest.events.clone(),
+                    commands: manifest.commands.clone(),
+                },
+            );
+            let previous = replaced
+                .map(|extension| extension.runtime)
+                .filter(|runtime| !matches!(runtime, ExtensionRuntime::InProcess { .. }));
+            Ok((reloaded, previous))
+        })
+    }
+
+    #[cfg(not(feature = "python"))]
+    fn load_in_process(
+        &self,
+        _id: &str,
+        _extension_dir: &Path,
+        _manifest: &ExtensionManifest,
+        _permissions: Arc<PermissionSet>,
+    ) -> Result<(bool, Option<ExtensionRuntime>), String> {
+        Err(python_exec::PYTHON_UNAVAILABLE.to_string())
+    }
+
+    /**
+     * 別プロセスのホストに読み込む（読み込みに失敗した場合は以前の状態を使い続ける）
+     */
+    fn load_out_of_process(
+        &self,
+        id: &str,
+        extension_dir: &Path,
+        manifest: &ExtensionManifest,
+        permissions: Arc<PermissionSet>,
+    ) -> Result<(bool, Option<ExtensionRuntime>), String> {
+        let host_key = extension_process::host_key(manifest.host, id);
+        let processes = self.processes.clone();
+        let main_file = manifest.main_file.clone();
+        let loaded_permissions = permissions.clone();
+        without_gil(|| processes.load(&host_key, id, extension_dir, &main_file, loaded_permissions))?;
+
+        let mut extensions = self.extensions.lock().map_err(|e| e.to_string())?;
+        let replaced = extensions.insert(
+            id.to_string(),
+            LoadedExtension {
+                runtime: ExtensionRuntime::Process {
+                    host_key: host_key.clone(),
+                },
+                permissions,
+                events: manifest.events.clone(),
+                commands: manifest.commands.clone(),
+            },
+        );
+        let reloaded = replaced.is_some();
+        let previous = replaced.map(|extension| extension.runtime).filter(
+            |runtime| !matches!(runtime, ExtensionRuntime::Process { host_key: previous } if *previous == host_key),
+        );
+        Ok((reloaded, previous))
+    }
+
+    /**
+     * WebAssemblyモジュールとして読み込む（読み込みに失敗した場合は以前の状態を使い続ける）
+     */
+    #[cfg(feature = "wasm")]
+    fn load_wasm(
+        &self,
+        id: &str,
+        extension_dir: &Path,
+        manifest: &ExtensionManifest,
+        permissions: Arc<PermissionSet>,
+    ) -> Result<(bool, Option<ExtensionRuntime>), String> {
+        let main_path = extension_dir.join(&manifest.main_file);
+        let extension = without_gil(|| WasmExtension::load(id, &main_path))?;
+
+        let mut extensions = self.extensions.lock().map_err(|e| e.to_string())?;
+        let replaced = extensions.insert(
+            id.to_string(),
+            LoadedExtension {
+                runtime: ExtensionRuntime::Wasm(Arc::new(Mutex::new(extension))),
+                permissions,
+                events: manifest.events.clone(),
+                commands: manifest.commands.clone(),
+            },
+        );
+        let reloaded = replaced.is_some();
+        let previous = replaced
+            .map(|extension| extension.runtime)
+            .filter(|runtime| !matches!(runtime, ExtensionRuntime::Wasm(_)));
+        Ok((reloaded, previous))
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    fn load_wasm(
+        &self,
+        _id: &str,
+        _extension_dir: &Path,
+        _manifest: &ExtensionManifest,
+        _permissions: Arc<PermissionSet>,
+    ) -> Result<(bool, Option<ExtensionRuntime>), String> {
+        Err(crate::extension_wasm::WASM_UNAVAILABLE.to_string())
+    }
+
+    /**
+     * 取り除いた拡張機能の実行先を片付ける
+     */
+    fn release(&self, id: &str, runtime: ExtensionRuntime) {
+        match runtime {
+            #[cfg(feature = "python")]
+            ExtensionRuntime::InProcess { module_name, .. } => {
+                Python::with_gil(|py| remove_from_sys_modules(py, &module_name));
+            }
+            ExtensionRuntime::Process { host_key } => {
+                let processes = self.processes.clone();
+                without_gil(|| processes.unload(&host_key, id));
+            }
+            // インスタンスは参照がなくなった時点で破棄される
+            #[cfg(feature = "wasm")]
+            ExtensionRuntime::Wasm(_) => {}
+        }
+    }
+
+    /**
+     * 拡張機能をアンロードする
+     * on_deactivateを購読している場合は取り除く前に通知する
+     */
+    pub fn unload(&self, id: &str) -> bool {
+        self.notify(id, extension_events::ON_DEACTIVATE, &serde_json::json!({}));
+        let removed = match self.extensions.lock() {
+            Ok(mut extensions) => extensions.remove(id),
+            Err(_) => None,
+        };
+
+        match removed {
+            Some(extension) => {
+                self.release(id, extension.runtime);
+                extension_config::deactivate(id);
+                println!("🧩 Extension unloaded: {}", id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /**
+     * 拡張機能のon_event(event_type, event_data)を呼び出す
+     * 戻り値はon_eventが返したJSON文字列をパースしたもの（空文字ならNone）
+     * editorはvinsert.get_text()/get_selection()が返すエディタの状態
+     */
+    pub fn dispatch(
+        &self,
+        id: &str,
+        event_type: &str,
+        payload: &serde_json::Value,
+        editor: EditorSnapshot,
+    ) -> Result<Option<serde_json::Value>, String> {
+        let event_data = serde_json::to_string(payload)
+            .map_err(|e| format!("Failed to serialize event data: {}", e))?;
+        self.call(id, "on_event", &[event_type, &event_data], editor)
+    }
+
+    /**
+     * 拡張機能のon_command(command_id)を呼び出す（メニューコマンドの実行）
+     * 戻り値の扱いはdispatchと同じ
+     */
+    pub fn run_command(
+        &self,
+        id: &str,
+        command_id: &str,
+        editor: EditorSnapshot,
+    ) -> Result<Option<serde_json::Value>, String> {
+        let declared = self
+            .extensions
+            .lock()
+            .map_err(|e| e.to_string())?
+            .get(id)
+            .map(|extension| extension.commands.iter().any(|command| command.id == command_id));
+        match declared {
+            Some(true) => self.call(id, "on_command", &[command_id], editor),
+            Some(false) => Err(format!("Extension '{}' has no command '{}'", id, command_id)),
+            None => Err(format!("Extension '{}' is not loaded", id)),
+        }
+    }
+
+    /**
+     * 拡張機能の関数を文字列引数で呼び出し、戻り値のJSON文字列をパースする
+     * 処理時間はextension_statsに記録し、イベント処理が続けて遅い場合は警告・無効化する
+     */
+    fn call(
+        &self,
+        id: &str,
+        function: &str,
+        args: &[&str],
+        editor: EditorSnapshot,
+    ) -> Result<Option<serde_json::Value>, String> {
+        let target = self.call_target(id)?;
+        // vinsert.get_text・get_selectionと同じく、read_documentがなければ内容を渡さない
+        let editor = if self.allows(id, &Permission::ReadDocument) {
+            editor
+        } else {
+            EditorSnapshot::default()
+        };
+        let started = Instant::now();
+        let result = self.invoke(target, id, function, args, editor);
+        let elapsed = started.elapsed();
+
+        // イベントはイベント名、コマンドは"command:<id>"ごとに記録する
+        let (event, budgeted) = match (function, args.first()) {
+            ("on_event", Some(event_type)) => (event_type.to_string(), true),
+            ("on_command", Some(command_id)) => (format!("command:{}", command_id), false),
+            _ => (function.to_string(), false),
+        };
+        let verdict = self.stats.record(id, &event, elapsed, result.is_ok(), budgeted);
+        if verdict != SlowVerdict::Ok {
+            self.report_slow(id, &event, elapsed.as_secs_f64() * 1000.0, verdict);
+        }
+        result
+    }
+
+    /**
+     * 呼び出し先の実行環境を取得する
+     */
+    fn call_target(&self, id: &str) -> Result<CallTarget, String> {
+        match self.extensions.lock().map_err(|e| e.to_string())?.get(id) {
+            Some(extension) => match &extension.runtime {
+                ExtensionRuntime::Process { host_key } => Ok(CallTarget::Process(host_key.clone())),
+                #[cfg(feature = "python")]
+                ExtensionRuntime::InProcess { .. } => Ok(CallTarget::InProcess),
+                #[cfg(feature = "wasm")]
+                ExtensionRuntime::Wasm(extension) => Ok(CallTarget::Wasm(extension.clone())),
+            },
+            None => Err(format!("Extension '{}' is not loaded", id)),
+        }
+    }
+
+    fn invoke(
+        &self,
+        target: CallTarget,
+        id: &str,
+        function: &str,
+        args: &[&str],
+        editor: EditorSnapshot,
+    ) -> Result<Option<serde_json::Value>, String> {
+        let response = match target {
+            CallTarget::Process(host_key) => {
+                let processes = self.processes.clone();
+                without_gil(|| processes.call(&host_key, id, function, args, &editor))?.unwrap_or_default()
+            }
+            CallTarget::InProcess => self.call_in_process(id, function, args, editor)?,
+            #[cfg(feature = "wasm")]
+            CallTarget::Wasm(extension) => without_gil(|| {
+                extension
+                    .lock()
+                    .map_err(|e| e.to_string())
+                    .and_then(|mut extension| extension.call(function, args))
+            })?,
+        };
+
+        let response = response.trim();
+        if response.is_empty() {
+            return Ok(None);
+        }
+
+        let response: serde_json::Value = serde_json::from_str(response)
+            .map_err(|e| format!("Extension '{}' returned invalid JSON: {}", id, e))?;
+
+        // 戻り値の操作もvinsert APIと同じ権限で確認してからWebViewへ渡す
+        let permissions = match self.extensions.lock().map_err(|e| e.to_string())?.get(id) {
+            Some(extension) => extension.permissions.clone(),
+            None => return Err(format!("Extension '{}' is not loaded", id)),
+        };
+        extension_permissions::check_action(&permissions, &response)?;
+        Ok(Some(response))
+    }
+
+    /**
+     * 処理が遅い拡張機能をWebViewへ通知する
+     * Disableの場合はアンロードし、次に明示的に読み込むまでイベントで読み込み直さない
+     */
+    fn report_slow(&self, id: &str, event: &str, elapsed_ms: f64, verdict: SlowVerdict) {
+        let disabled = verdict == SlowVerdict::Disable;
+        if disabled {
+            println!("🐢 Extension {} is too slow in {} ({:.0} ms), disabling", id, event, elapsed_ms);
+            self.stats.mark_disabled(id);
+        } else {
+            println!("🐢 Extension {} is slow in {} ({:.0} ms)", id, event, elapsed_ms);
+        }
+
+        let payload = SlowExtensionEvent {
+            extension_id: id.to_string(),
+            event: event.to_string(),
+            elapsed_ms,
+            budget_ms: extension_stats::LATENCY_BUDGET_MS,
+            disabled,
+        };
+        if let Err(e) = vinsert_api::emit_event(extension_stats::SLOW_EXTENSION_EVENT, payload) {
+            println!("⚠️ {}", e);
+        }
+
+        if disabled {
+            self.unload(id);
+            // dispatchはジョブ内（GIL保持中）で呼ばれるため、GILを解放してから作り直しを依頼する
+            if let Some(app) = vinsert_api::try_app_handle() {
+                without_gil(|| extension_menu::refresh(app));
+            }
+        }
+    }
+
+    /**
+     * プロセス内の拡張機能モジュールの関数を呼び出す（Noneは空文字列）
+     */
+    #[cfg(feature = "python")]
+    fn call_in_process(
+        &self,
+        id: &str,
+        function: &str,
+        args: &[&str],
+        editor: EditorSnapshot,
+    ) -> Result<String, String> {
+        Python::with_gil(|py| -> Result<String, String> {
+            // ロックはモジュール参照の取得中のみ保持する
+            let (module, permissions) = {
+                let extensions = self.extensions.lock().map_err(|e| e.to_string())?;
+                match extensions.get(id) {
+                    Some(LoadedExtension {
+                        runtime: ExtensionRuntime::InProcess { module, .. },
+                        permissions,
+                        ..
+                    }) => (module.clone_ref(py), permissions.clone()),
+                    _ => return Err(format!("Extension '{}' is not loaded", id)),
+                }
+            };
+
+            let module = module.bind(py);
+            if !module.hasattr(function).unwrap_or(false) {
+                return Err(format!("{} function not found in extension '{}'", function, id));
+            }
+
+            let result = vinsert_api::with_context(permissions, editor, || {
+                module.call_method1(function, PyTuple::new_bound(py, args))
+            })
+            .map_err(|e| format!("Extension '{}' error in {}: {}", id, function, e))?;
+
+            if result.is_none() {
+                return Ok(String::new());
+            }
+            result
+                .extract::<String>()
+                .map_err(|e| format!("Extension '{}' returned a non-string value: {}", id, e))
+        })
+    }
+
+    #[cfg(not(feature = "python"))]
+    fn call_in_process(
+        &self,
+        _id: &str,
+        _function: &str,
+        _args: &[&str],
+        _editor: EditorSnapshot,
+    ) -> Result<String, String> {
+        Err(python_exec::PYTHON_UNAVAILABLE.to_string())
+    }
+
+    /**
+     * 購読している拡張機能にだけイベントを送り、戻り値の操作をWebViewへ転送する
+     * 失敗しても呼び出し元の処理は止めない（ログのみ）
+     */
+    fn notify(&self, id: &str, event_type: &str, payload: &serde_json::Value) {
+        if !self.is_subscribed(id, event_type) {
+            return;
+        }
+        match self.dispatch(id, event_type, payload, EditorSnapshot::default()) {
+            Ok(Some(response)) => extension_events::forward_response(id, response),
+            Ok(None) => {}
+            Err(e) => println!("⚠️ {}: {}", event_type, e),
+        }
+    }
+
+    /**
+     * 拡張機能がイベントを購読しているかどうか
+     */
+    pub fn is_subscribed(&self, id: &str, event_type: &str) -> bool {
+        self.extensions
+            .lock()
+            .map(|extensions| {
+                extensions
+                    .get(id)
+                    .is_some_and(|extension| extension.events.iter().any(|event| event == event_type))
+            })
+            .unwrap_or(false)
+    }
+
+    /**
+     * 拡張機能に権限が許可されているかどうか
+     */
+    pub fn allows(&self, id: &str, permission: &Permission) -> bool {
+        self.extensions
+            .lock()
+            .map(|extensions| {
+                extensions
+                    .get(id)
+                    .is_some_and(|extension| extension.permissions.allows(permission))
+            })
+            .unwrap_or(false)
+    }
+
+    /**
+     * イベントを購読している読み込み済み拡張機能のID（ID順）
+     */
+    pub fn subscribers(&self, event_type: &str) -> Vec<String> {
+        let mut ids: Vec<String> = self
+            .extensions
+            .lock()
+            .map(|extensions| {
+                extensions
+                    .iter()
+                    .filter(|(_, extension)| extension.events.iter().any(|event| event == event_type))
+                    .map(|(id, _)| id.clone())
+                    .collect()
+            })
+            .unwrap_or_default();
+        ids.sort();
+        ids
+    }
+
+    /**
+     * 読み込み済み拡張機能のメニューコマンド（拡張機能ID順）
+     */
+    pub fn menu_commands(&self) -> Vec<(String, ExtensionCommand)> {
+        let mut commands: Vec<(String, ExtensionCommand)> = self
+            .extensions
+            .lock()
+            .map(|extensions| {
+                extensions
+                    .iter()
+                    .flat_map(|(id, extension)| {
+                        extension.commands.iter().map(move |command| (id.clone(), command.clone()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        commands.sort_by(|a, b| a.0.cmp(&b.0));
+        commands
+    }
+
+    pub fn is_loaded(&self, id: &str) -> bool {
+        self.extensions
+            .lock()
+            .map(|extensions| extensions.contains_key(id))
+            .unwrap_or(false)
+    }
+}
+
+/**
+ * 拡張機能ディレクトリ（appDataDir/vinsert/extension）を取得
+ * JavaScript側のgetExtensionsDirectory()と同じ場所を指す
+ */
+pub fn extensions_directory(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    Ok(app_data.join("vinsert").join("extension"))
+}
+
+/**
+ * setting.jsonで宣言された権限をユーザーに確認する
+ * 確認ダイアログを表示することがあるため、メインスレッド以外から呼ぶこと
+ */
+pub fn grant_permissions_blocking(
+    app: &tauri::AppHandle,
+    id: &str,
+    extension_dir: &Path,
+) -> Result<PermissionSet, String> {
+    let manifest = extension_manifest::load_manifest(id, extension_dir)?;
+    extension_permissions::ensure_granted(
+        app,
+        &manifest.id,
+        manifest.display_name(),
+        extension_dir,
+        &manifest.permissions,
+    )
+}
+
+/**
+ * grant_permissions_blockingを別スレッドで実行する（コマンド用）
+ */
+async fn grant_permissions(
+    app: &tauri::AppHandle,
+    id: &str,
+    extension_dir: &Path,
+) -> Result<PermissionSet, String> {
+    let app = app.clone();
+    let id = id.to_string();
+    let extension_dir = extension_dir.to_path_buf();
+
+    tauri::async_runtime::spawn_blocking(move || grant_permissions_blocking(&app, &id, &extension_dir))
+        .await
+        .map_err(|e| format!("Permission prompt failed: {}", e))?
+}
+
+/**
+ * callで呼び出す実行先（拡張機能一覧のロックを外してから呼び出すため）
+ */
+enum CallTarget {
+    #[cfg_attr(not(feature = "python"), allow(dead_code))]
+    InProcess,
+    Process(String),
+    #[cfg(feature = "wasm")]
+    Wasm(Arc<Mutex<WasmExtension>>),
+}
+
+/**
+ * 別プロセスのホストの応答待ちやWebAssemblyの実行中はGILを解放し、他の拡張機能やPythonの実行を止めない
+ */
+#[cfg(feature = "python")]
+fn without_gil<T: Send>(f: impl FnOnce() -> T + Send) -> T {
+    Python::with_gil(|py| py.allow_threads(f))
+}
+
+#[cfg(not(feature = "python"))]
+fn without_gil<T>(f: impl FnOnce() -> T) -> T {
+    f()
+}
+
+/**
+ * 拡張機能IDから衝突しないモジュール名を作る
+ * ASCIIの英数字以外（_を含む）はUTF-8のバイトごとに "_<16進数2桁>" にする
+ * （html-supportとhtml_supportが同じ名前にならないように。extension_process_host.pyの_module_name_forと同じ規則）
+ */
+#[cfg(feature = "python")]
+fn module_name_for(id: &str) -> String {
+    let encoded: String = id
+        .bytes()
+        .map(|byte| {
+            if byte.is_ascii_alphanumeric() {
+                (byte as char).to_string()
+            } else {
+                format!("_{:02x}", byte)
+            }
+        })
+        .collect();
+    format!("vinsert_ext_{}", encoded)
+}
+
+#[cfg(feature = "python")]
+fn restore_sys_module(py: Python<'_>, module_name: &str, module: &Bound<'_, PyModule>) {
+    if let Ok(modules) = py.import_bound("sys").and_then(|sys| sys.getattr("modules")) {
+        let _ = modules.set_item(module_name, module);
+    }
+}
+
+#[cfg(feature = "python")]
+fn remove_from_sys_modules(py: Python<'_>, module_name: &str) {
+    if let Ok(modules) = py.import_bound("sys").and_then(|sys| sys.getattr("modules")) {
+        let _ = modules.del_item(module_name);
+    }
+}
+
+/**
+ * 拡張機能を実行できるランタイムがあるか確認する
+ */
+pub fn ensure_available() -> Result<(), String> {
+    if extension_runtimes().is_empty() {
+        Err(python_exec::PYTHON_UNAVAILABLE.to_string())
+    } else {
+        Ok(())
+    }
+}
+
+// =====================================================
+// Tauriコマンド
+// =====================================================
+
+/**
+ * このビルドで使える拡張機能のランタイム（python / wasm）
+ */
+#[tauri::command]
+pub fn extension_runtimes() -> Vec<&'static str> {
+    let mut runtimes = Vec::new();
+    if cfg!(feature = "python") {
+        runtimes.push("python");
+    }
+    if cfg!(feature = "wasm") {
+        runtimes.push("wasm");
+    }
+    runtimes
+}
+
+/**
+ * 拡張機能を読み込む
+ */
+#[tauri::command]
+pub async fn load_extension(
+    app: tauri::AppHandle,
+    host: tauri::State<'_, ExtensionHost>,
+    jobs: tauri::State<'_, PythonJobs>,
+    id: String,
+) -> Result<(), PythonExecError> {
+    ensure_available()?;
+    extension_manifest::validate_extension_id(&id)?;
+    let extension_dir = extensions_directory(&app)?.join(&id);
+    let permissions = grant_permissions(&app, &id, &extension_dir).await?;
+    let host = host.inner().clone();
+    // 処理が遅いため無効化した拡張機能も、明示的に読み込む場合は受け付ける
+    host.stats().clear_disabled(&id);
+    jobs.run(None, Some(EXTENSION_TIMEOUT_MS), move |_py| {
+        host.load(&id, extension_dir, permissions)
+    })
+    .await?;
+    extension_menu::refresh(&app);
+    Ok(())
+}
+
+/**
+ * 拡張機能をアンロードする
+ * on_deactivateが制限時間を過ぎないよう拡張機能と同じ実行キューで行う
+ */
+#[tauri::command]
+pub async fn unload_extension(
+    app: tauri::AppHandle,
+    host: tauri::State<'_, ExtensionHost>,
+    jobs: tauri::State<'_, PythonJobs>,
+    id: String,
+) -> Result<bool, PythonExecError> {
+    extension_manifest::validate_extension_id(&id)?;
+    let host = host.inner().clone();
+    let unloaded = jobs
+        .run(None, Some(EXTENSION_TIMEOUT_MS), move |_py| Ok(host.unload(&id)))
+        .await?;
+    if unloaded {
+        extension_menu::refresh(&app);
+    }
+    Ok(unloaded)
+}
+
+/**
+ * 読み込み済み拡張機能にイベントを送る
+ * on_eventが制限時間を過ぎた場合は中断してTimeoutを返す
+ */
+#[tauri::command]
+pub async fn dispatch_extension_event(
+    app: tauri::AppHandle,
+    host: tauri::State<'_, ExtensionHost>,
+    jobs: tauri::State<'_, PythonJobs>,
+    id: String,
+    event_type: String,
+    payload: serde_json::Value,
+    editor: Option<EditorSnapshot>,
+) -> Result<Option<serde_json::Value>, PythonExecError> {
+    ensure_available()?;
+    extension_manifest::validate_extension_id(&id)?;
+    let extension_dir = extensions_directory(&app)?.join(&id);
+    let host = host.inner().clone();
+
+    // 未読み込みの場合は初回のみ権限を確認して読み込む
+    // 処理が遅いため無効化した拡張機能はload_extensionで読み込むまで読み込み直さない
+    let permissions = if host.is_loaded(&id) {
+        None
+    } else if host.stats().is_disabled(&id) {
+        return Err(format!("Extension '{}' was disabled because its event handlers were too slow", id).into());
+    } else {
+        Some(grant_permissions(&app, &id, &extension_dir).await?)
+    };
+
+    let needs_load = permissions.is_some();
+    let response = jobs.run(None, Some(EXTENSION_TIMEOUT_MS), move |_py| {
+        if let Some(permissions) = permissions {
+            host.load(&id, extension_dir, permissions)?;
+        }
+        host.dispatch(&id, &event_type, &payload, editor.unwrap_or_default())
+    })
+    .await;
+    if needs_load {
+        extension_menu::refresh(&app);
+    }
+    response
+}
+
+/**
+ * 拡張機能のメニューコマンドを実行する（ネイティブメニューからWebView経由で呼ばれる）
+ * 戻り値はdispatch_extension_eventと同じくWebView側で処理する
+ */
+#[tauri::command]
+pub async fn run_extension_command(
+    host: tauri::State<'_, ExtensionHost>,
+    jobs: tauri::State<'_, PythonJobs>,
+    extension_id: String,
+    command_id: String,
+    editor: Option<EditorSnapshot>,
+) -> Result<Option<serde_json::Value>, PythonExecError> {
+    extension_manifest::validate_extension_id(&extension_id)?;
+    let host = host.inner().clone();
+    jobs.run(None, Some(EXTENSION_TIMEOUT_MS), move |_py| {
+        host.run_command(&extension_id, &command_id, editor.unwrap_or_default())
+    })
+    .await
+}
@@ -0,0 +1,344 @@
+/*
+ * =====================================================
+ * Vinsert Editor - 拡張機能のインストール・アンインストール
+ * .zip / .vsix 形式のアーカイブを拡張機能ディレクトリへ展開する
+ * 展開は一時ディレクトリで行い、失敗時は元の状態に戻す
+ * =====================================================
+ */
+
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::extension_config;
+use crate::extension_host::{self, ExtensionHost, EXTENSION_TIMEOUT_MS};
+use crate::extension_manifest::{self, validate_extension_id, ExtensionManifest, MANIFEST_FILE};
+use crate::extension_menu;
+use crate::extension_permissions;
+use crate::extension_signing::{self, SignatureStatus};
+use crate::python_exec::PythonJobs;
+
+/// シンボリックリンクを示すUnixのファイル種別
+const UNIX_FILE_TYPE_MASK: u32 = 0o170000;
+const UNIX_SYMLINK: u32 = 0o120000;
+
+/**
+ * インストール結果
+ * 新しいバージョンが既にインストールされている場合はNeedsConfirmationを返し、
+ * 呼び出し側がユーザーに確認してからallow_downgradeを付けて再実行する
+ */
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum InstallOutcome {
+    Installed {
+        id: String,
+        version: String,
+        previous_version: Option<String>,
+        signature: SignatureStatus,
+    },
+    NeedsConfirmation {
+        id: String,
+        version: String,
+        installed_version: String,
+    },
+}
+
+/**
+ * 作業用ディレクトリ（Dropで削除されるため、途中で失敗しても残らない）
+ */
+pub struct WorkDir(PathBuf);
+
+impl WorkDir {
+    /**
+     * 拡張機能ディレクトリ内に作業用ディレクトリを作る
+     * 同じファイルシステム上で rename できるよう拡張機能ディレクトリ内に作る
+     * （先頭が . のディレクトリはlist_extensionsの走査対象外）
+     */
+    pub fn create(extensions_dir: &Path, purpose: &str) -> Result<WorkDir, String> {
+        let nonce = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        let path = extensions_dir.join(format!(".{}-{}-{}", purpose, std::process::id(), nonce));
+        fs::create_dir_all(&path).map_err(|e| format!("Failed to create '{}': {}", path.display(), e))?;
+        Ok(WorkDir(path))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for WorkDir {
+    fn drop(&mut self) {
+        if self.0.exists() {
+            if let Err(e) = fs::remove_dir_all(&self.0) {
+                println!("⚠️ Failed to clean up '{}': {}", self.0.display(), e);
+            }
+        }
+    }
+}
+
+/**
+ * アーカイブを展開する
+ * パストラバーサル（../ や絶対パス）やシンボリックリンクを含む場合は全体を拒否する
+ */
+fn extract_archive(archive_path: &Path, destination: &Path) -> Result<(), String> {
+    let file = fs::File::open(archive_path)
+        .map_err(|e| format!("Failed to open '{}': {}", archive_path.display(), e))?;
+    let mut archive = zip::ZipArchive::new(file)
+        .map_err(|e| format!("Failed to read archive '{}': {}", archive_path.display(), e))?;
+
+    for index in 0..archive.len() {
+        let mut entry = archive
+            .by_index(index)
+            .map_err(|e| format!("Failed to read archive entry: {}", e))?;
+
+        let relative = match entry.enclosed_name() {
+            Some(path) => path,
+            None => return Err(format!("Archive entry '{}' escapes the extension directory", entry.name())),
+        };
+        if entry
+            .unix_mode()
+            .is_some_and(|mode| mode & UNIX_FILE_TYPE_MASK == UNIX_SYMLINK)
+        {
+            return Err(format!("Archive entry '{}' is a symbolic link", entry.name()));
+        }
+
+        let target = destination.join(&relative);
+        if entry.is_dir() {
+            fs::create_dir_all(&target)
+                .map_err(|e| format!("Failed to create '{}': {}", target.display(), e))?;
+            continue;
+        }
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create '{}': {}", parent.display(), e))?;
+        }
+        let mut output = fs::File::create(&target)
+            .map_err(|e| format!("Failed to create '{}': {}", target.display(), e))?;
+        io::copy(&mut entry, &mut output)
+            .map_err(|e| format!("Failed to extract '{}': {}", relative.display(), e))?;
+    }
+
+    Ok(())
+}
+
+/**
+ * 展開したアーカイブ内の拡張機能のルートを探す
+ * setting.jsonが直下にない場合は、唯一のサブディレクトリ（extension/ など）を見る
+ */
+fn find_extension_root(extracted: &Path) -> Result<PathBuf, String> {
+    if extracted.join(MANIFEST_FILE).is_file() {
+        return Ok(extracted.to_path_buf());
+    }
+
+    let subdirectories: Vec<PathBuf> = fs::read_dir(extracted)
+        .map_err(|e| format!("Failed to read '{}': {}", extracted.display(), e))?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+
+    match subdirectories.as_slice() {
+        [only] if only.join(MANIFEST_FILE).is_file() => Ok(only.clone()),
+        _ => Err(format!("{} not found in archive", MANIFEST_FILE)),
+    }
+}
+
+fn read_manifest_id(extension_root: &Path) -> Result<String, String> {
+    let manifest_path = extension_root.join(MANIFEST_FILE);
+    let content = fs::read_to_string(&manifest_path)
+        .map_err(|e| format!("Failed to read {}: {}", MANIFEST_FILE, e))?;
+    let value: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Invalid JSON in {}: {}", MANIFEST_FILE, e))?;
+    value
+        .get("id")
+        .and_then(|id| id.as_str())
+        .map(|id| id.to_string())
+        .ok_or_else(|| format!("Missing required field 'id' in {}", MANIFEST_FILE))
+}
+
+/**
+ * インストール済みの拡張機能のバージョン（読めない場合はNone）
+ */
+pub fn installed_version(extension_dir: &Path) -> Option<semver::Version> {
+    let (manifest, _) = extension_manifest::inspect_extension(extension_dir);
+    manifest.and_then(|manifest| semver::Version::parse(manifest.version.trim()).ok())
+}
+
+/**
+ * アーカイブから拡張機能をインストールする
+ * expectedを指定した場合（レジストリからのインストール）は、アーカイブの拡張機能IDとバージョンが一致することを確認する
+ */
+pub fn install_from_archive(
+    extensions_dir: &Path,
+    archive_path: &Path,
+    allow_downgrade: bool,
+    expected: Option<(&str, &semver::Version)>,
+) -> Result<InstallOutcome, String> {
+    fs::create_dir_all(extensions_dir)
+        .map_err(|e| format!("Failed to create '{}': {}", extensions_dir.display(), e))?;
+
+    let work = WorkDir::create(extensions_dir, "install")?;
+    let extracted = work.0.join("archive");
+    fs::create_dir_all(&extracted)
+        .map_err(|e| format!("Failed to create '{}': {}", extracted.display(), e))?;
+
+    extract_archive(archive_path, &extracted)?;
+
+    let root = find_extension_root(&extracted)?;
+    let id = read_manifest_id(&root)?;
+    validate_extension_id(&id)?;
+
+    // ディレクトリ名をIDに合わせてから通常の検証を行う
+    let staged = work.0.join("staged").join(&id);
+    fs::create_dir_all(work.0.join("staged"))
+        .and_then(|_| fs::rename(&root, &staged))
+        .map_err(|e| format!("Failed to stage extension: {}", e))?;
+    let manifest: ExtensionManifest = extension_manifest::load_manifest(&id, &staged)?;
+    let new_version = semver::Version::parse(manifest.version.trim())
+        .map_err(|e| format!("Invalid version '{}': {}", manifest.version, e))?;
+    let signature = extension_signing::check(&id, &staged)?;
+
+    if let Some((expected_id, expected_version)) = expected {
+        if id != expected_id || &new_version != expected_version {
+            return Err(format!(
+                "Archive contains {} {}, but {} {} was expected",
+                id, new_version, expected_id, expected_version
+            ));
+        }
+    }
+
+    let target = extensions_dir.join(&id);
+    let previous = if target.exists() { installed_version(&target) } else { None };
+
+    if let Some(previous) = &previous {
+        if previous > &new_version && !allow_downgrade {
+            println!("⚠️ Extension {} {} is newer than archive {}", id, previous, new_version);
+            return Ok(InstallOutcome::NeedsConfirmation {
+                id,
+                version: new_version.to_string(),
+                installed_version: previous.to_string(),
+            });
+        }
+    }
+
+    // 既存の拡張機能は作業用ディレクトリへ退避し、失敗したら戻す
+    let backup = work.0.join("previous");
+    let had_existing = target.exists();
+    if had_existing {
+        fs::rename(&target, &backup)
+            .map_err(|e| format!("Failed to move existing extension '{}': {}", id, e))?;
+    }
+
+    if let Err(e) = fs::rename(&staged, &target) {
+        if had_existing {
+            if let Err(restore_error) = fs::rename(&backup, &target) {
+                println!("❌ Failed to restore extension '{}': {}", id, restore_error);
+            }
+        }
+        return Err(format!("Failed to install extension '{}': {}", id, e));
+    }
+
+    println!("📦 Extension installed: {} {}", id, new_version);
+    Ok(InstallOutcome::Installed {
+        id,
+        version: new_version.to_string(),
+        previous_version: previous.map(|version| version.to_string()),
+        signature,
+    })
+}
+
+/**
+ * 読み込み済みの拡張機能をアンロードする（on_deactivateには制限時間を設ける）
+ */
+pub async fn unload(host: &ExtensionHost, jobs: &PythonJobs, id: &str) {
+    let host = host.clone();
+    let id = id.to_string();
+    if let Err(e) = jobs.run(None, Some(EXTENSION_TIMEOUT_MS), move |_py| Ok(host.unload(&id))).await {
+        println!("⚠️ Failed to unload extension: {}", e);
+    }
+}
+
+// =====================================================
+// Tauriコマンド
+// =====================================================
+
+/**
+ * .zip / .vsix アーカイブから拡張機能をインストールする
+ * 読み込み済みの場合はアンロードし、次回使用時に新しいコードを読み込む
+ */
+#[tauri::command]
+pub async fn install_extension_from_archive(
+    app: tauri::AppHandle,
+    host: tauri::State<'_, ExtensionHost>,
+    jobs: tauri::State<'_, PythonJobs>,
+    path: String,
+    allow_downgrade: Option<bool>,
+) -> Result<InstallOutcome, String> {
+    let extensions_dir = extension_host::extensions_directory(&app)?;
+    let archive_path = PathBuf::from(&path);
+
+    let outcome = tauri::async_runtime::spawn_blocking(move || {
+        install_from_archive(&extensions_dir, &archive_path, allow_downgrade.unwrap_or(false), None)
+    })
+    .await
+    .map_err(|e| format!("Install task failed: {}", e))??;
+
+    if let InstallOutcome::Installed { id, .. } = &outcome {
+        unload(&host, &jobs, id).await;
+        extension_menu::refresh(&app);
+    }
+    Ok(outcome)
+}
+
+/**
+ * 拡張機能をアンインストールする
+ */
+#[tauri::command]
+pub async fn uninstall_extension(
+    app: tauri::AppHandle,
+    host: tauri::State<'_, ExtensionHost>,
+    jobs: tauri::State<'_, PythonJobs>,
+    id: String,
+) -> Result<(), String> {
+    validate_extension_id(&id)?;
+    let extension_dir = extension_host::extensions_directory(&app)?.join(&id);
+    if !extension_dir.is_dir() {
+        return Err(format!("Extension '{}' is not installed", id));
+    }
+
+    unload(&host, &jobs, &id).await;
+    extension_menu::refresh(&app);
+    fs::remove_dir_all(&extension_dir)
+        .map_err(|e| format!("Failed to remove extension '{}': {}", id, e))?;
+    extension_permissions::forget_decisions(&app, &id)?;
+    extension_config::forget_config(&app, &id)?;
+
+    println!("🗑️ Extension uninstalled: {}", id);
+    Ok(())
+}
+
+/**
+ * 拡張機能の権限の許可・拒否の記録を削除する
+ * 読み込み済みの場合はアンロードし、次に読み込むときに改めて確認する
+ */
+#[tauri::command]
+pub async fn reset_extension_permissions(
+    app: tauri::AppHandle,
+    host: tauri::State<'_, ExtensionHost>,
+    jobs: tauri::State<'_, PythonJobs>,
+    id: String,
+) -> Result<(), String> {
+    validate_extension_id(&id)?;
+
+    unload(&host, &jobs, &id).await;
+    extension_menu::refresh(&app);
+    extension_permissions::forget_decisions(&app, &id)?;
+
+    println!("🔐 Extension permissions reset: {}", id);
+    Ok(())
+}
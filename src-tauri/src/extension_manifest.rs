@@ -0,0 +1,491 @@
+/*
+ * =====================================================
+ * Vinsert Editor - 拡張機能のマニフェスト（setting.json）
+ * 拡張機能ディレクトリの走査とsetting.jsonの検証を行う
+ * =====================================================
+ */
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+use crate::extension_config::{self, ConfigProperty};
+use crate::extension_events;
+use crate::extension_host;
+use crate::extension_permissions::Permission;
+use crate::extension_signing::{self, SignatureStatus};
+
+/// マニフェストのファイル名
+pub const MANIFEST_FILE: &str = "setting.json";
+
+/**
+ * setting.jsonの内容
+ * 未知の項目はextraに保持し、書き戻したときに失われないようにする
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtensionManifest {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default)]
+    pub author: String,
+    #[serde(default = "default_main_file")]
+    pub main_file: String,
+    #[serde(default)]
+    pub enabled: bool,
+    /// 拡張機能が必要とする権限（read_document, filesystem:<path> など）
+    #[serde(default)]
+    pub permissions: Vec<String>,
+    /// 購読するライフサイクルイベント（on_activate, before_save など）
+    #[serde(default)]
+    pub events: Vec<String>,
+    /// 機能拡張メニューに追加するコマンド
+    #[serde(default)]
+    pub commands: Vec<ExtensionCommand>,
+    /// ユーザーが変更できる設定項目（設定ダイアログに表示する）
+    #[serde(default)]
+    pub configuration: Vec<ConfigProperty>,
+    /// 拡張機能のランタイム（python / wasm）
+    #[serde(default)]
+    pub runtime: RuntimeKind,
+    /// 拡張機能を実行するホスト（in_process / process / shared、Pythonのみ）
+    #[serde(default)]
+    pub host: HostMode,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/**
+ * 拡張機能のランタイム
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuntimeKind {
+    /// main_fileのPythonモジュール（on_event関数）
+    #[default]
+    Python,
+    /// main_fileのWebAssemblyモジュール（extension_wasmのABI）
+    Wasm,
+}
+
+/**
+ * 拡張機能の実行方法
+ * 別プロセスのホストではC拡張モジュールがクラッシュしてもエディタは終了しない
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HostMode {
+    /// エディタのプロセス内（PyO3）で実行する
+    #[default]
+    InProcess,
+    /// 拡張機能ごとの子プロセスで実行する
+    Process,
+    /// sharedを指定した拡張機能が共有する1つの子プロセスで実行する
+    Shared,
+}
+
+/**
+ * 拡張機能が追加するメニューコマンド
+ * クリックされると拡張機能のon_command(id)が呼ばれる
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtensionCommand {
+    pub id: String,
+    pub title: LocalizedText,
+    /// ショートカット（例: "CmdOrCtrl+Shift+H"）
+    #[serde(default)]
+    pub accelerator: Option<String>,
+    /// 機能拡張メニュー内のサブメニュー名（省略時は機能拡張メニュー直下）
+    #[serde(default)]
+    pub menu: Option<LocalizedText>,
+}
+
+/**
+ * 文字列、または言語コードごとの文字列（{"ja": "...", "en": "..."}）
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum LocalizedText {
+    Text(String),
+    Localized(std::collections::BTreeMap<String, String>),
+}
+
+impl LocalizedText {
+    /**
+     * 指定した言語の文字列（無ければja、en、最初の項目の順に使う）
+     */
+    pub fn resolve(&self, language: &str) -> String {
+        match self {
+            LocalizedText::Text(text) => text.clone(),
+            LocalizedText::Localized(texts) => [language, "ja", "en"]
+                .iter()
+                .find_map(|code| texts.get(*code))
+                .or_else(|| texts.values().next())
+                .cloned()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+fn default_main_file() -> String {
+    "main.py".to_string()
+}
+
+impl ExtensionManifest {
+    /**
+     * 表示用の名前（nameが空の場合はid）
+     */
+    pub fn display_name(&self) -> &str {
+        if self.name.trim().is_empty() {
+            &self.id
+        } else {
+            &self.name
+        }
+    }
+}
+
+/**
+ * 検証で見つかった問題
+ * line/columnはJSONの構文エラーの場合のみ（1始まり）
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct ExtensionDiagnostic {
+    pub severity: DiagnosticSeverity,
+    pub message: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticSeverity {
+    Error,
+    Warning,
+}
+
+impl ExtensionDiagnostic {
+    pub(crate) fn error(message: impl Into<String>) -> Self {
+        ExtensionDiagnostic {
+            severity: DiagnosticSeverity::Error,
+            message: message.into(),
+            line: None,
+            column: None,
+        }
+    }
+
+    fn warning(message: impl Into<String>) -> Self {
+        ExtensionDiagnostic {
+            severity: DiagnosticSeverity::Warning,
+            message: message.into(),
+            line: None,
+            column: None,
+        }
+    }
+}
+
+/**
+ * list_extensionsが返す拡張機能ごとの結果
+ * manifestはsetting.jsonを読み込めた場合のみ設定される
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct ExtensionEntry {
+    pub directory: String,
+    pub path: String,
+    pub manifest: Option<ExtensionManifest>,
+    pub diagnostics: Vec<ExtensionDiagnostic>,
+    pub valid: bool,
+    pub signature: SignatureStatus,
+}
+
+/**
+ * 拡張機能ディレクトリのsetting.jsonを読み込んで検証する
+ */
+pub fn inspect_extension(extension_dir: &Path) -> (Option<ExtensionManifest>, Vec<ExtensionDiagnostic>) {
+    let mut diagnostics = Vec::new();
+    let manifest_path = extension_dir.join(MANIFEST_FILE);
+
+    let content = match std::fs::read_to_string(&manifest_path) {
+        Ok(content) => content,
+        Err(e) => {
+            diagnostics.push(ExtensionDiagnostic::error(format!("Failed to read {}: {}", MANIFEST_FILE, e)));
+            return (None, diagnostics);
+        }
+    };
+
+    // 構文エラーは行・列を付けて報告する
+    let value: serde_json::Value = match serde_json::from_str(&content) {
+        Ok(value) => value,
+        Err(e) => {
+            diagnostics.push(ExtensionDiagnostic {
+                severity: DiagnosticSeverity::Error,
+                message: format!("Invalid JSON in {}: {}", MANIFEST_FILE, e),
+                line: Some(e.line()),
+                column: Some(e.column()),
+            });
+            return (None, diagnostics);
+        }
+    };
+
+    let object = match value.as_object() {
+        Some(object) => object,
+        None => {
+            diagnostics.push(ExtensionDiagnostic::error(format!("{} must be a JSON object", MANIFEST_FILE)));
+            return (None, diagnostics);
+        }
+    };
+
+    for field in ["id", "name", "version"] {
+        match object.get(field) {
+            Some(serde_json::Value::String(s)) if !s.trim().is_empty() => {}
+            Some(serde_json::Value::String(_)) => {
+                diagnostics.push(ExtensionDiagnostic::error(format!("Required field '{}' is empty", field)));
+            }
+            Some(_) => {
+                diagnostics.push(ExtensionDiagnostic::error(format!("Field '{}' must be a string", field)));
+            }
+            None => {
+                diagnostics.push(ExtensionDiagnostic::error(format!("Missing required field '{}'", field)));
+            }
+        }
+    }
+    if !diagnostics.is_empty() {
+        return (None, diagnostics);
+    }
+
+    let manifest: ExtensionManifest = match serde_json::from_value(value) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            diagnostics.push(ExtensionDiagnostic::error(format!("Invalid field in {}: {}", MANIFEST_FILE, e)));
+            return (None, diagnostics);
+        }
+    };
+
+    if let Err(e) = semver::Version::parse(manifest.version.trim()) {
+        diagnostics.push(ExtensionDiagnostic::error(format!(
+            "version '{}' is not a semantic version (e.g. 1.0.0): {}",
+            manifest.version, e
+        )));
+    }
+
+    // 拡張機能はディレクトリ名=IDとして読み込まれる
+    let directory = extension_dir.file_name().map(|name| name.to_string_lossy().into_owned());
+    if directory.as_deref() != Some(manifest.id.as_str()) {
+        diagnostics.push(ExtensionDiagnostic::error(format!(
+            "id '{}' does not match directory name '{}'",
+            manifest.id,
+            directory.unwrap_or_default()
+        )));
+    }
+
+    diagnostics.extend(check_main_file(extension_dir, &manifest.main_file));
+
+    for permission in &manifest.permissions {
+        if let Err(e) = Permission::parse(permission) {
+            diagnostics.push(ExtensionDiagnostic::error(e));
+        }
+    }
+
+    for event in &manifest.events {
+        if !extension_events::is_lifecycle_event(event) {
+            diagnostics.push(ExtensionDiagnostic::error(format!("Unknown event '{}' in events", event)));
+        }
+    }
+
+    let mut command_ids = std::collections::HashSet::new();
+    for command in &manifest.commands {
+        if command.id.trim().is_empty() {
+            diagnostics.push(ExtensionDiagnostic::error("Command id must not be empty"));
+        } else if !command_ids.insert(command.id.as_str()) {
+            diagnostics.push(ExtensionDiagnostic::error(format!("Duplicate command id '{}'", command.id)));
+        }
+        if command.title.resolve("ja").trim().is_empty() {
+            diagnostics.push(ExtensionDiagnostic::error(format!("Command '{}' has no title", command.id)));
+        }
+    }
+
+    diagnostics.extend(
+        extension_config::check_schema(&manifest.configuration)
+            .into_iter()
+            .map(ExtensionDiagnostic::error),
+    );
+
+    if manifest.runtime == RuntimeKind::Wasm && manifest.host != HostMode::InProcess {
+        diagnostics.push(ExtensionDiagnostic::warning("host is ignored for wasm extensions"));
+    }
+
+    if manifest.summary.trim().is_empty() {
+        diagnostics.push(ExtensionDiagnostic::warning("summary is not set"));
+    }
+
+    (Some(manifest), diagnostics)
+}
+
+/**
+ * main_fileが拡張機能ディレクトリ内に存在するか確認する
+ */
+fn check_main_file(extension_dir: &Path, main_file: &str) -> Option<ExtensionDiagnostic> {
+    let relative = Path::new(main_file);
+    let escapes = relative.is_absolute()
+        || relative
+            .components()
+            .any(|component| matches!(component, std::path::Component::ParentDir));
+    if escapes {
+        return Some(ExtensionDiagnostic::error(format!(
+            "main_file '{}' must be a relative path inside the extension directory",
+            main_file
+        )));
+    }
+
+    if extension_dir.join(relative).is_file() {
+        None
+    } else {
+        Some(ExtensionDiagnostic::error(format!("main_file '{}' not found", main_file)))
+    }
+}
+
+/**
+ * 拡張機能ディレクトリ配下を走査してすべての拡張機能を検証する
+ */
+pub fn scan_extensions(extensions_dir: &Path) -> Result<Vec<ExtensionEntry>, String> {
+    if !extensions_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let read_dir = std::fs::read_dir(extensions_dir)
+        .map_err(|e| format!("Failed to read '{}': {}", extensions_dir.display(), e))?;
+
+    let mut directories: Vec<PathBuf> = read_dir
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        // インストール作業用などの隠しディレクトリは除外
+        .filter(|path| {
+            !path
+                .file_name()
+                .map(|name| name.to_string_lossy().starts_with('.'))
+                .unwrap_or(false)
+        })
+        .collect();
+    directories.sort();
+
+    let mut entries: Vec<ExtensionEntry> = directories
+        .into_iter()
+        .map(|path| {
+            let (manifest, diagnostics) = inspect_extension(&path);
+            let signature = extension_signing::status(&path);
+            ExtensionEntry {
+                directory: path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                path: path.to_string_lossy().into_owned(),
+                manifest,
+                diagnostics,
+                valid: false,
+                signature,
+            }
+        })
+        .collect();
+
+    // IDの重複はすべての該当拡張機能にエラーとして付ける
+    let mut id_counts: HashMap<String, Vec<String>> = HashMap::new();
+    for entry in &entries {
+        if let Some(manifest) = &entry.manifest {
+            id_counts
+                .entry(manifest.id.clone())
+                .or_default()
+                .push(entry.directory.clone());
+        }
+    }
+    for entry in &mut entries {
+        if let Some(manifest) = &entry.manifest {
+            let directories = &id_counts[&manifest.id];
+            if directories.len() > 1 {
+                entry.diagnostics.push(ExtensionDiagnostic::error(format!(
+                    "Duplicate id '{}' in: {}",
+                    manifest.id,
+                    directories.join(", ")
+                )));
+            }
+        }
+    }
+
+    for entry in &mut entries {
+        entry.valid = entry.manifest.is_some()
+            && !entry
+                .diagnostics
+                .iter()
+                .any(|diagnostic| diagnostic.severity == DiagnosticSeverity::Error);
+    }
+
+    Ok(entries)
+}
+
+/**
+ * 拡張機能IDがディレクトリ名として安全か確認する
+ * WebViewから受け取ったIDを拡張機能ディレクトリと結合する前に必ず呼ぶ
+ */
+pub fn validate_extension_id(id: &str) -> Result<(), String> {
+    let mut components = Path::new(id).components();
+    let is_plain_name = matches!(components.next(), Some(Component::Normal(_))) && components.next().is_none();
+    if id.is_empty() || id.starts_with('.') || !is_plain_name {
+        return Err(format!("Invalid extension id '{}'", id));
+    }
+    Ok(())
+}
+
+/**
+ * 拡張機能ホストが読み込む前にマニフェストを検証する
+ * エラーがある場合はすべてのメッセージをまとめて返す
+ */
+pub fn load_manifest(id: &str, extension_dir: &Path) -> Result<ExtensionManifest, String> {
+    let (manifest, diagnostics) = inspect_extension(extension_dir);
+
+    let errors: Vec<String> = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == DiagnosticSeverity::Error)
+        .map(|diagnostic| diagnostic.message.clone())
+        .collect();
+    if !errors.is_empty() {
+        return Err(format!("Invalid extension '{}': {}", id, errors.join("; ")));
+    }
+
+    match manifest {
+        Some(manifest) if manifest.id == id => Ok(manifest),
+        Some(manifest) => Err(format!(
+            "Extension id mismatch: requested '{}' but {} declares '{}'",
+            id, MANIFEST_FILE, manifest.id
+        )),
+        None => Err(format!("Invalid extension '{}'", id)),
+    }
+}
+
+// =====================================================
+// Tauriコマンド
+// =====================================================
+
+/**
+ * インストール済みの拡張機能を一覧表示する（検証結果付き）
+ */
+#[tauri::command]
+pub async fn list_extensions(app: tauri::AppHandle) -> Result<Vec<ExtensionEntry>, String> {
+    let extensions_dir = extension_host::extensions_directory(&app)?;
+    let entries = scan_extensions(&extensions_dir)?;
+
+    for entry in &entries {
+        for diagnostic in &entry.diagnostics {
+            if diagnostic.severity == DiagnosticSeverity::Error {
+                println!("⚠️ Extension '{}': {}", entry.directory, diagnostic.message);
+            }
+        }
+    }
+    println!(
+        "🧩 Found {} extension(s) ({} valid)",
+        entries.len(),
+        entries.iter().filter(|entry| entry.valid).count()
+    );
+
+    Ok(entries)
+}
@@ -0,0 +1,107 @@
+/*
+ * =====================================================
+ * Vinsert Editor - 拡張機能のメニューコマンド
+ * setting.jsonのcommandsで宣言されたコマンドをネイティブメニューの
+ * 機能拡張メニューに追加し、クリックを拡張機能のon_commandへ渡す
+ * =====================================================
+ */
+
+use tauri::menu::{MenuItem, PredefinedMenuItem, Submenu};
+use tauri::Wry;
+
+use crate::extension_host::ExtensionHost;
+
+/// 拡張機能コマンドのメニューID（extension_command/<拡張機能ID>/<コマンドID>）
+const COMMAND_MENU_PREFIX: &str = "extension_command/";
+
+/// ネイティブメニューの表示言語（他のメニュー項目と合わせて日本語）
+const MENU_LANGUAGE: &str = "ja";
+
+fn menu_id(extension_id: &str, command_id: &str) -> String {
+    format!("{}{}/{}", COMMAND_MENU_PREFIX, extension_id, command_id)
+}
+
+/**
+ * メニューIDから拡張機能IDとコマンドIDを取り出す
+ * 拡張機能IDはディレクトリ名のため / を含まない
+ */
+fn parse_menu_id(menu_id: &str) -> Option<(&str, &str)> {
+    menu_id
+        .strip_prefix(COMMAND_MENU_PREFIX)?
+        .split_once('/')
+        .filter(|(extension_id, command_id)| !extension_id.is_empty() && !command_id.is_empty())
+}
+
+/**
+ * 読み込み済み拡張機能のコマンドを機能拡張メニューの末尾に追加する
+ * menuを指定したコマンドは同名のサブメニューにまとめる
+ */
+pub fn append_commands(app: &tauri::AppHandle, host: &ExtensionHost, menu: &Submenu<Wry>) -> Result<(), tauri::Error> {
+    let commands = host.menu_commands();
+    if commands.is_empty() {
+        return Ok(());
+    }
+
+    menu.append(&PredefinedMenuItem::separator(app)?)?;
+
+    let mut groups: Vec<(String, Submenu<Wry>)> = Vec::new();
+    for (extension_id, command) in commands {
+        let item = MenuItem::with_id(
+            app,
+            menu_id(&extension_id, &command.id),
+            command.title.resolve(MENU_LANGUAGE),
+            true,
+            command.accelerator.as_deref(),
+        )?;
+
+        let group = match &command.menu {
+            Some(group) => group.resolve(MENU_LANGUAGE),
+            None => {
+                menu.append(&item)?;
+                continue;
+            }
+        };
+
+        match groups.iter().find(|(label, _)| *label == group) {
+            Some((_, submenu)) => submenu.append(&item)?,
+            None => {
+                let submenu = Submenu::with_items(app, &group, true, &[&item])?;
+                menu.append(&submenu)?;
+                groups.push((group, submenu));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/**
+ * 拡張機能の読み込み状態に合わせてネイティブメニューを作り直す
+ * 作り直しはメインスレッドで行い、完了は待たない（GILを保持したまま呼ばないこと）
+ */
+pub fn refresh(app: &tauri::AppHandle) {
+    let handle = app.clone();
+    let scheduled = app.run_on_main_thread(move || {
+        match crate::create_native_menu(&handle).and_then(|menu| handle.set_menu(menu)) {
+            Ok(_) => println!("🍎 Native menu refreshed"),
+            Err(e) => println!("⚠️ Failed to refresh native menu: {}", e),
+        }
+    });
+    if let Err(e) = scheduled {
+        println!("⚠️ Failed to schedule native menu refresh: {}", e);
+    }
+}
+
+/**
+ * 拡張機能コマンドのメニューIDであれば、WebView側で実行するスクリプトを返す
+ * エディタの状態を添えて実行するため、run_extension_commandはWebView側から呼び出す
+ */
+pub fn command_script(menu_id: &str) -> Option<String> {
+    let (extension_id, command_id) = parse_menu_id(menu_id)?;
+    let extension_id = serde_json::to_string(extension_id).ok()?;
+    let command_id = serde_json::to_string(command_id).ok()?;
+    Some(format!(
+        "try {{ if (window.runExtensionCommand) window.runExtensionCommand({}, {}); }} catch(e) {{ console.error('runExtensionCommand error:', e); }}",
+        extension_id, command_id
+    ))
+}
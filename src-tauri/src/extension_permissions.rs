@@ -0,0 +1,565 @@
+/*
+ * =====================================================
+ * Vinsert Editor - 拡張機能の権限管理
+ * setting.jsonのpermissionsで宣言された権限をユーザーに確認し、
+ * sys.addaudithookで許可されていない操作をブロックする
+ * =====================================================
+ */
+
+#[cfg(feature = "python")]
+use pyo3::exceptions::PyPermissionError;
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3::types::PyTuple;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Component, Path, PathBuf};
+#[cfg(feature = "python")]
+use std::sync::{Arc, Once};
+use std::sync::{Mutex, OnceLock};
+use tauri::Manager;
+
+#[cfg(feature = "python")]
+use crate::vinsert_api::{self, Caller};
+
+/**
+ * 拡張機能が要求できる権限
+ */
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Permission {
+    ReadDocument,
+    WriteDocument,
+    Clipboard,
+    Subprocess,
+    Network,
+    /// filesystem:<scope> — scope配下の読み書き（"*"は全体）
+    Filesystem(String),
+}
+
+impl Permission {
+    pub fn parse(value: &str) -> Result<Permission, String> {
+        match value {
+            "read_document" => Ok(Permission::ReadDocument),
+            "write_document" => Ok(Permission::WriteDocument),
+            "clipboard" => Ok(Permission::Clipboard),
+            "subprocess" => Ok(Permission::Subprocess),
+            "network" => Ok(Permission::Network),
+            _ => match value.strip_prefix("filesystem:") {
+                Some(scope) if !scope.trim().is_empty() => Ok(Permission::Filesystem(scope.trim().to_string())),
+                _ => Err(format!("Unknown permission '{}'", value)),
+            },
+        }
+    }
+
+    pub fn as_string(&self) -> String {
+        match self {
+            Permission::ReadDocument => "read_document".to_string(),
+            Permission::WriteDocument => "write_document".to_string(),
+            Permission::Clipboard => "clipboard".to_string(),
+            Permission::Subprocess => "subprocess".to_string(),
+            Permission::Network => "network".to_string(),
+            Permission::Filesystem(scope) => format!("filesystem:{}", scope),
+        }
+    }
+
+    /**
+     * 確認ダイアログに表示する説明
+     */
+    fn description(&self) -> String {
+        match self {
+            Permission::ReadDocument => "編集中のドキュメントの読み取り".to_string(),
+            Permission::WriteDocument => "編集中のドキュメントへの書き込み".to_string(),
+            Permission::Clipboard => "クリップボードへのアクセス".to_string(),
+            Permission::Subprocess => "外部コマンドの実行".to_string(),
+            Permission::Network => "ネットワーク通信".to_string(),
+            Permission::Filesystem(scope) if scope == "*" => "すべてのファイルの読み書き".to_string(),
+            Permission::Filesystem(scope) => format!("ファイルの読み書き: {}", scope),
+        }
+    }
+}
+
+/**
+ * 読み込み済み拡張機能に許可された権限
+ */
+#[derive(Debug, Clone)]
+pub struct PermissionSet {
+    extension_id: String,
+    /// 拡張機能のディレクトリ（どの拡張機能にも属さない呼び出し元ではNone）
+    extension_dir: Option<PathBuf>,
+    permissions: BTreeSet<Permission>,
+    filesystem_scopes: Vec<PathBuf>,
+    filesystem_all: bool,
+}
+
+impl PermissionSet {
+    pub fn new(extension_id: &str, extension_dir: &Path, permissions: Vec<Permission>) -> Self {
+        let mut filesystem_scopes = Vec::new();
+        let mut filesystem_all = false;
+        for permission in &permissions {
+            if let Permission::Filesystem(scope) = permission {
+                if scope == "*" {
+                    filesystem_all = true;
+                } else {
+                    filesystem_scopes.push(normalize_path(&expand_home(scope)));
+                }
+            }
+        }
+
+        PermissionSet {
+            extension_id: extension_id.to_string(),
+            extension_dir: Some(normalize_path(extension_dir)),
+            permissions: permissions.into_iter().collect(),
+            filesystem_scopes,
+            filesystem_all,
+        }
+    }
+
+    /**
+     * 拡張機能のコンテキストにもエディタ本体のスレッドにも属さない呼び出し元の権限
+     * （拡張機能がthreadingやタイマーで起動したスレッドなど）
+     * 権限は持たず、読み取りもPython本体（標準ライブラリ等）のみ許可する
+     */
+    #[cfg(feature = "python")]
+    pub fn untrusted() -> Arc<PermissionSet> {
+        static UNTRUSTED: OnceLock<Arc<PermissionSet>> = OnceLock::new();
+        UNTRUSTED
+            .get_or_init(|| {
+                Arc::new(PermissionSet {
+                    extension_id: String::new(),
+                    extension_dir: None,
+                    permissions: BTreeSet::new(),
+                    filesystem_scopes: Vec::new(),
+                    filesystem_all: false,
+                })
+            })
+            .clone()
+    }
+
+    pub fn extension_id(&self) -> &str {
+        &self.extension_id
+    }
+
+    pub fn allows(&self, permission: &Permission) -> bool {
+        self.permissions.contains(permission)
+    }
+
+    /**
+     * 許可された権限の文字列表現（別プロセスのホストへ渡す）
+     */
+    pub fn granted(&self) -> Vec<String> {
+        self.permissions.iter().map(|permission| permission.as_string()).collect()
+    }
+
+    /**
+     * ファイルパスへのアクセス可否
+     * 読み取りは拡張機能自身のディレクトリとPython本体（標準ライブラリ等）も許可する
+     */
+    pub fn allows_path(&self, path: &Path, write: bool) -> bool {
+        if self.filesystem_all {
+            return true;
+        }
+
+        let path = normalize_path(path);
+        if self.filesystem_scopes.iter().any(|scope| path.starts_with(scope)) {
+            return true;
+        }
+        if write {
+            return false;
+        }
+
+        self.extension_dir.as_ref().is_some_and(|dir| path.starts_with(dir))
+            || python_read_paths().iter().any(|prefix| path.starts_with(prefix))
+    }
+
+    /**
+     * 権限が無い場合にPermissionErrorを返す
+     */
+    #[cfg(feature = "python")]
+    pub fn require(&self, permission: &Permission) -> PyResult<()> {
+        if self.allows(permission) {
+            Ok(())
+        } else {
+            Err(self.denied(&permission.as_string()))
+        }
+    }
+
+    /**
+     * ファイルパスへのアクセス権が無い場合にPermissionErrorを返す
+     */
+    #[cfg(feature = "python")]
+    pub fn require_path(&self, path: &Path, write: bool) -> PyResult<()> {
+        if self.allows_path(path, write) {
+            Ok(())
+        } else {
+            Err(self.denied(&format!(
+                "{} '{}'",
+                if write { "writing" } else { "reading" },
+                path.display()
+            )))
+        }
+    }
+
+    #[cfg(feature = "python")]
+    fn denied(&self, what: &str) -> PyErr {
+        if self.extension_dir.is_none() {
+            return PyPermissionError::new_err(format!(
+                "Python code running outside an extension call does not have permission for {}",
+                what
+            ));
+        }
+        PyPermissionError::new_err(format!(
+            "Extension '{}' does not have permission for {}",
+            self.extension_id, what
+        ))
+    }
+}
+
+/**
+ * 拡張機能の操作（on_event・on_commandの戻り値や送信した操作）に必要な権限を確認する
+ * vinsert_api.rsの各関数と同じ条件で、WebViewへ渡す前にすべての実行環境で確認する
+ */
+pub fn check_action(permissions: &PermissionSet, payload: &serde_json::Value) -> Result<(), String> {
+    let denied = |what: String| {
+        Err(format!(
+            "Extension '{}' does not have permission for {}",
+            permissions.extension_id(),
+            what
+        ))
+    };
+    match payload.get("action").and_then(serde_json::Value::as_str) {
+        Some("insert_text") if !permissions.allows(&Permission::WriteDocument) => {
+            denied(Permission::WriteDocument.as_string())
+        }
+        Some("open_file") => {
+            let path = payload.get("path").and_then(serde_json::Value::as_str).unwrap_or_default();
+            if permissions.allows_path(Path::new(path), false) {
+                Ok(())
+            } else {
+                denied(format!("reading '{}'", path))
+            }
+        }
+        _ => Ok(()),
+    }
+}
+
+// =====================================================
+// ユーザーの許可状況の保存
+// =====================================================
+
+/**
+ * 拡張機能ごとの許可・拒否の記録（extension_permissions.json）
+ */
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PermissionDecision {
+    #[serde(default)]
+    granted: BTreeSet<String>,
+    #[serde(default)]
+    denied: BTreeSet<String>,
+}
+
+// 確認ダイアログの多重表示と記録ファイルの同時書き込みを防ぐ
+static DECISION_LOCK: Mutex<()> = Mutex::new(());
+
+fn decisions_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    Ok(app_data.join("vinsert").join("extension_permissions.json"))
+}
+
+fn load_decisions(path: &Path) -> HashMap<String, PermissionDecision> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_decisions(path: &Path, decisions: &HashMap<String, PermissionDecision>) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create '{}': {}", parent.display(), e))?;
+    }
+    let content = serde_json::to_string_pretty(decisions)
+        .map_err(|e| format!("Failed to serialize permissions: {}", e))?;
+    std::fs::write(path, content).map_err(|e| format!("Failed to write '{}': {}", path.display(), e))
+}
+
+/**
+ * 宣言された権限のうちユーザーが許可したものを返す
+ * 初めて要求された権限がある場合は確認ダイアログを表示する（初回使用時のみ）
+ * 拒否した権限も記録するため、改めて確認するには拡張機能ダイアログで権限をリセットする
+ */
+pub fn ensure_granted(
+    app: &tauri::AppHandle,
+    extension_id: &str,
+    extension_name: &str,
+    extension_dir: &Path,
+    requested: &[String],
+) -> Result<PermissionSet, String> {
+    let requested = requested
+        .iter()
+        .map(|value| Permission::parse(value))
+        .collect::<Result<BTreeSet<_>, _>>()
+        .map_err(|e| format!("Invalid permissions for extension '{}': {}", extension_id, e))?;
+
+    let _guard = DECISION_LOCK.lock().map_err(|e| e.to_string())?;
+    let path = decisions_path(app)?;
+    let mut decisions = load_decisions(&path);
+    let decision = decisions.entry(extension_id.to_string()).or_default();
+
+    let pending: Vec<&Permission> = requested
+        .iter()
+        .filter(|permission| {
+            let key = permission.as_string();
+            !decision.granted.contains(&key) && !decision.denied.contains(&key)
+        })
+        .collect();
+
+    if !pending.is_empty() {
+        let allowed = ask_user(app, extension_name, &pending);
+        for permission in &pending {
+            let key = permission.as_string();
+            if allowed {
+                decision.granted.insert(key);
+            } else {
+                decision.denied.insert(key);
+            }
+        }
+        println!(
+            "🔐 Extension permissions {} for {}: {:?}",
+            if allowed { "granted" } else { "denied" },
+            extension_id,
+            pending.iter().map(|p| p.as_string()).collect::<Vec<_>>()
+        );
+        save_decisions(&path, &decisions)?;
+    }
+
+    let decision = decisions.get(extension_id);
+    let granted = requested
+        .into_iter()
+        .filter(|permission| {
+            decision
+                .map(|decision| decision.granted.contains(&permission.as_string()))
+                .unwrap_or(false)
+        })
+        .collect();
+
+    Ok(PermissionSet::new(extension_id, extension_dir, granted))
+}
+
+/**
+ * 拡張機能の許可・拒否の記録を削除する（アンインストール時、権限のリセット時）
+ */
+pub fn forget_decisions(app: &tauri::AppHandle, extension_id: &str) -> Result<(), String> {
+    let _guard = DECISION_LOCK.lock().map_err(|e| e.to_string())?;
+    let path = decisions_path(app)?;
+    let mut decisions = load_decisions(&path);
+    if decisions.remove(extension_id).is_some() {
+        save_decisions(&path, &decisions)?;
+    }
+    Ok(())
+}
+
+/**
+ * 権限の確認ダイアログを表示する（メインスレッド以外から呼ぶこと）
+ */
+fn ask_user(app: &tauri::AppHandle, extension_name: &str, pending: &[&Permission]) -> bool {
+    use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+    let list = pending
+        .iter()
+        .map(|permission| format!("・{}", permission.description()))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    app.dialog()
+        .message(format!(
+            "拡張機能「{}」が次の権限を要求しています。\n\n{}\n\n許可しますか？",
+            extension_name, list
+        ))
+        .title("拡張機能の権限")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom("許可".to_string(), "拒否".to_string()))
+        .blocking_show()
+}
+
+// =====================================================
+// 監査フック（sys.addaudithook）
+// =====================================================
+
+static PYTHON_READ_PATHS: OnceLock<Vec<PathBuf>> = OnceLock::new();
+#[cfg(feature = "python")]
+static INSTALL_HOOK: Once = Once::new();
+
+fn python_read_paths() -> &'static [PathBuf] {
+    PYTHON_READ_PATHS.get().map(|paths| paths.as_slice()).unwrap_or(&[])
+}
+
+/**
+ * 監査フックをインタープリターに登録する（プロセスで一度だけ）
+ * エディタ本体のスレッド以外（拡張機能の実行中や、拡張機能が起動したスレッド）で権限を検査する
+ */
+#[cfg(feature = "python")]
+pub fn install_audit_hook(py: Python<'_>) -> Result<(), String> {
+    let mut result = Ok(());
+    INSTALL_HOOK.call_once(|| {
+        result = (|| -> PyResult<()> {
+            let sys = py.import_bound("sys")?;
+
+            // 標準ライブラリやsite-packagesの読み取り（import）は常に許可する
+            let mut paths = Vec::new();
+            for attr in ["prefix", "base_prefix", "exec_prefix", "base_exec_prefix"] {
+                if let Ok(prefix) = sys.getattr(attr).and_then(|p| p.extract::<String>()) {
+                    paths.push(normalize_path(Path::new(&prefix)));
+                }
+            }
+            if let Ok(sys_path) = sys.getattr("path").and_then(|p| p.extract::<Vec<String>>()) {
+                paths.extend(
+                    sys_path
+                        .iter()
+                        .filter(|p| !p.is_empty())
+                        .map(|p| normalize_path(Path::new(p))),
+                );
+            }
+            let _ = PYTHON_READ_PATHS.set(paths);
+
+            let hook = wrap_pyfunction_bound!(hook::audit_hook, py)?;
+            sys.call_method1("addaudithook", (hook,))?;
+            Ok(())
+        })()
+        .map_err(|e| format!("Failed to install audit hook: {}", e));
+        if result.is_ok() {
+            println!("🔐 Extension audit hook installed");
+        }
+    });
+    result
+}
+
+/**
+ * sys.addaudithookに登録する関数
+ * clippy::useless_conversionの抑制はvinsert_api.rsのfunctionsと同じ理由
+ */
+#[cfg(feature = "python")]
+mod hook {
+    #![allow(clippy::useless_conversion)]
+
+    use super::*;
+
+    #[pyfunction]
+    pub(super) fn audit_hook(event: &str, args: &Bound<'_, PyTuple>) -> PyResult<()> {
+        // エディタ本体のスレッド（execute_python等）では検査しない
+        let permissions = match vinsert_api::current_caller() {
+            Caller::Extension(permissions) => permissions,
+            Caller::Host => return Ok(()),
+            Caller::Untrusted => PermissionSet::untrusted(),
+        };
+
+        match event {
+            "open" => {
+                let path = match path_arg(args, 0) {
+                    Some(path) => path,
+                    None => return Ok(()),
+                };
+                permissions.require_path(&path, is_write_open(args))?;
+            }
+            "os.listdir" | "os.scandir" => {
+                let path = path_arg(args, 0).unwrap_or_else(|| PathBuf::from("."));
+                permissions.require_path(&path, false)?;
+            }
+            "os.remove" | "os.rmdir" | "os.mkdir" | "os.chmod" | "os.truncate" | "shutil.rmtree" => {
+                if let Some(path) = path_arg(args, 0) {
+                    permissions.require_path(&path, true)?;
+                }
+            }
+            "os.rename" | "shutil.copyfile" | "shutil.move" => {
+                for index in 0..2 {
+                    if let Some(path) = path_arg(args, index) {
+                        permissions.require_path(&path, true)?;
+                    }
+                }
+            }
+            "subprocess.Popen" | "os.system" | "os.exec" | "os.spawn" | "os.posix_spawn" | "os.fork"
+            | "os.forkpty" | "os.startfile" | "pty.spawn" => {
+                permissions.require(&Permission::Subprocess)?;
+            }
+            "socket.connect" | "socket.bind" | "socket.sendto" | "socket.sendmsg" | "socket.getaddrinfo"
+            | "socket.gethostbyname" | "socket.gethostbyaddr" => {
+                permissions.require(&Permission::Network)?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+/**
+ * 監査イベントの引数からパスを取り出す（ファイルディスクリプタ等はNone）
+ */
+#[cfg(feature = "python")]
+fn path_arg(args: &Bound<'_, PyTuple>, index: usize) -> Option<PathBuf> {
+    let value = args.get_item(index).ok()?;
+    if let Ok(path) = value.extract::<String>() {
+        return Some(PathBuf::from(path));
+    }
+    if let Ok(bytes) = value.extract::<Vec<u8>>() {
+        return Some(PathBuf::from(String::from_utf8_lossy(&bytes).into_owned()));
+    }
+    // os.PathLike
+    value
+        .call_method0("__fspath__")
+        .ok()
+        .and_then(|path| path.extract::<String>().ok())
+        .map(PathBuf::from)
+}
+
+/**
+ * open イベントが書き込みを伴うかどうか（引数は path, mode, flags）
+ */
+#[cfg(feature = "python")]
+fn is_write_open(args: &Bound<'_, PyTuple>) -> bool {
+    if let Some(mode) = args.get_item(1).ok().and_then(|m| m.extract::<String>().ok()) {
+        return mode.contains(['w', 'a', 'x', '+']);
+    }
+    // os.open はmodeがNoneでflagsのみ（O_WRONLY=1, O_RDWR=2）
+    args.get_item(2)
+        .ok()
+        .and_then(|flags| flags.extract::<i64>().ok())
+        .map(|flags| flags & 0b11 != 0)
+        .unwrap_or(false)
+}
+
+fn expand_home(scope: &str) -> PathBuf {
+    if let Some(rest) = scope.strip_prefix("~") {
+        let home = std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE"));
+        if let Ok(home) = home {
+            return PathBuf::from(home).join(rest.trim_start_matches(['/', '\\']));
+        }
+    }
+    PathBuf::from(scope)
+}
+
+/**
+ * パスを絶対パスにして . と .. を取り除く（存在しないパスにも使える）
+ */
+fn normalize_path(path: &Path) -> PathBuf {
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir().unwrap_or_default().join(path)
+    };
+
+    let mut normalized = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    normalized
+}
@@ -0,0 +1,715 @@
+/*
+ * =====================================================
+ * Vinsert Editor - 別プロセスの拡張機能ホスト
+ * setting.jsonのhostにprocess / sharedを指定した拡張機能を子プロセスのPythonで実行する
+ * 子プロセスとは標準入出力のJSON-RPC 2.0（1行に1メッセージ）で通信し、
+ * 異常終了した場合は自動で再起動してWebViewへ通知する
+ *
+ * 子プロセス内で行うファイル・サブプロセス・ネットワークの権限確認は補助的なもので、
+ * 拡張機能のコードから無効にできる（OSのサンドボックスでホストごと制限しない限り強制されない）
+ * エディタ本体を経由する操作（action・クリップボード）はここで改めて確認する
+ * =====================================================
+ */
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, ExitStatus, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::extension_config;
+use crate::extension_host::EXTENSION_TIMEOUT_MS;
+use crate::extension_manifest::HostMode;
+use crate::extension_permissions::{self, Permission, PermissionSet};
+use crate::python_environment;
+use crate::python_runtime::PythonRuntime;
+use crate::vinsert_api::{self, EditorSnapshot};
+
+/// 子プロセスで実行するホストのスクリプト
+const HOST_SCRIPT: &str = include_str!("extension_process_host.py");
+
+/// ホストの異常終了を通知するイベント名
+pub const EXTENSION_HOST_CRASHED_EVENT: &str = "extension://host-crashed";
+
+/// sharedを指定した拡張機能が使うホストのキー
+const SHARED_HOST_KEY: &str = "shared";
+
+/// 短時間に異常終了を繰り返すホストは再起動しない
+const MAX_RESTARTS: usize = 3;
+const RESTART_WINDOW: Duration = Duration::from_secs(60);
+
+/// 出力が閉じてから終了コードを取得できるまで待つ時間
+const EXIT_GRACE: Duration = Duration::from_millis(500);
+
+/// Windowsで子プロセスのコンソールウィンドウを表示しない
+#[cfg(windows)]
+const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+/**
+ * 拡張機能を実行するホストのキー（processは拡張機能ごと、sharedは共通）
+ */
+pub fn host_key(mode: HostMode, extension_id: &str) -> String {
+    match mode {
+        HostMode::Shared => SHARED_HOST_KEY.to_string(),
+        _ => format!("extension:{}", extension_id),
+    }
+}
+
+/**
+ * 子プロセスの起動設定（起動時にPython実行環境から決める）
+ */
+#[derive(Debug, Clone)]
+pub struct ProcessHostConfig {
+    pub python: PathBuf,
+    /// 拡張機能用に追加したsite-packages（python_environment）
+    pub site_dirs: Vec<String>,
+}
+
+impl Default for ProcessHostConfig {
+    fn default() -> Self {
+        ProcessHostConfig {
+            python: PathBuf::from(if cfg!(windows) { "python" } else { "python3" }),
+            site_dirs: Vec::new(),
+        }
+    }
+}
+
+impl ProcessHostConfig {
+    /**
+     * 子プロセスのPythonを決める
+     * VINSERT_EXTENSION_PYTHON、拡張機能用のvenv、エディタが使っているPythonの順に探す
+     * （組み込みPythonではsys.executableがアプリ本体になるため、prefix配下の実行ファイルを使う）
+     */
+    pub fn from_runtime(runtime: &PythonRuntime) -> Self {
+        let mut config = ProcessHostConfig {
+            site_dirs: runtime.environment.added_paths.clone(),
+            ..Default::default()
+        };
+
+        if let Some(python) = std::env::var_os("VINSERT_EXTENSION_PYTHON").filter(|value| !value.is_empty()) {
+            config.python = PathBuf::from(python);
+            return config;
+        }
+
+        let venv_python = runtime
+            .environment
+            .venv
+            .as_deref()
+            .map(|venv| python_environment::venv_python(Path::new(venv)));
+        let executable = runtime.executable.as_deref().map(PathBuf::from).filter(|path| {
+            path.file_name()
+                .is_some_and(|name| name.to_string_lossy().to_lowercase().starts_with("python"))
+        });
+        let prefix_python = runtime.prefix.as_deref().map(|prefix| {
+            if cfg!(windows) {
+                Path::new(prefix).join("python.exe")
+            } else {
+                Path::new(prefix).join("bin").join("python3")
+            }
+        });
+
+        if let Some(python) = [venv_python, executable, prefix_python]
+            .into_iter()
+            .flatten()
+            .find(|path| path.is_file())
+        {
+            config.python = python;
+        }
+        config
+    }
+}
+
+/**
+ * 起動中の子プロセス
+ */
+struct HostProcess {
+    child: Child,
+    stdin: ChildStdin,
+    /// 子プロセスからのメッセージ（読み取りスレッドが受け取る）
+    incoming: mpsc::Receiver<Value>,
+    next_request_id: u64,
+}
+
+/**
+ * ホストに読み込んだ拡張機能（再起動時に同じ内容で読み込み直す）
+ */
+struct HostedExtension {
+    extension_dir: PathBuf,
+    main_file: String,
+    permissions: Arc<PermissionSet>,
+}
+
+/**
+ * ホストごとの状態
+ */
+struct HostSlot {
+    key: String,
+    process: Option<HostProcess>,
+    /// 起動ごとに増える番号（停止・再起動した後に届く古い終了通知を無視する）
+    generation: u64,
+    extensions: BTreeMap<String, HostedExtension>,
+    /// RESTART_WINDOW内に異常終了した時刻
+    crashes: Vec<Instant>,
+    /// エディタ側で停止させた場合の理由（応答なしなど）
+    exit_reason: Option<String>,
+    /// 再起動を諦めた理由（次に拡張機能を読み込むまで呼び出しを拒否する）
+    failed: Option<String>,
+}
+
+/**
+ * ホストの異常終了の通知内容
+ */
+#[derive(Debug, Clone, Serialize)]
+struct HostCrashedEvent {
+    host: String,
+    extensions: Vec<String>,
+    message: String,
+    restarted: bool,
+}
+
+/**
+ * 別プロセスのホスト一覧（ExtensionHostが保持する）
+ */
+#[derive(Clone, Default)]
+pub struct ProcessHosts {
+    slots: Arc<Mutex<HashMap<String, Arc<Mutex<HostSlot>>>>>,
+    config: Arc<Mutex<ProcessHostConfig>>,
+}
+
+impl ProcessHosts {
+    pub fn new() -> Self {
+        ProcessHosts::default()
+    }
+
+    /**
+     * 子プロセスの起動設定を変更する（次に起動するホストから反映）
+     */
+    pub fn configure(&self, config: ProcessHostConfig) {
+        println!("🧩 Extension process host: {}", config.python.display());
+        if let Ok(mut current) = self.config.lock() {
+            *current = config;
+        }
+    }
+
+    fn slot(&self, key: &str, create: bool) -> Result<Option<Arc<Mutex<HostSlot>>>, String> {
+        let mut slots = self.slots.lock().map_err(|e| e.to_string())?;
+        if create && !slots.contains_key(key) {
+            slots.insert(
+                key.to_string(),
+                Arc::new(Mutex::new(HostSlot {
+                    key: key.to_string(),
+                    process: None,
+                    generation: 0,
+                    extensions: BTreeMap::new(),
+                    crashes: Vec::new(),
+                    exit_reason: None,
+                    failed: None,
+                })),
+            );
+        }
+        Ok(slots.get(key).cloned())
+    }
+
+    /**
+     * 拡張機能を子プロセスに読み込む（ホストが起動していなければ起動する）
+     */
+    pub fn load(
+        &self,
+        key: &str,
+        extension_id: &str,
+        extension_dir: &Path,
+        main_file: &str,
+        permissions: Arc<PermissionSet>,
+    ) -> Result<(), String> {
+        let slot = match self.slot(key, true)? {
+            Some(slot) => slot,
+            None => return Err(format!("Extension host '{}' is not available", key)),
+        };
+        let mut slot = slot.lock().map_err(|e| e.to_string())?;
+
+        // 同じプロセスの拡張機能は互いのコードや権限を使えるため、権限が同じものだけ相乗りさせる
+        let granted = permissions.granted();
+        if let Some((other_id, _)) = slot
+            .extensions
+            .iter()
+            .find(|(other_id, other)| other_id.as_str() != extension_id && other.permissions.granted() != granted)
+        {
+            return Err(format!(
+                "Extension '{}' cannot share extension host '{}' with '{}' because their permissions differ; use \"host\": \"process\"",
+                extension_id, key, other_id
+            ));
+        }
+
+        // 明示的な読み込み（有効化・再読み込み）では再起動の制限を解除する
+        slot.failed = None;
+        slot.crashes.clear();
+        if slot.process.is_none() {
+            self.start(&mut slot)?;
+        }
+
+        let extension = HostedExtension {
+            extension_dir: extension_dir.to_path_buf(),
+            main_file: main_file.to_string(),
+            permissions,
+        };
+        request(&mut slot, "load", load_params(extension_id, &extension))?;
+        slot.extensions.insert(extension_id.to_string(), extension);
+        Ok(())
+    }
+
+    /**
+     * 拡張機能の関数を呼び出す（戻り値は文字列またはNone）
+     */
+    pub fn call(
+        &self,
+        key: &str,
+        extension_id: &str,
+        function: &str,
+        args: &[&str],
+        editor: &EditorSnapshot,
+    ) -> Result<Option<String>, String> {
+        let slot = match self.slot(key, false)? {
+            Some(slot) => slot,
+            None => return Err(format!("Extension '{}' is not loaded", extension_id)),
+        };
+        let mut slot = slot.lock().map_err(|e| e.to_string())?;
+        if let Some(reason) = &slot.failed {
+            return Err(format!("Extension host '{}' is stopped: {}", key, reason));
+        }
+
+        let params = json!({
+            "extension_id": extension_id,
+            "function": function,
+            "args": args,
+            "editor": editor,
+        });
+        match request(&mut slot, "call", params)? {
+            Value::Null => Ok(None),
+            Value::String(response) => Ok(Some(response)),
+            other => Err(format!("Extension '{}' returned a non-string value: {}", extension_id, other)),
+        }
+    }
+
+    /**
+     * 拡張機能をホストから取り除く（最後の拡張機能であればホストを停止する）
+     */
+    pub fn unload(&self, key: &str, extension_id: &str) -> bool {
+        let slot = match self.slot(key, false) {
+            Ok(Some(slot)) => slot,
+            _ => return false,
+        };
+        let mut slot = match slot.lock() {
+            Ok(slot) => slot,
+            Err(_) => return false,
+        };
+
+        if slot.extensions.remove(extension_id).is_none() {
+            return false;
+        }
+        if slot.process.is_some() {
+            if let Err(e) = request(&mut slot, "unload", json!({ "extension_id": extension_id })) {
+                println!("⚠️ Failed to unload {} from extension host: {}", extension_id, e);
+            }
+        }
+
+        if slot.extensions.is_empty() {
+            stop(&mut slot);
+            drop(slot);
+            if let Ok(mut slots) = self.slots.lock() {
+                slots.remove(key);
+            }
+        }
+        true
+    }
+
+    /**
+     * 子プロセスを起動してinitializeを送る
+     */
+    fn start(&self, slot: &mut HostSlot) -> Result<(), String> {
+        let config = self.config.lock().map_err(|e| e.to_string())?.clone();
+
+        let mut command = Command::new(&config.python);
+        // -B: 署名した拡張機能に__pycache__があると改ざんとして扱うため、バイトコードを書き込まない（extension_signing.rs）
+        command
+            .arg("-B")
+            .arg("-c")
+            .arg(HOST_SCRIPT)
+            .env("PYTHONIOENCODING", "utf-8")
+            .env("PYTHONUNBUFFERED", "1")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::CommandExt;
+            command.creation_flags(CREATE_NO_WINDOW);
+        }
+
+        let mut child = command.spawn().map_err(|e| {
+            format!(
+                "Failed to start extension host '{}' with '{}': {}",
+                slot.key,
+                config.python.display(),
+                e
+            )
+        })?;
+        let (stdin, stdout, stderr) = match (child.stdin.take(), child.stdout.take(), child.stderr.take()) {
+            (Some(stdin), Some(stdout), Some(stderr)) => (stdin, stdout, stderr),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("Failed to connect to extension host '{}'", slot.key));
+            }
+        };
+
+        slot.generation += 1;
+        slot.exit_reason = None;
+        let generation = slot.generation;
+        let (sender, incoming) = mpsc::channel();
+
+        // 出力が閉じたら（終了・クラッシュ）再起動を判断する
+        let hosts = self.clone();
+        let key = slot.key.clone();
+        let reader = std::thread::Builder::new()
+            .name(format!("vinsert-extension-host-{}", slot.key))
+            .spawn(move || {
+                read_messages(&key, stdout, sender);
+                hosts.handle_exit(&key, generation);
+            });
+
+        // 拡張機能のprintやトレースバックはログへ流す
+        let key = slot.key.clone();
+        let logger = std::thread::Builder::new()
+            .name(format!("vinsert-extension-host-{}-stderr", slot.key))
+            .spawn(move || {
+                for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                    println!("🐍 [{}] {}", key, line);
+                }
+            });
+
+        if let Err(e) = reader.and(logger) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!("Failed to start extension host thread: {}", e));
+        }
+
+        let pid = child.id();
+        slot.process = Some(HostProcess {
+            child,
+            stdin,
+            incoming,
+            next_request_id: 0,
+        });
+
+        if let Err(e) = request(slot, "initialize", json!({ "site_dirs": config.site_dirs })) {
+            stop(slot);
+            return Err(format!("Failed to initialize extension host '{}': {}", slot.key, e));
+        }
+        println!("🧩 Extension host started: {} (pid {})", slot.key, pid);
+        Ok(())
+    }
+
+    /**
+     * 子プロセスの出力が閉じたときの処理（読み取りスレッドから呼ばれる）
+     * 停止させたものでなければ異常終了として通知し、読み込み済みの拡張機能ごと再起動する
+     */
+    fn handle_exit(&self, key: &str, generation: u64) {
+        let slot = match self.slot(key, false) {
+            Ok(Some(slot)) => slot,
+            _ => return,
+        };
+        let mut slot = match slot.lock() {
+            Ok(slot) => slot,
+            Err(_) => return,
+        };
+        if slot.generation != generation {
+            return;
+        }
+
+        let status = slot.process.take().and_then(|process| wait_for_exit(process.child));
+        let message = slot
+            .exit_reason
+            .take()
+            .unwrap_or_else(|| status.map(|status| describe_exit(&status)).unwrap_or_else(|| "exited".to_string()));
+        let extensions: Vec<String> = slot.extensions.keys().cloned().collect();
+        println!("💥 Extension host crashed: {} ({})", key, message);
+
+        let now = Instant::now();
+        slot.crashes.retain(|crashed| now.duration_since(*crashed) < RESTART_WINDOW);
+        slot.crashes.push(now);
+
+        let restarted = if extensions.is_empty() {
+            false
+        } else if slot.crashes.len() > MAX_RESTARTS {
+            println!("❌ Extension host {} crashed {} times, not restarting", key, slot.crashes.len());
+            slot.failed = Some(format!("crashed {} times within {} s", slot.crashes.len(), RESTART_WINDOW.as_secs()));
+            false
+        } else {
+            match self.restart(&mut slot) {
+                Ok(()) => true,
+                Err(e) => {
+                    println!("❌ Failed to restart extension host {}: {}", key, e);
+                    slot.failed = Some(e);
+                    false
+                }
+            }
+        };
+
+        let event = HostCrashedEvent {
+            host: key.to_string(),
+            extensions,
+            message,
+            restarted,
+        };
+        if let Err(e) = vinsert_api::emit_event(EXTENSION_HOST_CRASHED_EVENT, event) {
+            println!("⚠️ {}", e);
+        }
+    }
+
+    /**
+     * ホストを起動し直し、読み込み済みだった拡張機能を読み込み直す
+     * 読み込み直しで再びクラッシュした場合は読み取りスレッドが改めて処理する
+     */
+    fn restart(&self, slot: &mut HostSlot) -> Result<(), String> {
+        self.start(slot)?;
+        let loads: Vec<(String, Value)> = slot
+            .extensions
+            .iter()
+            .map(|(id, extension)| (id.clone(), load_params(id, extension)))
+            .collect();
+        for (id, params) in loads {
+            if let Err(e) = request(slot, "load", params) {
+                println!("⚠️ Failed to reload {} after restart: {}", id, e);
+            }
+        }
+        println!("🔄 Extension host restarted: {}", slot.key);
+        Ok(())
+    }
+}
+
+fn load_params(extension_id: &str, extension: &HostedExtension) -> Value {
+    json!({
+        "extension_id": extension_id,
+        "extension_dir": extension.extension_dir.to_string_lossy(),
+        "main_file": extension.main_file,
+        "permissions": extension.permissions.granted(),
+    })
+}
+
+/**
+ * 子プロセスを停止する（終了通知は世代番号で無視される）
+ */
+fn stop(slot: &mut HostSlot) {
+    slot.generation += 1;
+    if let Some(process) = slot.process.take() {
+        let HostProcess { child, stdin, .. } = process;
+        // 標準入力を閉じるとホストは自分で終了する
+        drop(stdin);
+        if wait_for_exit(child).is_none() {
+            println!("⚠️ Extension host {} did not exit", slot.key);
+        }
+        println!("🧩 Extension host stopped: {}", slot.key);
+    }
+}
+
+/**
+ * 子プロセスの終了を少し待ち、終わらなければ強制終了する
+ */
+fn wait_for_exit(mut child: Child) -> Option<ExitStatus> {
+    let deadline = Instant::now() + EXIT_GRACE;
+    while Instant::now() < deadline {
+        match child.try_wait() {
+            Ok(Some(status)) => return Some(status),
+            Ok(None) => std::thread::sleep(Duration::from_millis(20)),
+            Err(_) => break,
+        }
+    }
+    let _ = child.kill();
+    child.wait().ok()
+}
+
+fn describe_exit(status: &ExitStatus) -> String {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return format!("terminated by signal {}", signal);
+        }
+    }
+    match status.code() {
+        Some(code) => format!("exited with status {}", code),
+        None => "exited".to_string(),
+    }
+}
+
+/**
+ * 子プロセスの標準出力を1行ずつJSONとして読み取る（出力が閉じるまで）
+ */
+fn read_messages(key: &str, stdout: ChildStdout, sender: mpsc::Sender<Value>) {
+    for line in BufReader::new(stdout).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(message) => {
+                if sender.send(message).is_err() {
+                    break;
+                }
+            }
+            Err(e) => println!("⚠️ Invalid message from extension host {}: {}", key, e),
+        }
+    }
+}
+
+fn write_message(stdin: &mut ChildStdin, message: &Value) -> std::io::Result<()> {
+    let mut line = message.to_string();
+    line.push('\n');
+    stdin.write_all(line.as_bytes())?;
+    stdin.flush()
+}
+
+/**
+ * 子プロセスへ要求を送り、応答を待つ
+ * 待っている間に届いた通知（action）や要求（clipboard）はここで処理する
+ * 制限時間を過ぎたホストは強制終了し、読み取りスレッドの終了処理で再起動させる
+ */
+fn request(slot: &mut HostSlot, method: &str, params: Value) -> Result<Value, String> {
+    let HostSlot {
+        key,
+        process,
+        extensions,
+        exit_reason,
+        ..
+    } = slot;
+    let process = match process.as_mut() {
+        Some(process) => process,
+        None => return Err(format!("Extension host '{}' is not running", key)),
+    };
+
+    // 呼び出し中の拡張機能（要求を処理している間に届いたaction等はこの拡張機能の権限で確認する）
+    let in_flight = params.get("extension_id").and_then(Value::as_str).map(str::to_string);
+
+    process.next_request_id += 1;
+    let request_id = process.next_request_id;
+    let message = json!({ "jsonrpc": "2.0", "id": request_id, "method": method, "params": params });
+    write_message(&mut process.stdin, &message)
+        .map_err(|e| format!("Extension host '{}' is not responding: {}", key, e))?;
+
+    let timeout = Duration::from_millis(EXTENSION_TIMEOUT_MS);
+    let deadline = Instant::now() + timeout;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match process.incoming.recv_timeout(remaining) {
+            Ok(message) => {
+                if message.get("method").is_some() {
+                    handle_host_message(&mut process.stdin, extensions, in_flight.as_deref(), &message);
+                    continue;
+                }
+                if message.get("id").and_then(Value::as_u64) != Some(request_id) {
+                    continue;
+                }
+                if let Some(error) = message.get("error") {
+                    return Err(error
+                        .get("message")
+                        .and_then(Value::as_str)
+                        .unwrap_or("Extension host error")
+                        .to_string());
+                }
+                return Ok(message.get("result").cloned().unwrap_or(Value::Null));
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                *exit_reason = Some(format!("did not respond to {} within {} ms", method, EXTENSION_TIMEOUT_MS));
+                let _ = process.child.kill();
+                return Err(format!(
+                    "Extension host '{}' did not respond within {} ms",
+                    key, EXTENSION_TIMEOUT_MS
+                ));
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(format!("Extension host '{}' exited unexpectedly", key));
+            }
+        }
+    }
+}
+
+/**
+ * 子プロセスからの通知・要求を処理する
+ * 子プロセス側でも権限を確認しているが、拡張機能が直接書き込んだ場合に備えてここでも確認する
+ * 拡張機能IDは子プロセスが送ってきた値ではなく、エディタから呼び出し中の拡張機能（in_flight）を使う
+ */
+fn handle_host_message(
+    stdin: &mut ChildStdin,
+    extensions: &BTreeMap<String, HostedExtension>,
+    in_flight: Option<&str>,
+    message: &Value,
+) {
+    let method = message.get("method").and_then(Value::as_str).unwrap_or_default();
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+    let result = match in_flight.and_then(|id| extensions.get_key_value(id)) {
+        None => Err(format!("Received '{}' while no extension call is in progress", method)),
+        Some((extension_id, extension)) => {
+            let extension_id = extension_id.as_str();
+            let permissions = &extension.permissions;
+            match method {
+                "action" => {
+                    let payload = params.get("payload").cloned().unwrap_or(Value::Null);
+                    extension_permissions::check_action(permissions, &payload)
+                        .and_then(|_| vinsert_api::forward_action(extension_id, payload))
+                        .map(|_| Value::Null)
+                }
+                "clipboard/read" => require(permissions, &Permission::Clipboard)
+                    .and_then(|_| vinsert_api::read_clipboard())
+                    .map(Value::String),
+                "config/get" => Ok(Value::Object(
+                    extension_config::current_values(extension_id).unwrap_or_default(),
+                )),
+                "clipboard/write" => {
+                    let text = params.get("text").and_then(Value::as_str).unwrap_or_default().to_string();
+                    require(permissions, &Permission::Clipboard)
+                        .and_then(|_| vinsert_api::write_clipboard(text))
+                        .map(|_| Value::Null)
+                }
+                _ => Err(format!("Unknown method '{}'", method)),
+            }
+        }
+    };
+
+    // 通知（idなし）には応答しない
+    let id = match message.get("id") {
+        Some(id) => id,
+        None => {
+            if let Err(e) = result {
+                println!("⚠️ {}: {}", method, e);
+            }
+            return;
+        }
+    };
+    let response = match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => json!({ "jsonrpc": "2.0", "id": id, "error": { "code": -32000, "message": e } }),
+    };
+    if let Err(e) = write_message(stdin, &response) {
+        println!("⚠️ Failed to respond to extension host: {}", e);
+    }
+}
+
+fn require(permissions: &PermissionSet, permission: &Permission) -> Result<(), String> {
+    if permissions.allows(permission) {
+        Ok(())
+    } else {
+        Err(format!(
+            "Extension '{}' does not have permission for {}",
+            permissions.extension_id(),
+            permission.as_string()
+        ))
+    }
+}
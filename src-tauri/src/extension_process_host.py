@@ -0,0 +1,460 @@
+"""
+=====================================================
+Vinsert Editor - 別プロセスの拡張機能ホスト
+エディタ本体（extension_process.rs）から子プロセスとして起動され、
+標準入出力のJSON-RPC 2.0（1行に1メッセージ）で拡張機能を読み込み・呼び出す
+拡張機能がクラッシュしてもエディタ本体は終了しない
+=====================================================
+"""
+
+import json
+import os
+import site
+import sys
+import traceback
+import types
+
+# 拡張機能のprintでプロトコルが壊れないよう、標準出力は標準エラーへ向ける
+_protocol_out = sys.stdout.buffer
+_protocol_in = sys.stdin.buffer
+sys.stdout = sys.stderr
+
+_next_request_id = 0
+
+# 読み込み済みの拡張機能（拡張機能ID → (モジュール名, モジュール, 権限)）
+_extensions = {}
+
+# 実行中の拡張機能のコンテキスト（拡張機能の外ではNone）
+_context = None
+
+# 標準ライブラリやsite-packagesの読み取り（import）は常に許可する
+_python_read_paths = []
+
+
+class RpcError(Exception):
+    def __init__(self, code, message):
+        super().__init__(message)
+        self.code = code
+        self.message = message
+
+
+class Context:
+    def __init__(self, extension_id, permissions, editor):
+        self.extension_id = extension_id
+        self.permissions = permissions
+        self.editor = editor or {}
+
+
+class Permissions:
+    """
+    拡張機能に許可された権限（extension_permissions.rsのPermissionSetと同じ判定）
+    """
+
+    def __init__(self, extension_dir, granted):
+        self.extension_dir = _normalize(extension_dir)
+        self.granted = set(granted)
+        self.filesystem_all = "filesystem:*" in self.granted
+        self.filesystem_scopes = [
+            _normalize(os.path.expanduser(permission[len("filesystem:"):].strip()))
+            for permission in self.granted
+            if permission.startswith("filesystem:") and permission != "filesystem:*"
+        ]
+
+    def allows_path(self, path, write):
+        if self.filesystem_all:
+            return True
+        path = _normalize(path)
+        if any(_is_within(path, scope) for scope in self.filesystem_scopes):
+            return True
+        if write:
+            return False
+        return _is_within(path, self.extension_dir) or any(
+            _is_within(path, prefix) for prefix in _python_read_paths
+        )
+
+
+def _normalize(path):
+    return os.path.normpath(os.path.abspath(os.fsdecode(path)))
+
+
+def _is_within(path, prefix):
+    return path == prefix or path.startswith(prefix.rstrip(os.sep) + os.sep)
+
+
+# =====================================================
+# JSON-RPC
+# =====================================================
+
+def _send(message):
+    message["jsonrpc"] = "2.0"
+    line = json.dumps(message, ensure_ascii=False) + "\n"
+    _protocol_out.write(line.encode("utf-8"))
+    _protocol_out.flush()
+
+
+def _read():
+    line = _protocol_in.readline()
+    if not line:
+        return None
+    return json.loads(line.decode("utf-8"))
+
+
+def _notify(method, params):
+    _send({"method": method, "params": params})
+
+
+def _request(method, params):
+    """
+    エディタ本体へ要求を送り、応答を待つ（拡張機能の呼び出し中のみ使う）
+    本体は呼び出しの応答を待っている間、この要求に応答する
+    """
+    global _next_request_id
+    _next_request_id += 1
+    request_id = _next_request_id
+    _send({"id": request_id, "method": method, "params": params})
+
+    while True:
+        message = _read()
+        if message is None:
+            sys.exit(0)
+        if message.get("id") != request_id or "method" in message:
+            continue
+        if "error" in message:
+            raise RuntimeError(message["error"].get("message", "Request failed"))
+        return message.get("result")
+
+
+# =====================================================
+# vinsertモジュール（vinsert_api.rsと同じ関数）
+# =====================================================
+
+def _current():
+    if _context is None:
+        raise RuntimeError("vinsert editor API is not available")
+    return _context
+
+
+def _require(permission):
+    context = _current()
+    if permission not in context.permissions.granted:
+        raise PermissionError(
+            "Extension '{}' does not have permission for {}".format(context.extension_id, permission)
+        )
+
+
+def _require_path(path, write):
+    context = _current()
+    if not context.permissions.allows_path(path, write):
+        raise PermissionError(
+            "Extension '{}' does not have permission for {} '{}'".format(
+                context.extension_id, "writing" if write else "reading", path
+            )
+        )
+
+
+def _emit_action(payload):
+    context = _current()
+    _notify("action", {"extension_id": context.extension_id, "payload": payload})
+
+
+class Selection:
+    """
+    エディタの選択範囲
+    """
+
+    def __init__(self, start, end, text):
+        self.start = start
+        self.end = end
+        self.text = text
+
+    def __repr__(self):
+        return "Selection(start={}, end={}, text={!r})".format(self.start, self.end, self.text)
+
+
+def get_text():
+    """エディタの全テキストを返す（read_document権限が必要）"""
+    _require("read_document")
+    return _current().editor.get("text", "")
+
+
+def get_selection():
+    """現在の選択範囲を返す（位置は文字単位、read_document権限が必要）"""
+    _require("read_document")
+    editor = _current().editor
+    text = editor.get("text", "")
+    start = editor.get("selection_start", 0)
+    end = editor.get("selection_end", 0)
+    start, end = min(start, end), max(start, end)
+    return Selection(start, end, text[start:end])
+
+
+def insert(text, move_cursor_back=0):
+    """カーソル位置にテキストを挿入し、末尾からmove_cursor_back文字戻る（write_document権限が必要）"""
+    _require("write_document")
+    _emit_action({"action": "insert_text", "text": text, "move_cursor_back": move_cursor_back})
+
+
+def show_suggestions(suggestions, position=None):
+    """補完候補を表示する（文字列または {"tag", "display"} の辞書のリスト、positionは文字単位）"""
+    items = []
+    for suggestion in suggestions:
+        if isinstance(suggestion, str):
+            items.append({"tag": suggestion, "display": suggestion})
+        else:
+            items.append(json.loads(json.dumps(suggestion)))
+    if position is None:
+        position = _current().editor.get("selection_start", 0)
+    _emit_action({"action": "show_suggestions", "suggestions": items, "position": position})
+
+
+def set_status(message):
+    """ステータスバーにメッセージを表示する"""
+    _emit_action({"action": "set_status", "message": message})
+
+
+def open_file(path):
+    """指定したパスのファイルをエディタで開く（そのパスへのfilesystem権限が必要）"""
+    _require_path(path, False)
+    _emit_action({"action": "open_file", "path": path})
+
+
+def get_clipboard():
+    """クリップボードのテキストを返す（clipboard権限が必要）"""
+    _require("clipboard")
+    return _request("clipboard/read", {"extension_id": _current().extension_id})
+
+
+def set_clipboard(text):
+    """クリップボードにテキストを書き込む（clipboard権限が必要）"""
+    _require("clipboard")
+    _request("clipboard/write", {"extension_id": _current().extension_id, "text": text})
+
+
+def get_config(key=None, default=None):
+    """拡張機能の設定値を返す（keyを省略するとすべての設定の辞書）"""
+    values = _request("config/get", {"extension_id": _current().extension_id}) or {}
+    if key is None:
+        return values
+    return values.get(key, default)
+
+
+def _create_vinsert_module():
+    module = types.ModuleType("vinsert")
+    for function in (get_text, get_selection, insert, show_suggestions, set_status,
+                     open_file, get_clipboard, set_clipboard, get_config):
+        setattr(module, function.__name__, function)
+    module.Selection = Selection
+    return module
+
+
+# =====================================================
+# 監査フック（extension_permissions.rsのaudit_hookと同じ判定）
+# =====================================================
+
+_SUBPROCESS_EVENTS = {
+    "subprocess.Popen", "os.system", "os.exec", "os.spawn", "os.posix_spawn", "os.fork",
+    "os.forkpty", "os.startfile", "pty.spawn",
+}
+_NETWORK_EVENTS = {
+    "socket.connect", "socket.bind", "socket.sendto", "socket.sendmsg", "socket.getaddrinfo",
+    "socket.gethostbyname", "socket.gethostbyaddr",
+}
+_WRITE_EVENTS = {"os.remove", "os.rmdir", "os.mkdir", "os.chmod", "os.truncate", "shutil.rmtree"}
+_COPY_EVENTS = {"os.rename", "shutil.copyfile", "shutil.move"}
+
+
+def _path_arg(args, index):
+    if len(args) <= index:
+        return None
+    value = args[index]
+    if isinstance(value, (str, bytes)):
+        return os.fsdecode(value)
+    if hasattr(value, "__fspath__"):
+        return os.fsdecode(value.__fspath__())
+    return None
+
+
+def _is_write_open(args):
+    mode = args[1] if len(args) > 1 else None
+    if isinstance(mode, str):
+        return any(flag in mode for flag in "wax+")
+    flags = args[2] if len(args) > 2 else None
+    return isinstance(flags, int) and flags & 0b11 != 0
+
+
+# ここでの確認は補助的なもの（拡張機能は同じプロセスの_context等を書き換えて回避できる）
+# ファイル・サブプロセス・ネットワークの制限を強制するには、OSのサンドボックスでこのプロセスごと制限する
+# エディタ本体を経由する操作（action・クリップボード）はextension_process.rsで改めて確認する
+def _audit_hook(event, args):
+    context = _context
+    if context is None:
+        return
+
+    if event == "open":
+        path = _path_arg(args, 0)
+        if path is not None:
+            _require_path(path, _is_write_open(args))
+    elif event in ("os.listdir", "os.scandir"):
+        _require_path(_path_arg(args, 0) or ".", False)
+    elif event in _WRITE_EVENTS:
+        path = _path_arg(args, 0)
+        if path is not None:
+            _require_path(path, True)
+    elif event in _COPY_EVENTS:
+        for index in range(2):
+            path = _path_arg(args, index)
+            if path is not None:
+                _require_path(path, True)
+    elif event in _SUBPROCESS_EVENTS:
+        _require("subprocess")
+    elif event in _NETWORK_EVENTS:
+        _require("network")
+
+
+# =====================================================
+# メソッド
+# =====================================================
+
+def _module_name_for(extension_id):
+    # extension_host.rsのmodule_name_forと同じ規則（英数字以外はUTF-8のバイトごとに "_<16進数2桁>"）
+    encoded = "".join(
+        chr(byte) if chr(byte).isascii() and chr(byte).isalnum() else "_%02x" % byte
+        for byte in extension_id.encode("utf-8")
+    )
+    return "vinsert_ext_" + encoded
+
+
+def _with_context(extension_id, permissions, editor, function, *args):
+    global _context
+    previous = _context
+    _context = Context(extension_id, permissions, editor)
+    try:
+        return function(*args)
+    finally:
+        _context = previous
+
+
+def initialize(params):
+    """
+    ホストの初期化（拡張機能用のsite-packagesを追加し、監査フックを登録する）
+    """
+    for directory in params.get("site_dirs", []):
+        site.addsitedir(directory)
+
+    for prefix in {sys.prefix, sys.base_prefix, sys.exec_prefix, sys.base_exec_prefix}:
+        _python_read_paths.append(_normalize(prefix))
+    _python_read_paths.extend(_normalize(path) for path in sys.path if path)
+    sys.addaudithook(_audit_hook)
+
+    return {"pid": os.getpid(), "version": list(sys.version_info[:3])}
+
+
+def load(params):
+    """
+    拡張機能を読み込む（読み込み済みの場合は再読み込み、失敗したら古いモジュールを使い続ける）
+    """
+    extension_id = params["extension_id"]
+    extension_dir = params["extension_dir"]
+    main_path = os.path.join(extension_dir, params["main_file"])
+    permissions = Permissions(extension_dir, params.get("permissions", []))
+    module_name = _module_name_for(extension_id)
+
+    with open(main_path, encoding="utf-8") as file:
+        code = file.read()
+
+    previous = _extensions.get(extension_id)
+    sys.modules.pop(module_name, None)
+    module = types.ModuleType(module_name)
+    module.__file__ = main_path
+    sys.modules[module_name] = module
+
+    try:
+        compiled = compile(code, main_path, "exec")
+        _with_context(extension_id, permissions, None, exec, compiled, module.__dict__)
+        if not hasattr(module, "on_event"):
+            raise RpcError(-32000, "on_event function not found in extension '{}'".format(extension_id))
+    except BaseException as e:
+        if previous is not None:
+            sys.modules[module_name] = previous[1]
+        else:
+            sys.modules.pop(module_name, None)
+        if isinstance(e, RpcError):
+            raise
+        raise RpcError(-32000, "Failed to load extension '{}': {}".format(extension_id, _describe(e)))
+
+    _extensions[extension_id] = (module_name, module, permissions)
+    return None
+
+
+def unload(params):
+    removed = _extensions.pop(params["extension_id"], None)
+    if removed is None:
+        return False
+    sys.modules.pop(removed[0], None)
+    return True
+
+
+def call(params):
+    """
+    拡張機能の関数（on_event / on_command）を文字列引数で呼び出す
+    戻り値は文字列（JSON）またはNone
+    """
+    extension_id = params["extension_id"]
+    function = params["function"]
+    if extension_id not in _extensions:
+        raise RpcError(-32000, "Extension '{}' is not loaded".format(extension_id))
+    _, module, permissions = _extensions[extension_id]
+
+    target = getattr(module, function, None)
+    if target is None:
+        raise RpcError(-32000, "{} function not found in extension '{}'".format(function, extension_id))
+
+    try:
+        result = _with_context(extension_id, permissions, params.get("editor"), target, *params.get("args", []))
+    except Exception as e:
+        raise RpcError(-32000, "Extension '{}' error in {}: {}".format(extension_id, function, _describe(e)))
+
+    if result is None:
+        return None
+    if not isinstance(result, str):
+        raise RpcError(-32000, "Extension '{}' returned a non-string value: {}".format(
+            extension_id, type(result).__name__))
+    return result
+
+
+def _describe(error):
+    return "".join(traceback.format_exception_only(type(error), error)).strip()
+
+
+METHODS = {
+    "initialize": initialize,
+    "load": load,
+    "unload": unload,
+    "call": call,
+}
+
+
+def main():
+    sys.modules["vinsert"] = _create_vinsert_module()
+
+    while True:
+        message = _read()
+        if message is None:
+            break
+
+        request_id = message.get("id")
+        method = METHODS.get(message.get("method"))
+        if method is None:
+            _send({"id": request_id, "error": {"code": -32601, "message": "Method not found"}})
+            continue
+
+        try:
+            _send({"id": request_id, "result": method(message.get("params") or {})})
+        except RpcError as e:
+            _send({"id": request_id, "error": {"code": e.code, "message": e.message}})
+        except (KeyError, TypeError) as e:
+            _send({"id": request_id, "error": {"code": -32602, "message": "Invalid params: {}".format(e)}})
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,455 @@
+/*
+ * =====================================================
+ * Vinsert Editor - ローカルの拡張機能レジストリ
+ * ネットワークドライブやgitのチェックアウトなど、index.jsonを置いたディレクトリを
+ * レジストリとして登録し、そこから拡張機能をインストール・更新する
+ * アーカイブはSHA-256を確認してから展開する
+ * =====================================================
+ */
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use tauri::Manager;
+
+use crate::extension_host::{self, ExtensionHost};
+use crate::extension_installer::{self, InstallOutcome, WorkDir};
+use crate::extension_manifest;
+use crate::extension_menu;
+use crate::python_exec::PythonJobs;
+
+/// レジストリのディレクトリに置く拡張機能の一覧
+const INDEX_FILE: &str = "index.json";
+
+/**
+ * 登録したレジストリ（extension_registries.json）
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RegistrySettings {
+    /// index.jsonを置いたディレクトリ（先に登録したものを優先する）
+    #[serde(default)]
+    pub directories: Vec<String>,
+}
+
+/**
+ * index.jsonの形式
+ *
+ *   {
+ *     "extensions": [
+ *       {
+ *         "id": "html-support",
+ *         "name": "HTML Support",
+ *         "description": "...",
+ *         "versions": [
+ *           { "version": "1.2.0", "archive": "archives/html-support-1.2.0.zip", "sha256": "..." }
+ *         ]
+ *       }
+ *     ]
+ *   }
+ *
+ * archiveはレジストリのディレクトリからの相対パス
+ */
+#[derive(Debug, Deserialize)]
+struct RegistryIndex {
+    #[serde(default)]
+    extensions: Vec<IndexEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IndexEntry {
+    id: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    versions: Vec<IndexVersion>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct IndexVersion {
+    version: String,
+    archive: String,
+    sha256: String,
+}
+
+/**
+ * レジストリにある拡張機能の1つのバージョン
+ */
+#[derive(Debug, Clone)]
+struct Candidate {
+    registry: PathBuf,
+    id: String,
+    name: String,
+    description: String,
+    version: semver::Version,
+    archive: String,
+    sha256: String,
+}
+
+/**
+ * list_registry_extensionsが返す拡張機能
+ * versionsは新しい順、registryは最新バージョンを提供するレジストリ
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct RegistryExtension {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub latest_version: String,
+    pub versions: Vec<String>,
+    pub registry: String,
+}
+
+/**
+ * list_registry_extensionsの戻り値
+ * errorsは読めなかったレジストリやindex.jsonの項目（それ以外の拡張機能は一覧に含める）
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct RegistryListing {
+    pub extensions: Vec<RegistryExtension>,
+    pub errors: Vec<String>,
+}
+
+/**
+ * インストール済みのバージョンとレジストリの最新バージョンの比較
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RegistryStatus {
+    NotInstalled,
+    UpToDate,
+    UpgradeAvailable,
+    /// インストール済みの方が新しい（またはバージョンを読めない）
+    InstalledNewer,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RegistryDiff {
+    pub id: String,
+    pub name: String,
+    pub installed_version: Option<String>,
+    pub latest_version: String,
+    pub status: RegistryStatus,
+    pub registry: String,
+}
+
+fn settings_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    Ok(app_data.join("vinsert").join("extension_registries.json"))
+}
+
+fn load_settings(path: &Path) -> RegistrySettings {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_settings(path: &Path, settings: &RegistrySettings) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create '{}': {}", parent.display(), e))?;
+    }
+    let content = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize extension registries: {}", e))?;
+    fs::write(path, content).map_err(|e| format!("Failed to write '{}': {}", path.display(), e))
+}
+
+/**
+ * 1つのレジストリのindex.jsonを読み、拡張機能のバージョンを一覧にする
+ * 不正な項目は飛ばしてerrorsに追加する
+ */
+fn read_index(registry: &Path, errors: &mut Vec<String>) -> Vec<Candidate> {
+    let index_path = registry.join(INDEX_FILE);
+    let index: RegistryIndex = match fs::read_to_string(&index_path)
+        .map_err(|e| e.to_string())
+        .and_then(|content| serde_json::from_str(&content).map_err(|e| e.to_string()))
+    {
+        Ok(index) => index,
+        Err(e) => {
+            errors.push(format!("{}: {}", index_path.display(), e));
+            return Vec::new();
+        }
+    };
+
+    let mut candidates = Vec::new();
+    for entry in index.extensions {
+        for version in entry.versions {
+            match semver::Version::parse(version.version.trim()) {
+                Ok(parsed) => candidates.push(Candidate {
+                    registry: registry.to_path_buf(),
+                    id: entry.id.clone(),
+                    name: entry.name.clone().unwrap_or_else(|| entry.id.clone()),
+                    description: entry.description.clone().unwrap_or_default(),
+                    version: parsed,
+                    archive: version.archive,
+                    sha256: version.sha256,
+                }),
+                Err(e) => errors.push(format!(
+                    "{}: invalid version '{}' of '{}': {}",
+                    index_path.display(),
+                    version.version,
+                    entry.id,
+                    e
+                )),
+            }
+        }
+    }
+    candidates
+}
+
+/**
+ * 登録したすべてのレジストリのバージョンを拡張機能IDごとにまとめる
+ * 同じバージョンが複数のレジストリにある場合は先に登録したレジストリのものを使う
+ */
+fn collect(settings: &RegistrySettings) -> (BTreeMap<String, Vec<Candidate>>, Vec<String>) {
+    let mut errors = Vec::new();
+    let mut by_id: BTreeMap<String, Vec<Candidate>> = BTreeMap::new();
+    for directory in &settings.directories {
+        for candidate in read_index(Path::new(directory), &mut errors) {
+            let versions = by_id.entry(candidate.id.clone()).or_default();
+            if !versions.iter().any(|existing| existing.version == candidate.version) {
+                versions.push(candidate);
+            }
+        }
+    }
+    for versions in by_id.values_mut() {
+        versions.sort_by(|a, b| b.version.cmp(&a.version));
+    }
+    (by_id, errors)
+}
+
+/**
+ * レジストリのディレクトリからの相対パスとしてアーカイブのパスを解決する
+ * 絶対パスや .. を含むパスはレジストリの外を指すため拒否する
+ */
+fn archive_path(candidate: &Candidate) -> Result<PathBuf, String> {
+    let relative = Path::new(&candidate.archive);
+    let is_contained = relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if candidate.archive.is_empty() || !is_contained {
+        return Err(format!(
+            "Archive path '{}' of '{}' must be relative to the registry",
+            candidate.archive, candidate.id
+        ));
+    }
+    Ok(candidate.registry.join(relative))
+}
+
+/**
+ * アーカイブを作業用ディレクトリへコピーしながらSHA-256を計算する
+ * 確認した後にレジストリ側のファイルが置き換えられても影響しないよう、展開はコピーから行う
+ */
+fn copy_with_digest(source: &Path, destination: &Path) -> Result<String, String> {
+    let mut input = fs::File::open(source).map_err(|e| format!("Failed to open '{}': {}", source.display(), e))?;
+    let mut output =
+        fs::File::create(destination).map_err(|e| format!("Failed to create '{}': {}", destination.display(), e))?;
+
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let read = match input.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(format!("Failed to read '{}': {}", source.display(), e)),
+        };
+        hasher.update(&buffer[..read]);
+        output
+            .write_all(&buffer[..read])
+            .map_err(|e| format!("Failed to write '{}': {}", destination.display(), e))?;
+    }
+
+    Ok(hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/**
+ * レジストリのアーカイブを検証してインストールする
+ */
+fn install_candidate(
+    extensions_dir: &Path,
+    candidate: &Candidate,
+    allow_downgrade: bool,
+) -> Result<InstallOutcome, String> {
+    let source = archive_path(candidate)?;
+    fs::create_dir_all(extensions_dir)
+        .map_err(|e| format!("Failed to create '{}': {}", extensions_dir.display(), e))?;
+    let work = WorkDir::create(extensions_dir, "registry")?;
+    let local = work.path().join("archive.zip");
+
+    let digest = copy_with_digest(&source, &local)?;
+    let expected = candidate.sha256.trim().to_ascii_lowercase();
+    if digest != expected {
+        return Err(format!(
+            "Checksum mismatch for '{}': expected {}, got {}",
+            source.display(),
+            expected,
+            digest
+        ));
+    }
+    println!("🔐 Verified {} {} ({})", candidate.id, candidate.version, digest);
+
+    extension_installer::install_from_archive(
+        extensions_dir,
+        &local,
+        allow_downgrade,
+        Some((&candidate.id, &candidate.version)),
+    )
+}
+
+fn status_of(installed: Option<&semver::Version>, installed_exists: bool, latest: &semver::Version) -> RegistryStatus {
+    match installed {
+        None if !installed_exists => RegistryStatus::NotInstalled,
+        Some(installed) if installed < latest => RegistryStatus::UpgradeAvailable,
+        Some(installed) if installed == latest => RegistryStatus::UpToDate,
+        _ => RegistryStatus::InstalledNewer,
+    }
+}
+
+// =====================================================
+// Tauriコマンド
+// =====================================================
+
+/**
+ * 登録したレジストリのディレクトリを返す
+ */
+#[tauri::command]
+pub fn get_extension_registries(app: tauri::AppHandle) -> Result<RegistrySettings, String> {
+    Ok(load_settings(&settings_path(&app)?))
+}
+
+/**
+ * レジストリのディレクトリを登録する（index.jsonがあるディレクトリのみ）
+ */
+#[tauri::command]
+pub fn set_extension_registries(app: tauri::AppHandle, directories: Vec<String>) -> Result<(), String> {
+    let mut settings = RegistrySettings::default();
+    for directory in directories.iter().map(|dir| dir.trim()).filter(|dir| !dir.is_empty()) {
+        if !Path::new(directory).join(INDEX_FILE).is_file() {
+            return Err(format!("{} not found in '{}'", INDEX_FILE, directory));
+        }
+        if !settings.directories.iter().any(|existing| existing == directory) {
+            settings.directories.push(directory.to_string());
+        }
+    }
+
+    save_settings(&settings_path(&app)?, &settings)?;
+    println!("📚 Extension registries: {:?}", settings.directories);
+    Ok(())
+}
+
+/**
+ * レジストリにある拡張機能の一覧
+ */
+#[tauri::command]
+pub async fn list_registry_extensions(app: tauri::AppHandle) -> Result<RegistryListing, String> {
+    let settings = load_settings(&settings_path(&app)?);
+    let (by_id, errors) = tauri::async_runtime::spawn_blocking(move || collect(&settings))
+        .await
+        .map_err(|e| format!("Registry task failed: {}", e))?;
+
+    let extensions = by_id
+        .into_values()
+        .filter_map(|versions| {
+            let latest = versions.first()?;
+            Some(RegistryExtension {
+                id: latest.id.clone(),
+                name: latest.name.clone(),
+                description: latest.description.clone(),
+                latest_version: latest.version.to_string(),
+                versions: versions.iter().map(|candidate| candidate.version.to_string()).collect(),
+                registry: latest.registry.display().to_string(),
+            })
+        })
+        .collect();
+    Ok(RegistryListing { extensions, errors })
+}
+
+/**
+ * レジストリの最新バージョンとインストール済みのバージョンを比較する
+ */
+#[tauri::command]
+pub async fn diff_registry_extensions(app: tauri::AppHandle) -> Result<Vec<RegistryDiff>, String> {
+    let settings = load_settings(&settings_path(&app)?);
+    let extensions_dir = extension_host::extensions_directory(&app)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let (by_id, errors) = collect(&settings);
+        for error in errors {
+            println!("⚠️ {}", error);
+        }
+
+        by_id
+            .into_values()
+            .filter_map(|versions| {
+                let latest = versions.first()?;
+                let installed_dir = extensions_dir.join(&latest.id);
+                let installed = extension_installer::installed_version(&installed_dir);
+                Some(RegistryDiff {
+                    id: latest.id.clone(),
+                    name: latest.name.clone(),
+                    installed_version: installed.as_ref().map(|version| version.to_string()),
+                    latest_version: latest.version.to_string(),
+                    status: status_of(installed.as_ref(), installed_dir.is_dir(), &latest.version),
+                    registry: latest.registry.display().to_string(),
+                })
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| format!("Registry task failed: {}", e))
+}
+
+/**
+ * レジストリから拡張機能をインストール・更新する
+ * versionを省略すると最新バージョンをインストールする
+ * インストール済みの方が新しい場合の扱いはinstall_extension_from_archiveと同じ
+ */
+#[tauri::command]
+pub async fn install_registry_extension(
+    app: tauri::AppHandle,
+    host: tauri::State<'_, ExtensionHost>,
+    jobs: tauri::State<'_, PythonJobs>,
+    id: String,
+    version: Option<String>,
+    allow_downgrade: Option<bool>,
+) -> Result<InstallOutcome, String> {
+    extension_manifest::validate_extension_id(&id)?;
+    let settings = load_settings(&settings_path(&app)?);
+    let extensions_dir = extension_host::extensions_directory(&app)?;
+    let requested = version
+        .map(|version| {
+            semver::Version::parse(version.trim()).map_err(|e| format!("Invalid version '{}': {}", version, e))
+        })
+        .transpose()?;
+
+    let outcome = tauri::async_runtime::spawn_blocking(move || {
+        let (mut by_id, _) = collect(&settings);
+        let versions = by_id
+            .remove(&id)
+            .ok_or_else(|| format!("Extension '{}' is not in any registry", id))?;
+        let candidate = match &requested {
+            Some(requested) => versions
+                .iter()
+                .find(|candidate| &candidate.version == requested)
+                .ok_or_else(|| format!("Version {} of '{}' is not in any registry", requested, id))?,
+            None => &versions[0],
+        };
+        install_candidate(&extensions_dir, candidate, allow_downgrade.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| format!("Install task failed: {}", e))??;
+
+    if let InstallOutcome::Installed { id, .. } = &outcome {
+        extension_installer::unload(&host, &jobs, id).await;
+        extension_menu::refresh(&app);
+    }
+    Ok(outcome)
+}
@@ -0,0 +1,328 @@
+/*
+ * =====================================================
+ * Vinsert Editor - 拡張機能の署名の検証
+ * 拡張機能のルートに置いたsignature.json（ed25519の署名）を、
+ * アプリデータに保存した信頼する公開鍵で検証する
+ * インストール時と読み込み時に確認し、設定により署名のない拡張機能を拒否する
+ *
+ * signature.json:
+ *   { "public_key": "<公開鍵32バイトのBase64>", "signature": "<署名64バイトのBase64>" }
+ *
+ * 署名の対象は、signature.jsonを除くすべてのファイルについて
+ * "<SHA-256の16進数>  <拡張機能のルートからの相対パス（/区切り）>\n" をパスの昇順に連結したテキスト
+ * （拡張機能のディレクトリで sha256sum を実行した出力と同じ形式）
+ * __pycache__は署名した.pyの代わりに読み込まれ得るため、含まれていれば改ざんとして扱う
+ * （エディタは拡張機能の読み込み時にバイトコードを書き込まない）
+ * =====================================================
+ */
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tauri::Manager;
+
+/// 拡張機能のルートに置く署名ファイル
+pub const SIGNATURE_FILE: &str = "signature.json";
+
+/// Pythonのバイトコードのキャッシュ（署名した拡張機能に含まれていれば改ざんとして扱う）
+const PYCACHE_DIR: &str = "__pycache__";
+
+static TRUST_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+// 設定ファイルの同時書き込みを防ぐ
+static TRUST_LOCK: Mutex<()> = Mutex::new(());
+
+/**
+ * 信頼する公開鍵
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustedKey {
+    pub name: String,
+    /// 公開鍵（32バイト）のBase64
+    pub public_key: String,
+}
+
+/**
+ * 署名の検証設定（trusted_keys.json）
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrustSettings {
+    /// trueの場合は信頼する鍵で署名された拡張機能だけをインストール・読み込みする
+    #[serde(default)]
+    pub require_signature: bool,
+    #[serde(default)]
+    pub keys: Vec<TrustedKey>,
+}
+
+/**
+ * 署名の検証結果
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SignatureStatus {
+    /// 信頼する鍵（keyはその名前）で署名され、内容も一致する
+    Signed { key: String },
+    /// signature.jsonがない
+    Unsigned,
+    /// 署名は正しいが、信頼する鍵の一覧にない鍵で署名されている
+    Untrusted { public_key: String },
+    /// 署名が内容と一致しない、または署名ファイルが壊れている
+    Tampered { reason: String },
+    /// 信頼する鍵の設定（trusted_keys.json）を読み込めないため検証できない
+    Unverifiable { reason: String },
+}
+
+#[derive(Debug, Deserialize)]
+struct SignatureFile {
+    public_key: String,
+    signature: String,
+}
+
+/**
+ * アプリケーション起動時に設定ファイルの場所を登録する
+ */
+pub fn initialize(app: &tauri::AppHandle) {
+    match trust_path(app) {
+        Ok(path) => {
+            let _ = TRUST_PATH.set(path);
+        }
+        Err(e) => println!("⚠️ Trusted extension keys are not available: {}", e),
+    }
+}
+
+fn trust_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    Ok(app_data.join("vinsert").join("trusted_keys.json"))
+}
+
+/**
+ * 署名の検証設定を読み込む（未設定なら署名を要求せず、信頼する鍵もない）
+ * ファイルがあるのに読み込めない場合は、署名の要求を無視しないようエラーを返す
+ */
+fn load_settings() -> Result<TrustSettings, String> {
+    let path = match TRUST_PATH.get() {
+        Some(path) => path,
+        None => return Ok(TrustSettings::default()),
+    };
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(TrustSettings::default()),
+        Err(e) => return Err(format!("Failed to read '{}': {}", path.display(), e)),
+    };
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse '{}': {}", path.display(), e))
+}
+
+fn save_settings(settings: &TrustSettings) -> Result<(), String> {
+    let path = TRUST_PATH
+        .get()
+        .ok_or_else(|| "Trusted extension keys are not available".to_string())?;
+    let _guard = TRUST_LOCK.lock().map_err(|e| e.to_string())?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create '{}': {}", parent.display(), e))?;
+    }
+    let content = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize trusted keys: {}", e))?;
+    fs::write(path, content).map_err(|e| format!("Failed to write '{}': {}", path.display(), e))
+}
+
+fn decode_public_key(encoded: &str) -> Result<VerifyingKey, String> {
+    let bytes = BASE64
+        .decode(encoded.trim())
+        .map_err(|e| format!("Invalid public key: {}", e))?;
+    let bytes: [u8; 32] = bytes
+        .try_into()
+        .map_err(|bytes: Vec<u8>| format!("Invalid public key: expected 32 bytes, got {}", bytes.len()))?;
+    VerifyingKey::from_bytes(&bytes).map_err(|e| format!("Invalid public key: {}", e))
+}
+
+fn decode_signature(encoded: &str) -> Result<Signature, String> {
+    let bytes = BASE64
+        .decode(encoded.trim())
+        .map_err(|e| format!("Invalid signature: {}", e))?;
+    let bytes: [u8; 64] = bytes
+        .try_into()
+        .map_err(|bytes: Vec<u8>| format!("Invalid signature: expected 64 bytes, got {}", bytes.len()))?;
+    Ok(Signature::from_bytes(&bytes))
+}
+
+/**
+ * 署名の対象のファイルを集める（extension_dirからの相対パス）
+ * シンボリックリンクは内容を特定できないため、__pycache__は署名した.pyの代わりに読み込まれ得るためエラーにする
+ */
+fn collect_files(extension_dir: &Path, dir: &Path, files: &mut Vec<String>) -> Result<(), String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read '{}': {}", dir.display(), e))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read '{}': {}", dir.display(), e))?;
+        let path = entry.path();
+        let file_type = entry
+            .file_type()
+            .map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+        let relative = path
+            .strip_prefix(extension_dir)
+            .map_err(|e| e.to_string())?
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        if file_type.is_symlink() {
+            return Err(format!("'{}' is a symbolic link", relative));
+        } else if file_type.is_dir() {
+            if entry.file_name() == PYCACHE_DIR {
+                return Err(format!("'{}' contains compiled Python bytecode that is not signed", relative));
+            }
+            collect_files(extension_dir, &path, files)?;
+        } else if relative != SIGNATURE_FILE {
+            files.push(relative);
+        }
+    }
+    Ok(())
+}
+
+/**
+ * 署名の対象のテキスト（ファイルごとのSHA-256とパスの一覧）
+ */
+pub fn signed_content(extension_dir: &Path) -> Result<String, String> {
+    let mut files = Vec::new();
+    collect_files(extension_dir, extension_dir, &mut files)?;
+    files.sort();
+
+    let mut content = String::new();
+    for relative in files {
+        let data = fs::read(extension_dir.join(&relative))
+            .map_err(|e| format!("Failed to read '{}': {}", relative, e))?;
+        let digest: String = Sha256::digest(&data).iter().map(|byte| format!("{:02x}", byte)).collect();
+        content.push_str(&format!("{}  {}\n", digest, relative));
+    }
+    Ok(content)
+}
+
+/**
+ * 拡張機能ディレクトリの署名を検証する
+ */
+pub fn verify(extension_dir: &Path, settings: &TrustSettings) -> SignatureStatus {
+    let signature_path = extension_dir.join(SIGNATURE_FILE);
+    if !signature_path.is_file() {
+        return SignatureStatus::Unsigned;
+    }
+
+    let result = (|| -> Result<SignatureStatus, String> {
+        let content = fs::read_to_string(&signature_path)
+            .map_err(|e| format!("Failed to read {}: {}", SIGNATURE_FILE, e))?;
+        let file: SignatureFile = serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid {}: {}", SIGNATURE_FILE, e))?;
+        let public_key = decode_public_key(&file.public_key)?;
+        let signature = decode_signature(&file.signature)?;
+
+        let signed = signed_content(extension_dir)?;
+        public_key
+            .verify_strict(signed.as_bytes(), &signature)
+            .map_err(|_| "signature does not match the extension files".to_string())?;
+
+        let trusted = settings.keys.iter().find(|key| {
+            decode_public_key(&key.public_key)
+                .map(|key| key == public_key)
+                .unwrap_or(false)
+        });
+        Ok(match trusted {
+            Some(key) => SignatureStatus::Signed { key: key.name.clone() },
+            None => SignatureStatus::Untrusted {
+                public_key: BASE64.encode(public_key.as_bytes()),
+            },
+        })
+    })();
+
+    result.unwrap_or_else(|reason| SignatureStatus::Tampered { reason })
+}
+
+/**
+ * 拡張機能ディレクトリの署名を現在の設定で検証する（一覧表示用）
+ */
+pub fn status(extension_dir: &Path) -> SignatureStatus {
+    match load_settings() {
+        Ok(settings) => verify(extension_dir, &settings),
+        Err(reason) => SignatureStatus::Unverifiable { reason },
+    }
+}
+
+/**
+ * インストール・読み込みの前に署名を確認する
+ * 署名が内容と一致しない拡張機能は常に、署名のない（信頼する鍵で署名されていない）拡張機能は
+ * require_signatureが有効な場合に拒否する
+ */
+pub fn check(id: &str, extension_dir: &Path) -> Result<SignatureStatus, String> {
+    let settings = load_settings().map_err(|e| format!("Extension '{}' cannot be verified: {}", id, e))?;
+    let status = verify(extension_dir, &settings);
+
+    match &status {
+        SignatureStatus::Signed { key } => println!("🔏 Extension {} is signed by {}", id, key),
+        SignatureStatus::Tampered { reason } => {
+            return Err(format!("Extension '{}' failed signature verification: {}", id, reason));
+        }
+        SignatureStatus::Unsigned if settings.require_signature => {
+            return Err(format!("Extension '{}' is not signed", id));
+        }
+        SignatureStatus::Untrusted { .. } if settings.require_signature => {
+            return Err(format!("Extension '{}' is signed with a key that is not trusted", id));
+        }
+        SignatureStatus::Unsigned | SignatureStatus::Untrusted { .. } => {}
+        // verifyは設定を読み込めた場合のみ呼ばれる
+        SignatureStatus::Unverifiable { reason } => {
+            return Err(format!("Extension '{}' cannot be verified: {}", id, reason));
+        }
+    }
+    Ok(status)
+}
+
+// =====================================================
+// Tauriコマンド
+// =====================================================
+
+/**
+ * 署名の検証設定（信頼する公開鍵と、署名のない拡張機能を拒否するかどうか）を返す
+ */
+#[tauri::command]
+pub fn get_extension_trust() -> Result<TrustSettings, String> {
+    load_settings()
+}
+
+/**
+ * 署名の検証設定を検証して保存する
+ * 変更後に読み込む拡張機能から反映される（読み込み済みの拡張機能はそのまま）
+ */
+#[tauri::command]
+pub fn set_extension_trust(settings: TrustSettings) -> Result<(), String> {
+    let mut keys: Vec<TrustedKey> = Vec::with_capacity(settings.keys.len());
+    for key in settings.keys {
+        let name = key.name.trim().to_string();
+        if name.is_empty() {
+            return Err("Trusted key name must not be empty".to_string());
+        }
+        let public_key = decode_public_key(&key.public_key).map_err(|e| format!("{}: {}", name, e))?;
+        keys.push(TrustedKey {
+            name,
+            public_key: BASE64.encode(public_key.as_bytes()),
+        });
+    }
+
+    let settings = TrustSettings {
+        require_signature: settings.require_signature,
+        keys,
+    };
+    save_settings(&settings)?;
+    println!(
+        "🔏 Trusted extension keys: {} (signature required: {})",
+        settings.keys.len(),
+        settings.require_signature
+    );
+    Ok(())
+}
@@ -0,0 +1,261 @@
+/*
+ * =====================================================
+ * Vinsert Editor - 拡張機能の実行時間の計測
+ * 拡張機能ごと・イベントごとに処理時間とエラー数を記録し、
+ * イベント処理が続けて目安の時間を超える拡張機能を警告・自動で無効化する
+ * =====================================================
+ */
+
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::extension_host::ExtensionHost;
+
+/// 処理が遅い拡張機能を通知するイベント名
+pub const SLOW_EXTENSION_EVENT: &str = "extension://slow";
+
+/// イベント処理1回あたりの目安の時間（キー入力ごとに呼ばれるため短めにする）
+pub const LATENCY_BUDGET_MS: u64 = 100;
+
+/// 続けて目安を超えた回数がこの回数になったら警告する
+const WARN_AFTER: u32 = 3;
+
+/// 続けて目安を超えた回数がこの回数になったら無効化する
+const DISABLE_AFTER: u32 = 10;
+
+/// パーセンタイルの計算に使う直近のサンプル数
+const MAX_SAMPLES: usize = 256;
+
+/**
+ * 1回の呼び出しを記録した結果
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlowVerdict {
+    Ok,
+    /// 続けて目安を超えたので警告する
+    Warn,
+    /// 警告後も目安を超え続けたので無効化する
+    Disable,
+}
+
+/**
+ * 1つのイベント（またはコマンド）の計測値
+ */
+#[derive(Default)]
+struct EventTimings {
+    count: u64,
+    errors: u64,
+    /// 目安の時間を超えた回数
+    slow: u64,
+    max: Duration,
+    /// 直近のサンプル（古いものから捨てる）
+    samples: VecDeque<Duration>,
+    /// 続けて目安を超えている回数
+    streak: u32,
+}
+
+impl EventTimings {
+    fn summary(&self, event: &str) -> EventStats {
+        let mut sorted: Vec<Duration> = self.samples.iter().copied().collect();
+        sorted.sort();
+        EventStats {
+            event: event.to_string(),
+            count: self.count,
+            errors: self.errors,
+            slow: self.slow,
+            p50_ms: percentile(&sorted, 50),
+            p95_ms: percentile(&sorted, 95),
+            max_ms: as_ms(self.max),
+        }
+    }
+}
+
+/**
+ * get_extension_statsが返すイベントごとの計測値
+ * eventはイベント名（コマンドは"command:<id>"、読み込み時などはPythonの関数名）
+ * p50_ms/p95_msは直近のサンプルから、count/errors/slow/max_msは起動してからの合計で求める
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct EventStats {
+    pub event: String,
+    pub count: u64,
+    pub errors: u64,
+    pub slow: u64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+}
+
+/**
+ * 拡張機能ごとの計測値
+ * auto_disabledは処理が遅いため無効化され、次に明示的に読み込むまで呼び出しを拒否しているかどうか
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct ExtensionStatsSummary {
+    pub extension_id: String,
+    pub auto_disabled: bool,
+    pub events: Vec<EventStats>,
+}
+
+/**
+ * get_extension_statsの戻り値
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct ExtensionStatsReport {
+    pub budget_ms: u64,
+    pub extensions: Vec<ExtensionStatsSummary>,
+}
+
+/**
+ * 処理が遅い拡張機能の通知内容
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct SlowExtensionEvent {
+    pub extension_id: String,
+    pub event: String,
+    pub elapsed_ms: f64,
+    pub budget_ms: u64,
+    /// 無効化したかどうか（falseなら警告のみ）
+    pub disabled: bool,
+}
+
+/**
+ * 実行時間の記録（ExtensionHostが保持し、cloneで同じ記録を共有する）
+ */
+#[derive(Clone, Default)]
+pub struct ExtensionStats {
+    timings: Arc<Mutex<HashMap<String, HashMap<String, EventTimings>>>>,
+    disabled: Arc<Mutex<HashSet<String>>>,
+}
+
+impl ExtensionStats {
+    pub fn new() -> Self {
+        ExtensionStats::default()
+    }
+
+    /**
+     * 1回の呼び出しを記録する
+     * budgetedがtrueの呼び出し（イベント処理）だけ、続けて目安を超えた回数で警告・無効化を判定する
+     */
+    pub fn record(&self, id: &str, event: &str, elapsed: Duration, succeeded: bool, budgeted: bool) -> SlowVerdict {
+        let mut timings = match self.timings.lock() {
+            Ok(timings) => timings,
+            Err(_) => return SlowVerdict::Ok,
+        };
+        let entry = timings
+            .entry(id.to_string())
+            .or_default()
+            .entry(event.to_string())
+            .or_default();
+
+        entry.count += 1;
+        if !succeeded {
+            entry.errors += 1;
+        }
+        entry.max = entry.max.max(elapsed);
+        if entry.samples.len() == MAX_SAMPLES {
+            entry.samples.pop_front();
+        }
+        entry.samples.push_back(elapsed);
+
+        if elapsed <= Duration::from_millis(LATENCY_BUDGET_MS) {
+            entry.streak = 0;
+            return SlowVerdict::Ok;
+        }
+        entry.slow += 1;
+        if !budgeted {
+            return SlowVerdict::Ok;
+        }
+
+        entry.streak += 1;
+        match entry.streak {
+            DISABLE_AFTER => {
+                entry.streak = 0;
+                SlowVerdict::Disable
+            }
+            WARN_AFTER => SlowVerdict::Warn,
+            _ => SlowVerdict::Ok,
+        }
+    }
+
+    /**
+     * 処理が遅いため無効化した拡張機能として記録する
+     */
+    pub fn mark_disabled(&self, id: &str) {
+        if let Ok(mut disabled) = self.disabled.lock() {
+            disabled.insert(id.to_string());
+        }
+    }
+
+    /**
+     * 無効化の記録を取り消す（ユーザーが明示的に読み込み直したとき）
+     */
+    pub fn clear_disabled(&self, id: &str) {
+        if let Ok(mut disabled) = self.disabled.lock() {
+            disabled.remove(id);
+        }
+    }
+
+    pub fn is_disabled(&self, id: &str) -> bool {
+        self.disabled
+            .lock()
+            .map(|disabled| disabled.contains(id))
+            .unwrap_or(false)
+    }
+
+    /**
+     * 記録した計測値の一覧（拡張機能ID、イベント名の順）
+     */
+    pub fn report(&self) -> ExtensionStatsReport {
+        let mut extensions = Vec::new();
+        if let Ok(timings) = self.timings.lock() {
+            for (id, events) in timings.iter() {
+                let mut events: Vec<EventStats> = events
+                    .iter()
+                    .map(|(event, timings)| timings.summary(event))
+                    .collect();
+                events.sort_by(|a, b| a.event.cmp(&b.event));
+                extensions.push(ExtensionStatsSummary {
+                    extension_id: id.clone(),
+                    auto_disabled: self.is_disabled(id),
+                    events,
+                });
+            }
+        }
+        extensions.sort_by(|a, b| a.extension_id.cmp(&b.extension_id));
+
+        ExtensionStatsReport {
+            budget_ms: LATENCY_BUDGET_MS,
+            extensions,
+        }
+    }
+}
+
+/**
+ * 昇順に並べたサンプルのパーセンタイル（最近傍順位法）
+ */
+fn percentile(sorted: &[Duration], percent: usize) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (sorted.len() * percent).div_ceil(100).max(1);
+    as_ms(sorted[rank - 1])
+}
+
+fn as_ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+// =====================================================
+// Tauriコマンド
+// =====================================================
+
+/**
+ * 拡張機能ごと・イベントごとの実行時間（回数、p50、p95、最大）とエラー数を取得する
+ */
+#[tauri::command]
+pub fn get_extension_stats(host: tauri::State<'_, ExtensionHost>) -> ExtensionStatsReport {
+    host.stats().report()
+}
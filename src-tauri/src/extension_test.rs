@@ -0,0 +1,367 @@
+/*
+ * =====================================================
+ * Vinsert Editor - 拡張機能のヘッドレステスト
+ * vinsert --test-extension <dir> [--fixture <file>]... [--python-env <file>]
+ * <dir>のディレクトリ名は、エディタで読み込む場合と同じくsetting.jsonのidと一致している必要がある
+ * ウィンドウを作らずに拡張機能を読み込み、フィクスチャ（JSON）のイベントを順に送って
+ * 拡張機能が返した・送信した操作（insert_text、show_suggestions など）を期待値と比較する
+ * Python環境はエディタと同じく保存された設定（python_environment.json）を適用し、
+ * --python-envを指定した場合はそのファイルを代わりに使う
+ * すべて一致すれば終了コード0、一致しないステップがあれば1、読み込みなどに失敗した場合は2で終了する
+ *
+ * フィクスチャ（--fixtureを省略した場合は <dir>/tests 内の .json ファイル）:
+ *   {
+ *     "steps": [
+ *       {
+ *         "event": "text_input",
+ *         "payload": { "data": ">" },
+ *         "editor": { "text": "<div>", "selection_start": 5, "selection_end": 5 },
+ *         "expect": [ { "action": "insert_text", "text": "</div>" } ]
+ *       },
+ *       { "command": "wrap_selection", "expect_error": "not allowed" }
+ *     ]
+ *   }
+ *
+ * 各ステップはeventかcommandのどちらかを指定する
+ * editorのselection_start/selection_endはエディタと同じくtextareaのUTF-16単位で書く
+ * expectの各操作は、期待値に書いたキーだけを比較する（オブジェクトは再帰的に、それ以外は完全一致）
+ * expectを省略したステップは比較しない（前準備用）
+ * =====================================================
+ */
+
+use serde::Deserialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+use crate::extension_host::{self, ExtensionHost, EXTENSION_TIMEOUT_MS};
+use crate::extension_manifest::{self, DiagnosticSeverity};
+use crate::extension_permissions::{Permission, PermissionSet};
+use crate::extension_process::ProcessHostConfig;
+use crate::python_environment;
+use crate::python_exec::PythonJobs;
+use crate::python_runtime::PythonRuntime;
+use crate::vinsert_api::{self, EditorSnapshot, EXTENSION_ACTION_EVENT};
+
+/// テストモードで起動するコマンドライン引数
+pub const TEST_EXTENSION_FLAG: &str = "--test-extension";
+
+/// 使用するフィクスチャを指定する引数（複数指定可）
+const FIXTURE_FLAG: &str = "--fixture";
+
+/// 保存された設定の代わりに使うPython環境の設定ファイル（python_environment.jsonと同じ形式）
+const PYTHON_ENV_FLAG: &str = "--python-env";
+
+/// --fixtureを省略した場合にフィクスチャを探す拡張機能内のディレクトリ
+const FIXTURE_DIR: &str = "tests";
+
+const EXIT_PASSED: i32 = 0;
+const EXIT_FAILED: i32 = 1;
+const EXIT_ERROR: i32 = 2;
+
+#[derive(Debug, Deserialize)]
+struct Fixture {
+    #[serde(default)]
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Step {
+    #[serde(default)]
+    event: Option<String>,
+    #[serde(default)]
+    command: Option<String>,
+    #[serde(default)]
+    payload: Option<Value>,
+    #[serde(default)]
+    editor: Option<EditorSnapshot>,
+    #[serde(default)]
+    expect: Option<Vec<Value>>,
+    /// 失敗することを期待する場合のエラーメッセージ（の一部）
+    #[serde(default)]
+    expect_error: Option<String>,
+}
+
+struct Options {
+    extension_dir: PathBuf,
+    fixtures: Vec<PathBuf>,
+    python_env: Option<PathBuf>,
+}
+
+/**
+ * コマンドライン引数がテストモードの指定を含むか
+ */
+pub fn requested(args: &[String]) -> bool {
+    args.iter().any(|arg| arg == TEST_EXTENSION_FLAG)
+}
+
+/**
+ * Windowsのリリースビルドはwindows_subsystem = "windows"のため標準出力・標準エラーがない
+ * テストモードでは起動元のコンソールに接続して結果を表示する
+ */
+#[cfg(all(target_os = "windows", not(debug_assertions)))]
+pub fn attach_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    const STD_OUTPUT_HANDLE: u32 = -11i32 as u32;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+        fn GetStdHandle(std_handle: u32) -> *mut std::ffi::c_void;
+    }
+
+    // リダイレクトされている場合はそのまま使う
+    unsafe {
+        if GetStdHandle(STD_OUTPUT_HANDLE).is_null() {
+            AttachConsole(ATTACH_PARENT_PROCESS);
+        }
+    }
+}
+
+/**
+ * テストを実行して終了コードを返す
+ * identifierはアプリの識別子（保存されたPython環境の設定を探すのに使う）
+ */
+pub fn run(args: &[String], identifier: &str) -> i32 {
+    let result = parse_args(args).and_then(|options| run_fixtures(&options, identifier));
+    match result {
+        Ok(true) => EXIT_PASSED,
+        Ok(false) => EXIT_FAILED,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            EXIT_ERROR
+        }
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let usage = format!(
+        "Usage: vinsert {} <dir> [{} <file>]... [{} <file>]\n\
+         The name of <dir> must be the extension id in {}",
+        TEST_EXTENSION_FLAG,
+        FIXTURE_FLAG,
+        PYTHON_ENV_FLAG,
+        extension_manifest::MANIFEST_FILE
+    );
+    let mut extension_dir = None;
+    let mut fixtures = Vec::new();
+    let mut python_env = None;
+
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            TEST_EXTENSION_FLAG => {
+                extension_dir = Some(PathBuf::from(iter.next().ok_or_else(|| usage.clone())?));
+            }
+            FIXTURE_FLAG => fixtures.push(PathBuf::from(iter.next().ok_or_else(|| usage.clone())?)),
+            PYTHON_ENV_FLAG => python_env = Some(PathBuf::from(iter.next().ok_or_else(|| usage.clone())?)),
+            other => return Err(format!("Unknown argument '{}'\n{}", other, usage)),
+        }
+    }
+
+    let extension_dir = extension_dir.ok_or(usage)?;
+    if fixtures.is_empty() {
+        fixtures = find_fixtures(&extension_dir.join(FIXTURE_DIR))?;
+    }
+    Ok(Options { extension_dir, fixtures, python_env })
+}
+
+/**
+ * 拡張機能のtestsディレクトリにあるフィクスチャ（*.json、名前順）
+ */
+fn find_fixtures(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut fixtures: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read '{}': {}", dir.display(), e))?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|extension| extension == "json"))
+        .collect();
+    fixtures.sort();
+
+    if fixtures.is_empty() {
+        return Err(format!("No fixtures (*.json) found in '{}'", dir.display()));
+    }
+    Ok(fixtures)
+}
+
+/**
+ * 拡張機能を読み込み、すべてのフィクスチャを実行する
+ * 戻り値はすべてのステップが期待どおりだったかどうか
+ */
+fn run_fixtures(options: &Options, identifier: &str) -> Result<bool, String> {
+    extension_host::ensure_available()?;
+    // エディタの起動時と同じく、実行環境の検出前にPython環境を適用する
+    let python_environment = match &options.python_env {
+        Some(path) => python_environment::apply_file_strict(path)?,
+        None => python_environment::saved_settings_path(identifier)
+            .map(|path| python_environment::apply_file(&path))
+            .unwrap_or_default(),
+    };
+    let extension_dir = options
+        .extension_dir
+        .canonicalize()
+        .map_err(|e| format!("Extension directory '{}' not found: {}", options.extension_dir.display(), e))?;
+
+    // エディタと同じ検証を行い、読み込めない理由（ディレクトリ名とIDの不一致など）をすべて表示する
+    let (manifest, diagnostics) = extension_manifest::inspect_extension(&extension_dir);
+    let mut errors = Vec::new();
+    for diagnostic in &diagnostics {
+        let location = match (diagnostic.line, diagnostic.column) {
+            (Some(line), Some(column)) => format!(" ({}:{})", line, column),
+            _ => String::new(),
+        };
+        match diagnostic.severity {
+            DiagnosticSeverity::Error => errors.push(format!("{}{}", diagnostic.message, location)),
+            DiagnosticSeverity::Warning => println!("⚠️ {}{}", diagnostic.message, location),
+        }
+    }
+    let manifest = match manifest {
+        Some(manifest) if errors.is_empty() => manifest,
+        _ => {
+            return Err(format!(
+                "Invalid extension '{}':\n   {}",
+                extension_dir.display(),
+                errors.join("\n   ")
+            ))
+        }
+    };
+    let id = manifest.id.clone();
+
+    // テストでは宣言された権限をすべて許可する
+    let permissions = manifest
+        .permissions
+        .iter()
+        .map(|permission| Permission::parse(permission))
+        .collect::<Result<Vec<_>, _>>()?;
+    let permissions = PermissionSet::new(&id, &extension_dir, permissions);
+
+    // WebViewの代わりに操作を記録し、エディタと同じ拡張機能ホストで読み込む
+    vinsert_api::capture_events();
+    let host = ExtensionHost::new();
+    let mut runtime = PythonRuntime::detect();
+    runtime.environment = python_environment;
+    host.configure_process_hosts(ProcessHostConfig::from_runtime(&runtime));
+    let jobs = PythonJobs::new();
+
+    let loader = host.clone();
+    let load_id = id.clone();
+    jobs.run_blocking(None, Some(EXTENSION_TIMEOUT_MS), move |_py| {
+        loader.load(&load_id, extension_dir, permissions)
+    })
+    .map_err(|e| e.to_string())?;
+    vinsert_api::take_captured_events();
+
+    let mut passed = 0;
+    let mut failed = 0;
+    for fixture_path in &options.fixtures {
+        let fixture = read_fixture(fixture_path)?;
+        let name = fixture_path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        for (index, step) in fixture.steps.into_iter().enumerate() {
+            let label = format!("{} #{} {}", name, index + 1, step_name(&step));
+            match run_step(&host, &jobs, &id, step) {
+                Ok(()) => {
+                    passed += 1;
+                    println!("✅ {}", label);
+                }
+                Err(message) => {
+                    failed += 1;
+                    println!("❌ {}\n   {}", label, message.replace('\n', "\n   "));
+                }
+            }
+        }
+    }
+
+    let unloader = host.clone();
+    let _ = jobs.run_blocking(None, Some(EXTENSION_TIMEOUT_MS), move |_py| Ok(unloader.unload(&id)));
+
+    println!("🧪 {} passed, {} failed", passed, failed);
+    Ok(failed == 0)
+}
+
+fn read_fixture(path: &Path) -> Result<Fixture, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid fixture '{}': {}", path.display(), e))
+}
+
+fn step_name(step: &Step) -> String {
+    match (&step.event, &step.command) {
+        (Some(event), _) => event.clone(),
+        (None, Some(command)) => format!("command:{}", command),
+        (None, None) => String::new(),
+    }
+}
+
+/**
+ * 1つのステップを実行して期待値と比較する（一致しない場合は理由を返す）
+ */
+fn run_step(host: &ExtensionHost, jobs: &PythonJobs, id: &str, step: Step) -> Result<(), String> {
+    let host = host.clone();
+    let extension_id = id.to_string();
+    let editor = step.editor.unwrap_or_default();
+    let payload = step.payload.unwrap_or_else(|| serde_json::json!({}));
+
+    let response = match (step.event, step.command) {
+        (Some(event), None) => jobs.run_blocking(None, Some(EXTENSION_TIMEOUT_MS), move |_py| {
+            host.dispatch(&extension_id, &event, &payload, editor)
+        }),
+        (None, Some(command)) => jobs.run_blocking(None, Some(EXTENSION_TIMEOUT_MS), move |_py| {
+            host.run_command(&extension_id, &command, editor)
+        }),
+        _ => return Err("A step must have either \"event\" or \"command\"".to_string()),
+    };
+
+    // 実行中に送信された操作、戻り値の操作の順
+    let mut actions: Vec<Value> = vinsert_api::take_captured_events()
+        .into_iter()
+        .filter(|(event, _)| event == EXTENSION_ACTION_EVENT)
+        .map(|(_, payload)| payload)
+        .collect();
+
+    let response = match (response, step.expect_error) {
+        (Err(e), Some(expected)) if e.to_string().contains(&expected) => return Ok(()),
+        (Err(e), Some(expected)) => return Err(format!("expected an error containing {:?}, got: {}", expected, e)),
+        (Err(e), None) => return Err(e.to_string()),
+        (Ok(_), Some(expected)) => return Err(format!("expected an error containing {:?}, but the step succeeded", expected)),
+        (Ok(response), None) => response,
+    };
+    if let Some(response) = response.filter(|response| response.get("action").is_some()) {
+        actions.push(response);
+    }
+    for action in &mut actions {
+        if let Some(object) = action.as_object_mut() {
+            object.remove("extension_id");
+        }
+    }
+
+    let expected = match step.expect {
+        Some(expected) => expected,
+        None => return Ok(()),
+    };
+    let all_match = expected.len() == actions.len()
+        && expected.iter().zip(&actions).all(|(expected, actual)| matches(expected, actual));
+    if all_match {
+        return Ok(());
+    }
+
+    Err(format!(
+        "expected: {}\nactual:   {}",
+        Value::Array(expected),
+        Value::Array(actions)
+    ))
+}
+
+/**
+ * 期待値に書いたキーだけを比較する（オブジェクトは再帰的に、それ以外は完全一致）
+ */
+fn matches(expected: &Value, actual: &Value) -> bool {
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => expected
+            .iter()
+            .all(|(key, value)| actual.get(key).is_some_and(|actual| matches(value, actual))),
+        _ => expected == actual,
+    }
+}
@@ -0,0 +1,255 @@
+/*
+ * =====================================================
+ * Vinsert Editor - WebAssembly拡張機能ランタイム
+ * setting.jsonでruntime: "wasm"を指定した拡張機能をwasmtime（WASI）で実行する
+ * 呼び出しごとに燃料（fuel）と時間の上限を設け、ファイルシステムは公開しない
+ * =====================================================
+ */
+
+/// WebAssemblyサポートなし（wasmフィーチャー無効）でビルドした場合のエラー
+#[cfg(not(feature = "wasm"))]
+pub const WASM_UNAVAILABLE: &str = "WebAssembly extension support not compiled in";
+
+#[cfg(feature = "wasm")]
+pub use runtime::WasmExtension;
+
+/**
+ * 拡張機能モジュールとのやり取り（ABI）
+ *
+ * エクスポートが必要なもの:
+ *   memory                     線形メモリ
+ *   vinsert_alloc(len) -> ptr  引数の文字列を書き込む領域を確保する
+ *   on_event(ptr, len, ptr, len) -> i64
+ *
+ * 文字列はUTF-8で(ptr, len)の組として渡す（on_eventはevent_typeとpayloadのJSON）。
+ * 戻り値は上位32ビットがptr、下位32ビットがlenの操作JSON（0は操作なし）で、
+ * on_eventの戻り値と同じ形式として扱う。on_commandも同じ規約で呼び出す。
+ * _initialize（WASIリアクター）と vinsert_free(ptr, len) は任意。
+ */
+#[cfg(feature = "wasm")]
+mod runtime {
+    use std::path::Path;
+    use std::sync::OnceLock;
+    use std::time::Duration;
+    use wasmtime::{
+        Config, Engine, Func, Instance, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, Trap, Val,
+        ValType,
+    };
+    use wasmtime_wasi::preview1::{self, WasiP1Ctx};
+    use wasmtime_wasi::WasiCtxBuilder;
+
+    use crate::extension_host::EXTENSION_TIMEOUT_MS;
+
+    /// 1回の呼び出しで消費できる燃料（おおよそ実行する命令数）
+    const FUEL_PER_CALL: u64 = 2_000_000_000;
+
+    /// 拡張機能1つが使える線形メモリの上限
+    const MEMORY_LIMIT_BYTES: usize = 64 * 1024 * 1024;
+
+    /// 時間制限を判定するエポックの間隔
+    const EPOCH_TICK_MS: u64 = 10;
+
+    static ENGINE: OnceLock<Engine> = OnceLock::new();
+
+    /**
+     * ストアに保持する状態
+     */
+    struct WasmState {
+        wasi: WasiP1Ctx,
+        limits: StoreLimits,
+    }
+
+    /**
+     * 読み込み済みのWebAssembly拡張機能
+     * 呼び出しの間もインスタンス（線形メモリの状態）を保持する
+     */
+    pub struct WasmExtension {
+        id: String,
+        store: Store<WasmState>,
+        instance: Instance,
+        memory: Memory,
+    }
+
+    /**
+     * 燃料・エポックによる中断を有効にしたエンジン（初回にエポックを進めるスレッドを起動する）
+     */
+    fn engine() -> Result<&'static Engine, String> {
+        if let Some(engine) = ENGINE.get() {
+            return Ok(engine);
+        }
+
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        config.epoch_interruption(true);
+        let engine = Engine::new(&config).map_err(|e| format!("Failed to create WebAssembly engine: {}", e))?;
+
+        let engine = ENGINE.get_or_init(|| {
+            let ticker = engine.clone();
+            let _ = std::thread::Builder::new()
+                .name("vinsert-wasm-epoch".to_string())
+                .spawn(move || loop {
+                    std::thread::sleep(Duration::from_millis(EPOCH_TICK_MS));
+                    ticker.increment_epoch();
+                });
+            engine
+        });
+        Ok(engine)
+    }
+
+    impl WasmExtension {
+        /**
+         * モジュールをコンパイルしてインスタンス化する
+         * WASIは標準出力・標準エラーのみ（ディレクトリ、環境変数、引数は渡さない）
+         */
+        pub fn load(id: &str, main_path: &Path) -> Result<Self, String> {
+            let engine = engine()?;
+            let module = Module::from_file(engine, main_path)
+                .map_err(|e| format!("Failed to load extension '{}': {}", id, e))?;
+
+            let mut linker: Linker<WasmState> = Linker::new(engine);
+            preview1::add_to_linker_sync(&mut linker, |state: &mut WasmState| &mut state.wasi)
+                .map_err(|e| format!("Failed to set up WASI for extension '{}': {}", id, e))?;
+
+            let wasi = WasiCtxBuilder::new().inherit_stdout().inherit_stderr().build_p1();
+            let limits = StoreLimitsBuilder::new().memory_size(MEMORY_LIMIT_BYTES).build();
+            let mut store = Store::new(engine, WasmState { wasi, limits });
+            store.limiter(|state| &mut state.limits);
+
+            set_limits(&mut store)?;
+            let instance = linker
+                .instantiate(&mut store, &module)
+                .map_err(|e| describe_error(id, "instantiate", e))?;
+
+            let memory = instance
+                .get_memory(&mut store, "memory")
+                .ok_or_else(|| format!("Extension '{}' does not export memory", id))?;
+            if instance.get_func(&mut store, "vinsert_alloc").is_none() {
+                return Err(format!("vinsert_alloc function not found in extension '{}'", id));
+            }
+            if instance.get_func(&mut store, "on_event").is_none() {
+                return Err(format!("on_event function not found in extension '{}'", id));
+            }
+
+            let mut extension = WasmExtension {
+                id: id.to_string(),
+                store,
+                instance,
+                memory,
+            };
+
+            // WASIリアクターの初期化（Rustのcdylibなど）
+            if let Some(initialize) = extension.instance.get_func(&mut extension.store, "_initialize") {
+                set_limits(&mut extension.store)?;
+                initialize
+                    .call(&mut extension.store, &[], &mut [])
+                    .map_err(|e| describe_error(id, "_initialize", e))?;
+            }
+
+            Ok(extension)
+        }
+
+        /**
+         * エクスポート関数を文字列引数で呼び出し、戻り値の文字列を返す（操作なしは空文字列）
+         */
+        pub fn call(&mut self, function: &str, args: &[&str]) -> Result<String, String> {
+            let id = self.id.clone();
+            let func = self
+                .instance
+                .get_func(&mut self.store, function)
+                .ok_or_else(|| format!("{} function not found in extension '{}'", function, id))?;
+            check_signature(&self.store, &func, args.len())
+                .map_err(|e| format!("Extension '{}' {} {}", id, function, e))?;
+
+            set_limits(&mut self.store)?;
+            let mut params = Vec::with_capacity(args.len() * 2);
+            for arg in args {
+                let (ptr, len) = self.write_string(arg)?;
+                params.push(Val::I32(ptr as i32));
+                params.push(Val::I32(len as i32));
+            }
+
+            let mut results = [Val::I64(0)];
+            func.call(&mut self.store, &params, &mut results)
+                .map_err(|e| describe_error(&id, function, e))?;
+
+            let packed = results[0].unwrap_i64() as u64;
+            if packed == 0 {
+                return Ok(String::new());
+            }
+            let ptr = (packed >> 32) as usize;
+            let len = (packed & 0xffff_ffff) as usize;
+
+            let mut bytes = vec![0u8; len];
+            self.memory
+                .read(&self.store, ptr, &mut bytes)
+                .map_err(|e| format!("Extension '{}' returned an invalid pointer: {}", id, e))?;
+
+            if let Some(free) = self.instance.get_func(&mut self.store, "vinsert_free") {
+                let _ = free.call(&mut self.store, &[Val::I32(ptr as i32), Val::I32(len as i32)], &mut []);
+            }
+
+            String::from_utf8(bytes).map_err(|e| format!("Extension '{}' returned invalid UTF-8: {}", id, e))
+        }
+
+        /**
+         * vinsert_allocで確保した領域に文字列を書き込む
+         */
+        fn write_string(&mut self, text: &str) -> Result<(u32, u32), String> {
+            let len = u32::try_from(text.len()).map_err(|_| "Argument is too large".to_string())?;
+            let alloc = self
+                .instance
+                .get_typed_func::<i32, i32>(&mut self.store, "vinsert_alloc")
+                .map_err(|e| format!("Extension '{}' vinsert_alloc has an unsupported signature: {}", self.id, e))?;
+            let ptr = alloc
+                .call(&mut self.store, len as i32)
+                .map_err(|e| describe_error(&self.id, "vinsert_alloc", e))? as u32;
+            self.memory
+                .write(&mut self.store, ptr as usize, text.as_bytes())
+                .map_err(|e| format!("Extension '{}' vinsert_alloc returned an invalid pointer: {}", self.id, e))?;
+            Ok((ptr, len))
+        }
+    }
+
+    /**
+     * 呼び出しごとの燃料と制限時間を設定する
+     */
+    fn set_limits(store: &mut Store<WasmState>) -> Result<(), String> {
+        store
+            .set_fuel(FUEL_PER_CALL)
+            .map_err(|e| format!("Failed to set fuel: {}", e))?;
+        store.set_epoch_deadline(EXTENSION_TIMEOUT_MS / EPOCH_TICK_MS);
+        Ok(())
+    }
+
+    /**
+     * 関数の型が (i32, i32) × 引数の数 -> i64 であるか確認する
+     */
+    fn check_signature(store: &Store<WasmState>, func: &Func, arg_count: usize) -> Result<(), String> {
+        let ty = func.ty(store);
+        let params_ok = ty.params().len() == arg_count * 2 && ty.params().all(|param| matches!(param, ValType::I32));
+        let results: Vec<ValType> = ty.results().collect();
+        let results_ok = results.len() == 1 && matches!(results[0], ValType::I64);
+        if params_ok && results_ok {
+            Ok(())
+        } else {
+            Err(format!(
+                "has an unsupported signature (expected {} i32 parameters and an i64 result)",
+                arg_count * 2
+            ))
+        }
+    }
+
+    /**
+     * 燃料切れ・時間切れを分かるメッセージにする
+     */
+    fn describe_error(id: &str, function: &str, error: wasmtime::Error) -> String {
+        match error.downcast_ref::<Trap>() {
+            Some(Trap::OutOfFuel) => format!("Extension '{}' exceeded its fuel limit in {}", id, function),
+            Some(Trap::Interrupt) => format!(
+                "Extension '{}' exceeded {} ms in {}",
+                id, EXTENSION_TIMEOUT_MS, function
+            ),
+            _ => format!("Extension '{}' error in {}: {}", id, function, error),
+        }
+    }
+}
@@ -0,0 +1,203 @@
+/*
+ * =====================================================
+ * Vinsert Editor - 拡張機能のホットリロード
+ * 拡張機能ディレクトリを監視し、main.pyやsetting.jsonが
+ * 変更された読み込み済み拡張機能を自動で読み込み直す
+ * =====================================================
+ */
+
+use notify::{RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+
+use crate::extension_host::{self, ExtensionHost, EXTENSION_TIMEOUT_MS};
+use crate::extension_manifest::{self, DiagnosticSeverity, ExtensionDiagnostic, MANIFEST_FILE};
+use crate::extension_menu;
+use crate::python_exec::PythonJobs;
+
+/// 再読み込みに成功したときのイベント名
+pub const EXTENSION_RELOADED_EVENT: &str = "extension://reloaded";
+/// 再読み込みに失敗したときのイベント名
+pub const EXTENSION_ERROR_EVENT: &str = "extension://error";
+
+/// 保存時に連続して届く変更通知をまとめる待ち時間
+const DEBOUNCE_MS: u64 = 300;
+/// 変更が続いている場合でも、最初の通知からこの時間が経てば再読み込みする
+const MAX_BATCH_MS: u64 = 2_000;
+
+#[derive(Clone, Serialize)]
+struct ExtensionReloadedEvent {
+    id: String,
+}
+
+#[derive(Clone, Serialize)]
+struct ExtensionErrorEvent {
+    id: String,
+    message: String,
+    diagnostics: Vec<ExtensionDiagnostic>,
+}
+
+/**
+ * 拡張機能ディレクトリの監視を開始する（アプリ起動時に一度だけ呼ぶ）
+ * 監視は専用スレッドで行い、アプリ終了まで続ける
+ */
+pub fn start(app: tauri::AppHandle, host: ExtensionHost, jobs: PythonJobs) -> Result<(), String> {
+    let extensions_dir = extension_host::extensions_directory(&app)?;
+    std::fs::create_dir_all(&extensions_dir)
+        .map_err(|e| format!("Failed to create '{}': {}", extensions_dir.display(), e))?;
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)
+        .map_err(|e| format!("Failed to create extension watcher: {}", e))?;
+    watcher
+        .watch(&extensions_dir, RecursiveMode::Recursive)
+        .map_err(|e| format!("Failed to watch '{}': {}", extensions_dir.display(), e))?;
+
+    println!("👀 Watching extensions: {}", extensions_dir.display());
+
+    std::thread::Builder::new()
+        .name("vinsert-extension-watcher".to_string())
+        .spawn(move || {
+            // watcherはスレッドが生きている間保持する
+            let _watcher = watcher;
+            watch_loop(&app, &host, &jobs, &extensions_dir, rx);
+        })
+        .map_err(|e| format!("Failed to start extension watcher: {}", e))?;
+    Ok(())
+}
+
+fn watch_loop(
+    app: &tauri::AppHandle,
+    host: &ExtensionHost,
+    jobs: &PythonJobs,
+    extensions_dir: &Path,
+    rx: mpsc::Receiver<notify::Result<notify::Event>>,
+) {
+    while let Ok(first) = rx.recv() {
+        let mut changed = BTreeSet::new();
+        // 拡張機能のコード・setting.json以外の変更（ログやキャッシュ等）では待ち始めない
+        if !collect_changed(extensions_dir, first, &mut changed) {
+            continue;
+        }
+
+        // エディタの保存は複数の通知に分かれるため、落ち着くまで待ってからまとめて処理する
+        // 待ち時間を延長するのは対象ファイルの変更のみで、全体の待ち時間はMAX_BATCH_MSまで
+        let batch_deadline = Instant::now() + Duration::from_millis(MAX_BATCH_MS);
+        let mut quiet_deadline = Instant::now() + Duration::from_millis(DEBOUNCE_MS);
+        loop {
+            let wait = quiet_deadline.min(batch_deadline).saturating_duration_since(Instant::now());
+            if wait.is_zero() {
+                break;
+            }
+            match rx.recv_timeout(wait) {
+                Ok(next) => {
+                    if collect_changed(extensions_dir, next, &mut changed) {
+                        quiet_deadline = Instant::now() + Duration::from_millis(DEBOUNCE_MS);
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+
+        for id in changed {
+            // 有効化されていない（読み込まれていない）拡張機能は次回の読み込み時に反映される
+            if host.is_loaded(&id) {
+                reload(app, host, jobs, extensions_dir, &id);
+            }
+        }
+    }
+}
+
+/**
+ * 変更通知から対象の拡張機能IDを取り出す
+ * 戻り値は拡張機能のコードまたはsetting.jsonの変更が含まれていたかどうか
+ */
+fn collect_changed(
+    extensions_dir: &Path,
+    event: notify::Result<notify::Event>,
+    changed: &mut BTreeSet<String>,
+) -> bool {
+    let event = match event {
+        Ok(event) => event,
+        Err(e) => {
+            println!("⚠️ Extension watcher error: {}", e);
+            return false;
+        }
+    };
+    if event.kind.is_access() {
+        return false;
+    }
+
+    let mut relevant = false;
+    for path in &event.paths {
+        if let Some(id) = extension_id_for(extensions_dir, path) {
+            changed.insert(id);
+            relevant = true;
+        }
+    }
+    relevant
+}
+
+/**
+ * 拡張機能のコード（.py / .wasm）またはsetting.jsonであれば拡張機能IDを返す
+ */
+fn extension_id_for(extensions_dir: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(extensions_dir).ok()?;
+    let mut components = relative.components();
+    let id = components.next()?.as_os_str().to_string_lossy().into_owned();
+    if id.starts_with('.') {
+        return None;
+    }
+
+    let is_source = path.extension().is_some_and(|ext| ext == "py" || ext == "wasm");
+    let is_manifest = relative.components().count() == 2 && path.file_name().is_some_and(|name| name == MANIFEST_FILE);
+    if is_source || is_manifest {
+        Some(id)
+    } else {
+        None
+    }
+}
+
+fn reload(app: &tauri::AppHandle, host: &ExtensionHost, jobs: &PythonJobs, extensions_dir: &Path, id: &str) {
+    let extension_dir: PathBuf = extensions_dir.join(id);
+
+    let result = extension_host::grant_permissions_blocking(app, id, &extension_dir).and_then(|permissions| {
+        let host = host.clone();
+        let id = id.to_string();
+        jobs.run_blocking(None, Some(EXTENSION_TIMEOUT_MS), move |_py| {
+            host.load(&id, extension_dir, permissions)
+        })
+        .map_err(|e| e.to_string())
+    });
+
+    match result {
+        Ok(()) => {
+            println!("🔄 Extension reloaded: {}", id);
+            extension_menu::refresh(app);
+            let _ = app.emit(EXTENSION_RELOADED_EVENT, ExtensionReloadedEvent { id: id.to_string() });
+        }
+        Err(message) => {
+            println!("❌ Extension reload failed: {}: {}", id, message);
+
+            // setting.jsonの問題は診断結果をそのまま、Pythonのエラーはメッセージを診断として返す
+            let (_, mut diagnostics) = extension_manifest::inspect_extension(&extensions_dir.join(id));
+            diagnostics.retain(|diagnostic| diagnostic.severity == DiagnosticSeverity::Error);
+            if diagnostics.is_empty() {
+                diagnostics.push(ExtensionDiagnostic::error(message.clone()));
+            }
+
+            let _ = app.emit(
+                EXTENSION_ERROR_EVENT,
+                ExtensionErrorEvent {
+                    id: id.to_string(),
+                    message,
+                    diagnostics,
+                },
+            );
+        }
+    }
+}
@@ -8,19 +8,41 @@
  * =====================================================
  */
 
+mod extension_config;
+mod extension_events;
+mod extension_host;
+mod extension_installer;
+mod extension_manifest;
+mod extension_menu;
+mod extension_permissions;
+mod extension_process;
+mod extension_registry;
+mod extension_signing;
+mod extension_stats;
+mod extension_test;
+mod extension_wasm;
+#[cfg(any(feature = "python", feature = "wasm"))]
+mod extension_watcher;
+mod python_exec;
+mod python_environment;
+mod python_cells;
+mod python_evaluate;
+mod python_runtime;
+mod python_session;
+mod vinsert_api;
+
 use tauri::Manager;
+#[cfg(feature = "python")]
+use tauri::Emitter;
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
+#[cfg(feature = "python")]
 use pyo3::types::PyDict;
 use std::env;
+#[cfg(feature = "python")]
+use std::sync::Arc;
 
-static mut PYTHON_TYPE: PythonType = PythonType::Unknown;
-
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum PythonType {
-    Unknown,
-    Embedded,    // python-build-standaloneによる組み込みPython
-    System,      // システム環境のPython
-}
+use python_exec::{ExecutionResult, PythonExecError, PythonJobs};
 
 // =====================================================
 // Python統合機能（PyO3）
@@ -30,6 +52,7 @@ enum PythonType {
  * PyO3の基本テスト関数
  * Python環境が正常に動作するかテストする
  */
+#[cfg(feature = "python")]
 #[tauri::command]
 fn test_python() -> Result<String, String> {
     Python::with_gil(|py| {
@@ -43,55 +66,80 @@ fn test_python() -> Result<String, String> {
 
 /**
  * 任意のPythonコードを実行
- * 機能拡張で使用される予定
+ * ワーカースレッドで実行し、timeout_ms（省略時は既定値）を過ぎると中断する
+ * job_idを指定するとcancel_python_executionで停止できる
+ * stdout/stderr・result変数のrepr・例外とトレースバック・実行時間を返す
  */
+#[cfg(feature = "python")]
 #[tauri::command]
-fn execute_python(code: String) -> Result<String, String> {
-    Python::with_gil(|py| {
-        
-        // 標準出力をキャプチャするための設定
-        let sys = py.import_bound("sys").map_err(|e| format!("Failed to import sys: {}", e))?;
-        let io_module = py.import_bound("io").map_err(|e| format!("Failed to import io: {}", e))?;
-        let string_io = io_module.getattr("StringIO").map_err(|e| format!("Failed to get StringIO: {}", e))?;
-        let output_buffer = string_io.call0().map_err(|e| format!("Failed to create StringIO: {}", e))?;
-        
-        // 標準出力を一時的にStringIOにリダイレクト
-        let original_stdout = sys.getattr("stdout").map_err(|e| format!("Failed to get stdout: {}", e))?;
-        sys.setattr("stdout", &output_buffer).map_err(|e| format!("Failed to redirect stdout: {}", e))?;
-        
-        let locals = PyDict::new_bound(py);
-        let result = py.run_bound(&code, None, Some(&locals));
-        
-        // 標準出力を元に戻す
-        sys.setattr("stdout", original_stdout).map_err(|e| format!("Failed to restore stdout: {}", e))?;
-        
-        // 出力を取得
-        let output = output_buffer.call_method0("getvalue")
-            .map_err(|e| format!("Failed to get output: {}", e))?
-            .to_string();
-        
-        match result {
-            Ok(_) => {
-                // printされた内容を返す
-                if !output.is_empty() {
-                    Ok(output.trim().to_string())
-                } else {
-                    // 結果を取得する場合（例：最後の変数の値）
-                    match locals.get_item("result") {
-                        Ok(Some(val)) => Ok(format!("{}", val)),
-                        _ => Ok("Code executed successfully".to_string()),
-                    }
-                }
-            },
-            Err(e) => Err(format!("Python execution error: {}", e)),
-        }
+async fn execute_python(
+    jobs: tauri::State<'_, PythonJobs>,
+    code: String,
+    timeout_ms: Option<u64>,
+    job_id: Option<String>,
+) -> Result<ExecutionResult, PythonExecError> {
+    jobs.run(job_id, timeout_ms, move |py| {
+        let globals = PyDict::new_bound(py);
+        python_exec::run_captured(py, &code, "<vinsert>", true, &globals)
     })
+    .await
+}
+
+/**
+ * Pythonコードを実行し、出力を逐次イベントで通知する
+ * stdout/stderrへの書き込みごとに python://output、終了時に python://complete を送る
+ * 実行はバックグラウンドで行い、キャンセル用のジョブIDをすぐに返す
+ */
+#[cfg(feature = "python")]
+#[tauri::command]
+async fn execute_python_streaming(
+    app: tauri::AppHandle,
+    jobs: tauri::State<'_, PythonJobs>,
+    code: String,
+    timeout_ms: Option<u64>,
+    job_id: Option<String>,
+) -> Result<String, PythonExecError> {
+    let job_id = job_id.unwrap_or_else(|| jobs.next_job_id());
+    let jobs = jobs.inner().clone();
+    let worker_job_id = job_id.clone();
+    
+    tauri::async_runtime::spawn(async move {
+        let output_app = app.clone();
+        let output_job_id = worker_job_id.clone();
+        let listener: python_exec::OutputListener = Arc::new(move |stream: &str, text: &str| {
+            let _ = output_app.emit(python_exec::PYTHON_OUTPUT_EVENT, python_exec::PythonOutputEvent {
+                job_id: output_job_id.clone(),
+                stream: stream.to_string(),
+                text: text.to_string(),
+            });
+        });
+        
+        let outcome = jobs.run(Some(worker_job_id.clone()), timeout_ms, move |py| {
+            let globals = PyDict::new_bound(py);
+            python_exec::run_with_listener(py, &code, "<vinsert>", true, &globals, Some(listener))
+        })
+        .await;
+        
+        let (result, error) = match outcome {
+            Ok(result) => (Some(result), None),
+            Err(e) => (None, Some(e)),
+        };
+        println!("🐍 Streaming Python job finished: {}", worker_job_id);
+        let _ = app.emit(python_exec::PYTHON_COMPLETE_EVENT, python_exec::PythonCompleteEvent {
+            job_id: worker_job_id,
+            result,
+            error,
+        });
+    });
+    
+    Ok(job_id)
 }
 
 /**
  * Python式を評価する関数
  * 機能拡張で使用される予定
  */
+#[cfg(feature = "python")]
 #[tauri::command]
 fn evaluate_python_expression(expression: String) -> Result<String, String> {
     Python::with_gil(|py| {
@@ -104,199 +152,61 @@ fn evaluate_python_expression(expression: String) -> Result<String, String> {
 
 /**
  * Pythonファイルを実行する関数
- * 機能拡張読み込みで使用される予定
+ * execute_pythonと同様に制限時間とキャンセルに対応し、同じ形式の実行結果を返す
  */
+#[cfg(feature = "python")]
 #[tauri::command]
-fn run_python_file(file_path: String) -> Result<String, String> {
-    Python::with_gil(|py| {
-        match std::fs::read_to_string(&file_path) {
-            Ok(code) => {
-                let locals = PyDict::new_bound(py);
-                match py.run_bound(&code, None, Some(&locals)) {
-                    Ok(_) => Ok("Python file executed successfully".to_string()),
-                    Err(e) => Err(format!("Python file execution error: {}", e)),
-                }
-            },
-            Err(e) => Err(format!("Failed to read Python file: {}", e)),
-        }
+async fn run_python_file(
+    jobs: tauri::State<'_, PythonJobs>,
+    file_path: String,
+    timeout_ms: Option<u64>,
+    job_id: Option<String>,
+) -> Result<ExecutionResult, PythonExecError> {
+    let code = std::fs::read_to_string(&file_path)
+        .map_err(|e| format!("Failed to read Python file: {}", e))?;
+    
+    jobs.run(job_id, timeout_ms, move |py| {
+        let globals = PyDict::new_bound(py);
+        globals.set_item("__file__", &file_path)
+            .map_err(|e| format!("Failed to set __file__: {}", e))?;
+        python_exec::run_captured(py, &code, &file_path, false, &globals)
     })
+    .await
 }
 
-/**
- * Python詳細情報を取得（組み込み判定機能強化版）
- */
+// =====================================================
+// Pythonサポートなしのビルド（pythonフィーチャー無効）
+// コマンドは登録したまま、呼び出されたらエラーを返す
+// =====================================================
+
+#[cfg(not(feature = "python"))]
 #[tauri::command]
-fn get_python_info() -> Result<String, String> {
-    Python::with_gil(|py| {
-        // 基本情報を取得
-        let sys = py.import_bound("sys").map_err(|e| format!("sysモジュール取得失敗: {}", e))?;
-        let version = sys.getattr("version").and_then(|v| v.extract::<String>()).unwrap_or_else(|_| "Unknown".to_string());
-        let executable = sys.getattr("executable").and_then(|v| v.extract::<String>()).unwrap_or_else(|_| "Unknown".to_string());
-        
-        // 組み込みPython判定
-        let is_embedded = detect_embedded_python(&executable);
-        let python_type = if is_embedded {
-            "🔗 EMBEDDED (組み込みPython)"
-        } else {
-            "🖥️ SYSTEM (システムPython)"
-        };
-        
-        // site-packagesのパス
-        let site_packages = sys.getattr("path")
-            .and_then(|path_list| path_list.extract::<Vec<String>>())
-            .unwrap_or_else(|_| vec![]);
-        
-        let site_packages_info = site_packages.iter()
-            .map(|p| format!("  - {}", p))
-            .collect::<Vec<_>>()
-            .join("\n");
-        
-        let result = format!(
-            "🐍 Python環境情報 🐍\n\n\
-            📊 環境タイプ: {}\n\
-            📋 バージョン: {}\n\
-            📁 実行ファイル: {}\n\
-            🎯 判定: {}\n\n\
-            📦 Python Path:\n{}\n",
-            python_type,
-            version,
-            executable,
-            if is_embedded { "アプリケーション組み込み" } else { "システム環境" },
-            site_packages_info
-        );
-        
-        Ok(result)
-    })
+fn test_python() -> Result<String, String> {
+    Err(python_exec::PYTHON_UNAVAILABLE.to_string())
 }
 
-/**
- * 開発者向け詳細Python環境診断
- */
+#[cfg(not(feature = "python"))]
 #[tauri::command]
-fn debug_python_environment() -> Result<String, String> {
-    Python::with_gil(|py| {
-        let mut result = String::new();
-        
-        // 基本環境情報
-        result.push_str("🔍 PYTHON環境詳細診断\n");
-        result.push_str("=" .repeat(50).as_str());
-        result.push_str("\n\n");
-        
-        // Python実行ファイル情報
-        match py.import_bound("sys") {
-            Ok(sys) => {
-                let executable = sys.getattr("executable")
-                    .and_then(|v| v.extract::<String>())
-                    .unwrap_or_else(|_| "Unknown".to_string());
-                    
-                result.push_str(&format!("📁 Python実行ファイル: {}\n", executable));
-                
-                // 組み込み判定の詳細
-                let is_embedded = detect_embedded_python(&executable);
-                result.push_str(&format!("🎯 組み込み判定: {}\n", is_embedded));
-                
-                // 判定理由の詳細
-                result.push_str("🔍 判定根拠:\n");
-                
-                if executable.contains("python-standalone") {
-                    result.push_str("   ✓ パスに 'python-standalone' が含まれる\n");
-                }
-                
-                if let Ok(current_exe) = std::env::current_exe() {
-                    if let Some(exe_dir) = current_exe.parent() {
-                        let exe_dir_str = exe_dir.to_string_lossy();
-                        if executable.starts_with(exe_dir_str.as_ref()) {
-                            result.push_str("   ✓ アプリケーションディレクトリ内のPython\n");
-                        }
-                    }
-                }
-                
-                // 現在のアプリケーション情報
-                if let Ok(current_exe) = std::env::current_exe() {
-                    result.push_str(&format!("🏠 アプリ実行ファイル: {}\n", current_exe.display()));
-                    if let Some(exe_dir) = current_exe.parent() {
-                        result.push_str(&format!("📂 アプリディレクトリ: {}\n", exe_dir.display()));
-                    }
-                }
-                
-                result.push_str("\n");
-                
-                // Python version詳細
-                if let Ok(version) = sys.getattr("version").and_then(|v| v.extract::<String>()) {
-                    result.push_str(&format!("🐍 Pythonバージョン: {}\n", version));
-                }
-                
-                // Python path詳細
-                if let Ok(path_list) = sys.getattr("path").and_then(|p| p.extract::<Vec<String>>()) {
-                    result.push_str("📦 Python Path (上位5件):\n");
-                    for (i, path) in path_list.iter().take(5).enumerate() {
-                        result.push_str(&format!("   {}. {}\n", i + 1, path));
-                    }
-                    if path_list.len() > 5 {
-                        result.push_str(&format!("   ... 他{}件\n", path_list.len() - 5));
-                    }
-                }
-                
-            },
-            Err(e) => {
-                result.push_str(&format!("❌ sys モジュール取得エラー: {}\n", e));
-            }
-        }
-        
-        // 環境変数チェック
-        result.push_str("\n🌍 関連環境変数:\n");
-        let env_vars = ["PYO3_PYTHON", "PYTHONHOME", "PYTHONPATH"];
-        for var in &env_vars {
-            match std::env::var(var) {
-                Ok(value) => result.push_str(&format!("   {}: {}\n", var, value)),
-                Err(_) => result.push_str(&format!("   {}: (未設定)\n", var)),
-            }
-        }
-        
-        // 最終結論
-        result.push_str("\n🎯 最終結論:\n");
-        let python_type = unsafe { PYTHON_TYPE };
-        match python_type {
-            PythonType::Embedded => {
-                result.push_str("   🟢 【組み込みPython】を使用中\n");
-                result.push_str("   → アプリケーション内蔵のPython環境\n");
-                result.push_str("   → ユーザー環境に依存しない独立動作\n");
-            },
-            PythonType::System => {
-                result.push_str("   🔵 【システムPython】を使用中\n");
-                result.push_str("   → ユーザーがインストールしたPython環境\n");
-                result.push_str("   → 拡張機能はユーザー環境のライブラリを利用可能\n");
-            },
-            PythonType::Unknown => {
-                result.push_str("   🔴 【不明・エラー】\n");
-                result.push_str("   → Python環境の判定に失敗\n");
-            }
-        }
-        
-        result.push_str("\n");
-        result.push_str("=" .repeat(50).as_str());
-        
-        Ok(result)
-    })
+fn execute_python() -> Result<ExecutionResult, PythonExecError> {
+    Err(python_exec::PYTHON_UNAVAILABLE.to_string().into())
 }
 
-fn detect_embedded_python(executable: &str) -> bool {
-    // 実行ファイルパスからの判定
-    if executable.contains("python-standalone") {
-        return true;
-    }
-    
-    // アプリケーションバンドル内かチェック
-    if let Ok(current_exe) = std::env::current_exe() {
-        if let Some(exe_dir) = current_exe.parent() {
-            let exe_dir_str = exe_dir.to_string_lossy();
-            if executable.starts_with(exe_dir_str.as_ref()) {
-                return true;
-            }
-        }
-    }
-    
-    false
+#[cfg(not(feature = "python"))]
+#[tauri::command]
+fn execute_python_streaming() -> Result<String, PythonExecError> {
+    Err(python_exec::PYTHON_UNAVAILABLE.to_string().into())
+}
+
+#[cfg(not(feature = "python"))]
+#[tauri::command]
+fn evaluate_python_expression() -> Result<String, String> {
+    Err(python_exec::PYTHON_UNAVAILABLE.to_string())
+}
+
+#[cfg(not(feature = "python"))]
+#[tauri::command]
+fn run_python_file() -> Result<ExecutionResult, PythonExecError> {
+    Err(python_exec::PYTHON_UNAVAILABLE.to_string().into())
 }
 
 // =====================================================
@@ -305,11 +215,16 @@ fn detect_embedded_python(executable: &str) -> bool {
 
 /**
  * アプリケーション終了コマンド
- * 即座に強制終了する
+ * app_exitを購読している拡張機能に通知してから即座に強制終了する
+ * 通知は非同期で行い、待つ間もメインスレッド（ウィンドウ）を止めない
  */
 #[tauri::command]
-fn exit_app() {
+async fn exit_app(
+    host: tauri::State<'_, extension_host::ExtensionHost>,
+    jobs: tauri::State<'_, PythonJobs>,
+) -> Result<(), String> {
     println!("🔥 Exit app command called - immediate shutdown");
+    extension_events::app_exit(&host, &jobs).await;
     std::process::exit(0);
 }
 
@@ -365,14 +280,20 @@ fn read_clipboard(app: tauri::AppHandle) -> Result<String, String> {
 
 /**
  * ファイルを読み込む
+ * 読み込み後にfile_openedを購読している拡張機能へ通知する
  */
 #[tauri::command]
-async fn read_file(path: String) -> Result<String, String> {
+async fn read_file(
+    host: tauri::State<'_, extension_host::ExtensionHost>,
+    jobs: tauri::State<'_, PythonJobs>,
+    path: String,
+) -> Result<String, String> {
     println!("📖 Reading file: {}", path);
     
     match std::fs::read_to_string(&path) {
         Ok(content) => {
             println!("✅ File read successfully: {} characters", content.len());
+            extension_events::file_opened(&host, &jobs, &path, &content).await;
             Ok(content)
         },
         Err(e) => {
@@ -385,15 +306,25 @@ async fn read_file(path: String) -> Result<String, String> {
 
 /**
  * ファイルに書き込む
+ * before_saveを購読している拡張機能が内容を書き換えた場合は、
+ * 書き換え後の内容を保存して返す（変更がなければNone）
  */
 #[tauri::command]
-async fn write_file(path: String, content: String) -> Result<(), String> {
+async fn write_file(
+    host: tauri::State<'_, extension_host::ExtensionHost>,
+    jobs: tauri::State<'_, PythonJobs>,
+    path: String,
+    content: String,
+) -> Result<Option<String>, String> {
+    let modified = extension_events::before_save(&host, &jobs, &path, &content).await;
+    let content = modified.as_deref().unwrap_or(&content);
     println!("💾 Writing file: {} ({} characters)", path, content.len());
     
-    match std::fs::write(&path, &content) {
+    match std::fs::write(&path, content) {
         Ok(_) => {
             println!("✅ File written successfully: {}", path);
-            Ok(())
+            extension_events::after_save(&host, &jobs, &path, content).await;
+            Ok(modified)
         },
         Err(e) => {
             let error_msg = format!("Failed to write file '{}': {}", path, e);
@@ -491,120 +422,23 @@ async fn open_folder(path: String) -> Result<(), String> {
 // メイン関数とアプリケーション設定
 // =====================================================
 
-fn initialize_python() -> PythonType {
-    // PyO3 0.22.6の初期化
-    // auto-initializeフィーチャーが有効な場合、初回のPython::with_gil()で自動初期化される
-    
-    // Python環境を詳細に確認
-    let python_type = detect_python_environment();
-    println!("🐍 Python初期化完了: {:?}", python_type);
-    python_type
-}
-
-/**
- * Python環境の詳細を検出・確認する関数（緊急修正版）
- */
-fn detect_python_environment() -> PythonType {
-    println!("=== Python環境詳細分析（組み込みPython対応版） ===");
-    
-    // ビルド時の組み込みPythonフラグをチェック（ランタイムで取得）
-    let is_embedded_build = std::env::var("VINSERT_EMBEDDED_PYTHON")
-        .unwrap_or_else(|_| "0".to_string())
-        .parse::<i32>()
-        .unwrap_or(0) == 1;
-    let embedded_python_path = std::env::var("VINSERT_PYTHON_PATH")
-        .unwrap_or_else(|_| "".to_string());
-    
-    println!("🔍 ビルド時の組み込みPythonフラグ: {}", is_embedded_build);
-    println!("🔍 組み込みPythonパス: {}", embedded_python_path);
-    
-    match Python::with_gil(|py| -> Result<PythonType, PyErr> {
-        // 基本的なPython動作テスト
-        let simple_test = py.eval_bound("2 + 2", None, None)?;
-        println!("🐍 Python基本動作テスト: {}", simple_test);
-        
-        // Python実行ファイルパスを取得
-        let sys = py.import_bound("sys")?;
-        let executable = sys.getattr("executable")?.extract::<String>()?;
-        println!("🐍 実行中のPython実行ファイル: {}", executable);
-        
-        // 組み込みPython判定の複数の方法
-        let mut embedded_indicators = Vec::new();
-        
-        // 1. ビルド時フラグによる判定
-        if is_embedded_build {
-            embedded_indicators.push("ビルド時組み込みフラグ");
-        }
-        
-        // 2. 実行ファイルパスによる判定
-        if executable.contains("python-standalone") || (!embedded_python_path.is_empty() && executable.contains(&embedded_python_path)) {
-            embedded_indicators.push("実行ファイルパスが組み込みPythonを示している");
-        }
-        
-        // 3. アプリケーション内部パスかチェック
-        if let Ok(current_exe) = std::env::current_exe() {
-            if let Some(exe_dir) = current_exe.parent() {
-                let exe_dir_str = exe_dir.to_string_lossy();
-                if executable.starts_with(exe_dir_str.as_ref()) {
-                    embedded_indicators.push("Pythonがアプリケーションディレクトリ内にある");
-                }
-            }
-        }
-        
-        // 4. PyOxidizer/組み込み環境の特徴を検出
-        if let Ok(modules) = sys.getattr("modules") {
-            if modules.contains("oxidized_importer").unwrap_or(false) {
-                embedded_indicators.push("PyOxidizer組み込み環境");
-            }
-        }
-        
-        // 5. frozen属性チェック
-        if let Ok(frozen) = sys.getattr("frozen") {
-            if !frozen.is_none() {
-                embedded_indicators.push("frozen属性が設定されている");
-            }
-        }
-        
-        // 6. site-packagesの場所をチェック
-        if let Ok(path_list) = sys.getattr("path") {
-            if let Ok(paths) = path_list.extract::<Vec<String>>() {
-                for path in &paths {
-                    if path.contains("python-standalone") {
-                        embedded_indicators.push("site-packagesが組み込みPython内にある");
-                        break;
-                    }
-                }
-            }
-        }
-        
-        // 判定結果
-        if !embedded_indicators.is_empty() {
-            println!("✅ 組み込みPython検出指標:");
-            for indicator in &embedded_indicators {
-                println!("   - {}", indicator);
-            }
-            Ok(PythonType::Embedded)
-        } else {
-            println!("📍 システムPython検出 (組み込み指標なし)");
-            Ok(PythonType::System)
-        }
-    }) {
-        Ok(python_type) => {
-            println!("🎯 最終判定: {:?}", python_type);
-            python_type
-        },
-        Err(e) => {
-            println!("❌ Python環境検出エラー: {}", e);
-            PythonType::Unknown
-        }
-    }
-}
-
 fn main() {
-    // Pythonの初期化
-    let python_type = initialize_python();
-    unsafe {
-        PYTHON_TYPE = python_type;
+    // 拡張機能向けvinsertモジュールをPython初期化前に登録
+    #[cfg(feature = "python")]
+    {
+        use vinsert_api::vinsert;
+        pyo3::append_to_inittab!(vinsert);
+        // セットアップやメインスレッドのコマンドからのPython実行は制限しない
+        vinsert_api::mark_host_thread();
+    }
+    
+    // vinsert --test-extension <dir>: ウィンドウを作らずに拡張機能のテストを実行して終了する
+    let context = tauri::generate_context!();
+    let args: Vec<String> = env::args().collect();
+    if extension_test::requested(&args) {
+        #[cfg(all(target_os = "windows", not(debug_assertions)))]
+        extension_test::attach_console();
+        std::process::exit(extension_test::run(&args, &context.config().identifier));
     }
     
     tauri::Builder::default()
@@ -616,15 +450,59 @@ fn main() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_clipboard_manager::init()) // 公式クリップボードプラグインを追加
         
+        // 拡張機能ホスト（拡張機能ごとのPython名前空間を保持）
+        .manage(extension_host::ExtensionHost::new())
+        
+        // Python実行ジョブ（制限時間・キャンセル管理）
+        .manage(PythonJobs::new())
+        
+        // 対話型コンソールのセッション（セッションごとの名前空間）
+        .manage(python_session::PythonSessions::new())
+        
+        // 選択範囲の評価用の名前空間
+        .manage(python_evaluate::EvaluationNamespace::new())
+        
         // Tauriコマンドの登録
         .invoke_handler(tauri::generate_handler![
             // Python関連
             test_python,
             execute_python,
+            execute_python_streaming,
             evaluate_python_expression,
             run_python_file,
-            get_python_info,
-            debug_python_environment,
+            python_runtime::python_runtime_info,
+            python_environment::get_python_environment,
+            python_environment::set_python_environment,
+            python_exec::cancel_python_execution,
+            python_session::python_session_create,
+            python_session::python_session_exec,
+            python_session::python_session_complete,
+            python_session::python_session_reset,
+            python_session::python_session_close,
+            python_evaluate::evaluate_selection,
+            python_cells::run_python_cells,
+            
+            // 拡張機能ホスト
+            extension_host::load_extension,
+            extension_host::unload_extension,
+            extension_host::dispatch_extension_event,
+            extension_host::run_extension_command,
+            extension_host::extension_runtimes,
+            extension_stats::get_extension_stats,
+            extension_events::emit_extension_event,
+            extension_manifest::list_extensions,
+            extension_config::get_extension_config,
+            extension_config::set_extension_config,
+            extension_installer::install_extension_from_archive,
+            extension_installer::uninstall_extension,
+            extension_installer::reset_extension_permissions,
+            extension_registry::get_extension_registries,
+            extension_registry::set_extension_registries,
+            extension_registry::list_registry_extensions,
+            extension_registry::diff_registry_extensions,
+            extension_registry::install_registry_extension,
+            extension_signing::get_extension_trust,
+            extension_signing::set_extension_trust,
             
             // アプリケーション制御
             exit_app,
@@ -655,6 +533,30 @@ fn main() {
         .setup(|app| {
             println!("🚀 Sert Editor starting up...");
             
+            // Pythonの初期化と実行環境の検出
+            // 拡張機能用のvenv・site-packagesをsys.pathへ追加してから検出する
+            let python_environment = python_environment::apply(app.handle());
+            let mut python_runtime = python_runtime::PythonRuntime::detect();
+            python_runtime.environment = python_environment;
+            app.state::<extension_host::ExtensionHost>()
+                .configure_process_hosts(extension_process::ProcessHostConfig::from_runtime(&python_runtime));
+            app.manage(python_runtime);
+            
+            // vinsertモジュールからWebViewへイベントを送れるようにする
+            vinsert_api::set_app_handle(app.handle().clone());
+            extension_config::initialize(app.handle());
+            extension_signing::initialize(app.handle());
+            
+            // 拡張機能ファイルの変更を監視して自動で読み込み直す（.pyのほか.wasmやsetting.jsonも対象）
+            #[cfg(any(feature = "python", feature = "wasm"))]
+            if let Err(e) = extension_watcher::start(
+                app.handle().clone(),
+                app.state::<extension_host::ExtensionHost>().inner().clone(),
+                app.state::<PythonJobs>().inner().clone(),
+            ) {
+                println!("⚠️ Extension hot reload disabled: {}", e);
+            }
+            
             // ウィンドウの取得と設定
             let windows = app.webview_windows();
             if let Some(_window) = windows.get("main") {
@@ -685,51 +587,9 @@ fn main() {
             
             // ===== 起動時にPython環境を明確に表示 =====
             println!("");
-            println!("=== PYTHON環境検出結果 ===");
-            
-            let python_type = unsafe { PYTHON_TYPE };
-            match python_type {
-                PythonType::Embedded => {
-                    println!("🟢 使用中のPython: 【組み込みPython】");
-                    println!("   → アプリケーション内蔵のPythonインタープリターを使用");
-                    println!("   → ユーザーのPython環境に依存せず、独立して動作");
-                    println!("   → PyOxidizerまたは類似の組み込み技術を検出");
-                },
-                PythonType::System => {
-                    println!("🔵 使用中のPython: 【ユーザー環境Python】");
-                    println!("   → ユーザーがインストールしたPython環境を使用");
-                    println!("   → システムまたはHomebrewなどからPythonを読み込み");
-                    println!("   → 拡張機能はユーザー環境のPythonライブラリを利用可能");
-                },
-                PythonType::Unknown => {
-                    println!("🔴 使用中のPython: 【不明・エラー】");
-                    println!("   → Python環境の検出に失敗しました");
-                    println!("   → 拡張機能が正常に動作しない可能性があります");
-                }
-            }
-            println!("========================");
+            app.state::<python_runtime::PythonRuntime>().log_summary();
             println!("");
             
-            // Python環境情報の詳細表示（詳細版）
-            println!("=== Python Environment Details ===");
-            match get_python_info() {
-                Ok(info) => {
-                    println!("✅ Python環境詳細情報:\n{}", info);
-                    
-                    // 追加のテスト実行
-                    println!("🧪 Running additional Python verification tests...");
-                    match test_python() {
-                        Ok(test_result) => println!("✅ Python test passed: {}", test_result),
-                        Err(test_error) => println!("❌ Python test failed: {}", test_error),
-                    }
-                },
-                Err(e) => {
-                    println!("❌ Python環境情報取得エラー: {}", e);
-                    println!("🔧 Troubleshooting: Check if Python is properly installed");
-                }
-            }
-            println!("=== End Python Details ===");
-            
             println!("📋 Clipboard operations enabled (official plugin)");
             println!("📁 File operations enabled (JavaScript-based dialogs)");
             println!("🍎 Native menu system enabled");
@@ -739,7 +599,7 @@ fn main() {
         })
         
         // アプリケーション実行
-        .run(tauri::generate_context!())
+        .run(context)
         .expect("error while running tauri application");
 }
 
@@ -864,6 +724,12 @@ fn create_native_menu(app: &tauri::AppHandle) -> Result<tauri::menu::Menu<tauri:
     let paste_item = PredefinedMenuItem::paste(app, Some("貼り付け"))?;
     let edit_separator2 = PredefinedMenuItem::separator(app)?;
     let select_all_item = PredefinedMenuItem::select_all(app, Some("すべて選択"))?;
+    let edit_separator3 = PredefinedMenuItem::separator(app)?;
+    let evaluate_selection_item = MenuItem::with_id(app, "evaluate_selection", "選択範囲を評価", cfg!(feature = "python"), Some("CmdOrCtrl+Shift+E"))?;
+    let evaluate_settings_item = MenuItem::with_id(app, "evaluate_settings", "評価結果の挿入方法…", cfg!(feature = "python"), None::<&str>)?;
+    // Ctrl/Cmd+EnterはPythonのドキュメントでのみWebView側（keyboard-shortcuts.js）で処理するため、アクセラレータは設定しない
+    let run_cell_item = MenuItem::with_id(app, "run_cell", "セルを実行", cfg!(feature = "python"), None::<&str>)?;
+    let run_all_cells_item = MenuItem::with_id(app, "run_all_cells", "すべてのセルを実行", cfg!(feature = "python"), None::<&str>)?;
     
     let edit_menu = Submenu::with_items(
         app,
@@ -878,6 +744,11 @@ fn create_native_menu(app: &tauri::AppHandle) -> Result<tauri::menu::Menu<tauri:
             &paste_item,
             &edit_separator2,
             &select_all_item,
+            &edit_separator3,
+            &evaluate_selection_item,
+            &evaluate_settings_item,
+            &run_cell_item,
+            &run_all_cells_item,
         ],
     )?;
     
@@ -927,8 +798,13 @@ fn create_native_menu(app: &tauri::AppHandle) -> Result<tauri::menu::Menu<tauri:
         ],
     )?;
     
-    // 機能拡張メニュー
-    let extension_settings_item = MenuItem::with_id(app, "extension_settings", "拡張機能設定", true, None::<&str>)?;
+    // 機能拡張メニュー（拡張機能のランタイムなしのビルドでは拡張機能の項目を無効にする）
+    let extensions_available = cfg!(any(feature = "python", feature = "wasm"));
+    let extension_settings_item = MenuItem::with_id(app, "extension_settings", "拡張機能設定", extensions_available, None::<&str>)?;
+    let install_extension_item = MenuItem::with_id(app, "install_extension", "拡張機能をインストール…", extensions_available, None::<&str>)?;
+    let extension_registry_item = MenuItem::with_id(app, "extension_registry", "レジストリから追加・更新…", extensions_available, None::<&str>)?;
+    let extension_trust_item = MenuItem::with_id(app, "extension_trust", "拡張機能の署名…", extensions_available, None::<&str>)?;
+    let python_environment_item = MenuItem::with_id(app, "python_environment", "Python環境…", cfg!(feature = "python"), None::<&str>)?;
     let extension_separator = PredefinedMenuItem::separator(app)?;
     let language_settings_item = MenuItem::with_id(app, "language_settings", "言語設定", true, None::<&str>)?;
     let theme_item = MenuItem::with_id(app, "show_theme", "テーマ", true, None::<&str>)?;
@@ -941,6 +817,10 @@ fn create_native_menu(app: &tauri::AppHandle) -> Result<tauri::menu::Menu<tauri:
         true,
         &[
             &extension_settings_item,
+            &install_extension_item,
+            &extension_registry_item,
+            &extension_trust_item,
+            &python_environment_item,
             &extension_separator,
             &language_settings_item,
             &theme_item,
@@ -948,6 +828,9 @@ fn create_native_menu(app: &tauri::AppHandle) -> Result<tauri::menu::Menu<tauri:
             &open_app_folder_item,
         ],
     )?;
+    if let Some(host) = app.try_state::<extension_host::ExtensionHost>() {
+        extension_menu::append_commands(app, &host, &extensions_menu)?;
+    }
     
     // ウィンドウメニュー（macOS固有）
     #[cfg(target_os = "macos")]
@@ -1032,6 +915,7 @@ fn handle_menu_event(app: &tauri::AppHandle, event: tauri::menu::MenuEvent) {
     
     // WebViewを取得
     if let Some(webview) = app.webview_windows().get("main") {
+        let extension_script;
         let script = match event.id().0.as_str() {
             // ファイルメニュー
             "new_file" => "try { if (window.newFile) window.newFile(); } catch(e) { console.error('newFile error:', e); }",
@@ -1043,6 +927,10 @@ fn handle_menu_event(app: &tauri::AppHandle, event: tauri::menu::MenuEvent) {
             // 編集メニュー
             "undo" => "try { if (window.undo) window.undo(); } catch(e) { console.error('undo error:', e); }",
             "redo" => "try { if (window.redo) window.redo(); } catch(e) { console.error('redo error:', e); }",
+            "evaluate_selection" => "try { if (window.evaluateSelection) window.evaluateSelection(); } catch(e) { console.error('evaluateSelection error:', e); }",
+            "evaluate_settings" => "try { if (window.showEvaluateSettingsDialog) window.showEvaluateSettingsDialog(); } catch(e) { console.error('evaluateSettings error:', e); }",
+            "run_cell" => "try { if (window.runCell) window.runCell(); } catch(e) { console.error('runCell error:', e); }",
+            "run_all_cells" => "try { if (window.runAllCells) window.runAllCells(); } catch(e) { console.error('runAllCells error:', e); }",
             
             // 表示メニュー
             "font_settings" => "try { if (window.showFontSettingsDialog) window.showFontSettingsDialog(); } catch(e) { console.error('fontSettings error:', e); }",
@@ -1060,6 +948,10 @@ fn handle_menu_event(app: &tauri::AppHandle, event: tauri::menu::MenuEvent) {
             
             // 機能拡張メニュー
             "extension_settings" => "try { if (window.showExtensionSettingsDialog) window.showExtensionSettingsDialog(); } catch(e) { console.error('extensionSettings error:', e); }",
+            "install_extension" => "try { if (window.installExtension) window.installExtension(); } catch(e) { console.error('installExtension error:', e); }",
+            "extension_registry" => "try { if (window.showExtensionRegistryDialog) window.showExtensionRegistryDialog(); } catch(e) { console.error('extensionRegistry error:', e); }",
+            "extension_trust" => "try { if (window.showExtensionTrustDialog) window.showExtensionTrustDialog(); } catch(e) { console.error('extensionTrust error:', e); }",
+            "python_environment" => "try { if (window.showPythonEnvironmentDialog) window.showPythonEnvironmentDialog(); } catch(e) { console.error('pythonEnvironment error:', e); }",
             "language_settings" => "try { if (window.showLanguageSettingsDialog) window.showLanguageSettingsDialog(); } catch(e) { console.error('languageSettings error:', e); }",
             "show_theme" => "try { if (window.showThemeDialog) window.showThemeDialog(); } catch(e) { console.error('showTheme error:', e); }",
             "open_app_folder" => "try { if (window.openAppFolder) window.openAppFolder(); } catch(e) { console.error('openAppFolder error:', e); }",
@@ -1067,10 +959,17 @@ fn handle_menu_event(app: &tauri::AppHandle, event: tauri::menu::MenuEvent) {
             // アバウトメニュー
             "about" => "try { if (window.showAboutDialog) window.showAboutDialog(); } catch(e) { console.error('showAboutDialog error:', e); }",
             
-            _ => {
-                println!("⚠️ Unhandled menu event: {:?}", event.id());
-                return;
-            }
+            // 拡張機能が追加したコマンド
+            id => match extension_menu::command_script(id) {
+                Some(script) => {
+                    extension_script = script;
+                    &extension_script
+                }
+                None => {
+                    println!("⚠️ Unhandled menu event: {:?}", event.id());
+                    return;
+                }
+            },
         };
         
         // JavaScriptを実行（エラーハンドリング付き）
@@ -0,0 +1,317 @@
+/*
+ * =====================================================
+ * Vinsert Editor - セル実行（# %% で区切ったPythonコード）
+ * カーソル位置のセル、またはすべてのセルをドキュメントごとのセッションで実行し、
+ * 出力をセルの直後に "#> " で始まるコメント行（出力ブロック）として書き込む
+ * =====================================================
+ */
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3::types::PyDict;
+use serde::Serialize;
+
+#[cfg(feature = "python")]
+use crate::python_exec::{self, ExecutionResult, PythonJobs};
+use crate::python_exec::PythonExecError;
+#[cfg(feature = "python")]
+use crate::python_session::PythonSessions;
+
+/// セルの区切り行の先頭
+#[cfg(feature = "python")]
+const CELL_MARKER: &str = "# %%";
+
+/// 出力ブロックの行の先頭
+#[cfg(feature = "python")]
+const OUTPUT_PREFIX: &str = "#>";
+
+/// 保存していないドキュメントのセッションとトレースバックに使う名前
+#[cfg(feature = "python")]
+const UNTITLED_DOCUMENT: &str = "<untitled>";
+
+/**
+ * セル実行でエラーになった位置
+ * lineはドキュメントの行番号（1始まり、書き込んだ出力ブロックを含めた実行後のテキスト上の位置）
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct CellError {
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+/**
+ * run_python_cellsの戻り値
+ * textは出力ブロックを書き込んだドキュメント全体、cursor_lineは実行後にカーソルを置く行（0始まり）
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct CellRunResult {
+    pub text: String,
+    pub cells_run: usize,
+    pub cursor_line: usize,
+    pub error: Option<CellError>,
+}
+
+/**
+ * ドキュメント内の1つのセル（行番号は0始まり、endは含まない）
+ *
+ *   start         区切り行（先頭のセルで区切り行が無い場合は0行目）
+ *   code_start    コードの最初の行
+ *   code_end      コードの終わり（この行から出力ブロック）
+ *   output_end    出力ブロックの終わり（この行からセル末尾の空行）
+ *   end           次のセルの区切り行
+ */
+#[cfg(feature = "python")]
+#[derive(Debug, Clone, Copy)]
+struct Cell {
+    start: usize,
+    code_start: usize,
+    code_end: usize,
+    output_end: usize,
+    end: usize,
+}
+
+#[cfg(feature = "python")]
+fn is_cell_marker(line: &str) -> bool {
+    line.trim_start().starts_with(CELL_MARKER)
+}
+
+#[cfg(feature = "python")]
+fn is_output_line(line: &str) -> bool {
+    line.starts_with(OUTPUT_PREFIX)
+}
+
+/**
+ * 行の一覧をセルに分割する
+ * 最初の区切り行より前に何か書かれていれば、それも1つのセルとして扱う（区切り行が1つもないドキュメントは実行しない）
+ */
+#[cfg(feature = "python")]
+fn parse_cells(lines: &[&str]) -> Vec<Cell> {
+    let mut starts: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| is_cell_marker(line))
+        .map(|(index, _)| index)
+        .collect();
+    let first_marker = starts.first().copied().unwrap_or(lines.len());
+    if lines[..first_marker].iter().any(|line| !line.trim().is_empty()) {
+        starts.insert(0, 0);
+    }
+
+    let mut cells = Vec::with_capacity(starts.len());
+    for (index, &start) in starts.iter().enumerate() {
+        let end = starts.get(index + 1).copied().unwrap_or(lines.len());
+        let code_start = if is_cell_marker(lines[start]) { start + 1 } else { start };
+
+        // セル末尾の空行、その前の出力ブロックの順に取り除く
+        let mut output_end = end;
+        while output_end > code_start && lines[output_end - 1].trim().is_empty() {
+            output_end -= 1;
+        }
+        let mut code_end = output_end;
+        while code_end > code_start && is_output_line(lines[code_end - 1]) {
+            code_end -= 1;
+        }
+
+        cells.push(Cell {
+            start,
+            code_start,
+            code_end,
+            output_end,
+            end,
+        });
+    }
+    cells
+}
+
+/**
+ * 実行結果を出力ブロックの行にする（標準出力、標準エラー出力、例外の順）
+ */
+#[cfg(feature = "python")]
+fn output_lines(result: &ExecutionResult) -> Vec<String> {
+    let mut text = String::new();
+    text.push_str(&result.stdout);
+    if !result.stderr.is_empty() {
+        if !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        }
+        text.push_str(&result.stderr);
+    }
+
+    if let Some(exception_type) = &result.exception_type {
+        if !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        }
+        if !result.traceback.is_empty() {
+            text.push_str("Traceback (most recent call last):\n");
+        }
+        for frame in &result.traceback {
+            match frame.line {
+                Some(line) => text.push_str(&format!("  File \"{}\", line {}, in {}\n", frame.file, line, frame.function)),
+                None => text.push_str(&format!("  File \"{}\", in {}\n", frame.file, frame.function)),
+            }
+            if let Some(source) = &frame.source {
+                text.push_str(&format!("    {}\n", source.trim()));
+            }
+        }
+        match &result.exception_message {
+            Some(message) if !message.is_empty() => text.push_str(&format!("{}: {}", exception_type, message)),
+            _ => text.push_str(exception_type),
+        }
+    }
+
+    text.trim_end_matches('\n')
+        .lines()
+        .map(|line| {
+            if line.is_empty() {
+                OUTPUT_PREFIX.to_string()
+            } else {
+                format!("{} {}", OUTPUT_PREFIX, line)
+            }
+        })
+        .collect()
+}
+
+/**
+ * 例外が起きたドキュメントの行（トレースバックのうちドキュメント内で最も深いフレーム）
+ */
+#[cfg(feature = "python")]
+fn error_line(result: &ExecutionResult, filename: &str) -> Option<usize> {
+    result
+        .traceback
+        .iter()
+        .rev()
+        .find(|frame| frame.file == filename)
+        .and_then(|frame| frame.line)
+}
+
+/**
+ * セルを順に実行し、出力ブロックを書き込んだドキュメントを組み立てる
+ * targetがSomeならその行を含むセルだけ、Noneならすべてのセルを実行する（エラーが起きたらそこで止める）
+ */
+#[cfg(feature = "python")]
+fn run_cells(
+    py: Python<'_>,
+    globals: &Bound<'_, PyDict>,
+    text: &str,
+    filename: &str,
+    target: Option<usize>,
+) -> Result<CellRunResult, String> {
+    let lines: Vec<&str> = text.split('\n').collect();
+    let cells = parse_cells(&lines);
+
+    let selected: Vec<usize> = match target {
+        Some(line) => cells
+            .iter()
+            .position(|cell| line >= cell.start && line < cell.end)
+            .or_else(|| cells.len().checked_sub(1))
+            .into_iter()
+            .collect(),
+        None => (0..cells.len()).collect(),
+    };
+
+    let mut output: Vec<String> = Vec::with_capacity(lines.len());
+    let mut copied_until = 0;
+    let mut cells_run = 0;
+    let mut cursor_line = target.unwrap_or(0);
+    let mut error = None;
+
+    for index in selected {
+        let cell = cells[index];
+        output.extend(lines[copied_until..cell.code_end].iter().map(|line| line.to_string()));
+        copied_until = cell.end;
+
+        let code_lines = &lines[cell.code_start..cell.code_end];
+        if code_lines.iter().all(|line| line.trim().is_empty()) {
+            // 空のセルは古い出力ブロックだけ残して飛ばす
+            output.extend(lines[cell.code_end..cell.end].iter().map(|line| line.to_string()));
+            cursor_line = output.len();
+            continue;
+        }
+
+        // トレースバックの行番号がドキュメントの行番号と一致するように、セルより前を空行で埋める
+        // linecacheにはこのセルまでのドキュメントを登録し、前のセルで定義した関数の行も表示できるようにする
+        let first_line = output.len() - code_lines.len();
+        let code = format!("{}{}\n", "\n".repeat(first_line), code_lines.join("\n"));
+        python_exec::register_linecache(py, &format!("{}\n", output.join("\n")), filename)
+            .map_err(|e| format!("Failed to register source: {}", e))?;
+        let result = python_exec::run_captured(py, &code, filename, false, globals)?;
+        cells_run += 1;
+
+        output.extend(output_lines(&result));
+        output.extend(lines[cell.output_end..cell.end].iter().map(|line| line.to_string()));
+        cursor_line = output.len();
+
+        if let Some(exception_type) = &result.exception_type {
+            let line = error_line(&result, filename);
+            if let Some(line) = line {
+                cursor_line = line.saturating_sub(1);
+            }
+            error = Some(CellError {
+                line,
+                message: match &result.exception_message {
+                    Some(message) if !message.is_empty() => format!("{}: {}", exception_type, message),
+                    _ => exception_type.clone(),
+                },
+            });
+            break;
+        }
+    }
+    output.extend(lines[copied_until..].iter().map(|line| line.to_string()));
+
+    Ok(CellRunResult {
+        text: output.join("\n"),
+        cells_run,
+        cursor_line: cursor_line.min(output.len().saturating_sub(1)),
+        error,
+    })
+}
+
+// =====================================================
+// Tauriコマンド
+// =====================================================
+
+/**
+ * ドキュメントのセルを実行する
+ * lineを指定するとその行（0始まり）を含むセルだけ、省略するとすべてのセルを順に実行する
+ * documentはファイルパス（未保存ならNone）で、同じドキュメントのセルは同じ名前空間で実行する
+ * job_idを指定するとcancel_python_executionで停止できる
+ */
+#[cfg(feature = "python")]
+#[tauri::command]
+pub async fn run_python_cells(
+    sessions: tauri::State<'_, PythonSessions>,
+    jobs: tauri::State<'_, PythonJobs>,
+    document: Option<String>,
+    text: String,
+    line: Option<usize>,
+    timeout_ms: Option<u64>,
+    job_id: Option<String>,
+) -> Result<CellRunResult, PythonExecError> {
+    // 区切り行のないドキュメント（Python以外のファイルなど）を丸ごと実行しない
+    if !text.split('\n').any(is_cell_marker) {
+        return Err(PythonExecError::Error(format!("No cell marker ({}) found in the document", CELL_MARKER)));
+    }
+
+    let filename = document.unwrap_or_else(|| UNTITLED_DOCUMENT.to_string());
+    let session_id = format!("cells:{}", filename);
+    let sessions = sessions.inner().clone();
+
+    let outcome = jobs.run(job_id, timeout_ms, move |py| {
+        let globals = sessions.globals_or_create(py, &session_id)?;
+        run_cells(py, &globals, &text, &filename, line)
+    })
+    .await?;
+
+    match &outcome.error {
+        Some(error) => println!("🐍 Ran {} cell(s), stopped at line {:?}: {}", outcome.cells_run, error.line, error.message),
+        None => println!("🐍 Ran {} cell(s)", outcome.cells_run),
+    }
+    Ok(outcome)
+}
+
+#[cfg(not(feature = "python"))]
+#[tauri::command]
+pub async fn run_python_cells() -> Result<CellRunResult, PythonExecError> {
+    Err(crate::python_exec::PYTHON_UNAVAILABLE.to_string().into())
+}
@@ -0,0 +1,382 @@
+/*
+ * =====================================================
+ * Vinsert Editor - 拡張機能用のPython環境
+ * ユーザーが指定した仮想環境（venv）や追加のsite-packagesを
+ * 起動時にsys.pathへ追加し、numpy等のライブラリを拡張機能から使えるようにする
+ * 組み込みPythonとABI（メジャー・マイナーバージョン）が異なる環境は追加しない
+ * =====================================================
+ */
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+/// 仮想環境の設定ファイル
+const PYVENV_CFG: &str = "pyvenv.cfg";
+
+/// site-packages内で拡張モジュールのABIタグを探す深さ
+const ABI_SCAN_DEPTH: usize = 2;
+
+/**
+ * 拡張機能用のPython環境の設定（python_environment.json）
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PythonEnvironmentSettings {
+    /// 仮想環境のディレクトリ（pyvenv.cfgのあるディレクトリ）
+    #[serde(default)]
+    pub venv: Option<String>,
+    /// 追加のsite-packagesディレクトリ
+    #[serde(default)]
+    pub site_packages: Vec<String>,
+}
+
+impl PythonEnvironmentSettings {
+    fn is_empty(&self) -> bool {
+        self.venv.is_none() && self.site_packages.is_empty()
+    }
+}
+
+/**
+ * 起動時に適用した結果（診断情報としてPythonRuntimeに含める）
+ */
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PythonEnvironmentStatus {
+    pub venv: Option<String>,
+    /// pyvenv.cfgに記録されたPythonのバージョン
+    pub venv_version: Option<String>,
+    /// sys.pathへ追加したディレクトリ
+    pub added_paths: Vec<String>,
+    /// 追加しなかったディレクトリとその理由
+    pub errors: Vec<String>,
+}
+
+fn settings_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    Ok(app_data.join("vinsert").join("python_environment.json"))
+}
+
+/**
+ * ウィンドウを作らないテストモード用の設定ファイルのパス
+ * AppHandleがないため、app_data_dir（data_dir/識別子）と同じ場所を組み立てる
+ */
+pub fn saved_settings_path(identifier: &str) -> Option<PathBuf> {
+    Some(dirs::data_dir()?.join(identifier).join("vinsert").join("python_environment.json"))
+}
+
+fn read_settings(path: &Path) -> Result<PythonEnvironmentSettings, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid Python environment '{}': {}", path.display(), e))
+}
+
+fn load_settings(path: &Path) -> PythonEnvironmentSettings {
+    read_settings(path).unwrap_or_default()
+}
+
+fn save_settings(path: &Path, settings: &PythonEnvironmentSettings) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create '{}': {}", parent.display(), e))?;
+    }
+    let content = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize Python environment: {}", e))?;
+    std::fs::write(path, content).map_err(|e| format!("Failed to write '{}': {}", path.display(), e))
+}
+
+/**
+ * "3.11.7" のようなバージョン文字列から (major, minor) を取り出す
+ */
+fn parse_major_minor(version: &str) -> Option<(u32, u32)> {
+    let mut parts = version.trim().split('.');
+    let major = parts.next()?.trim().parse().ok()?;
+    let minor: String = parts.next()?.chars().take_while(|c| c.is_ascii_digit()).collect();
+    Some((major, minor.parse().ok()?))
+}
+
+/**
+ * pyvenv.cfgからPythonのバージョンを読む
+ * venvは version、uvやvirtualenvは version_info に記録する
+ */
+fn read_venv_version(venv: &Path) -> Result<String, String> {
+    let cfg_path = venv.join(PYVENV_CFG);
+    let content = std::fs::read_to_string(&cfg_path)
+        .map_err(|e| format!("'{}' is not a virtual environment: {}", venv.display(), e))?;
+    content
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .find(|(key, _)| matches!(key.trim(), "version" | "version_info"))
+        .map(|(_, value)| value.trim().to_string())
+        .ok_or_else(|| format!("No Python version recorded in '{}'", cfg_path.display()))
+}
+
+/**
+ * 仮想環境のsite-packagesディレクトリ
+ */
+fn venv_site_packages(venv: &Path, (major, minor): (u32, u32)) -> PathBuf {
+    if cfg!(windows) {
+        venv.join("Lib").join("site-packages")
+    } else {
+        venv.join("lib").join(format!("python{}.{}", major, minor)).join("site-packages")
+    }
+}
+
+/**
+ * 仮想環境のPython実行ファイル
+ */
+pub fn venv_python(venv: &Path) -> PathBuf {
+    if cfg!(windows) {
+        venv.join("Scripts").join("python.exe")
+    } else {
+        venv.join("bin").join("python")
+    }
+}
+
+/**
+ * ディレクトリのパスに含まれる pythonX.Y からバージョンを推定する
+ * （lib/python3.12/site-packages など）
+ */
+fn version_from_path(dir: &Path) -> Option<(u32, u32)> {
+    dir.components().rev().find_map(|component| {
+        let name = component.as_os_str().to_str()?;
+        parse_major_minor(name.strip_prefix("python")?)
+    })
+}
+
+/**
+ * 拡張モジュールのファイル名のABIタグからバージョンを推定する
+ * （_core.cpython-312-x86_64-linux-gnu.so、_core.cp312-win_amd64.pyd など）
+ */
+fn version_from_extension_modules(dir: &Path, depth: usize) -> Option<(u32, u32)> {
+    let entries = std::fs::read_dir(dir).ok()?;
+    let mut subdirectories = Vec::new();
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        if path.is_dir() {
+            subdirectories.push(path);
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !(name.ends_with(".so") || name.ends_with(".pyd")) {
+            continue;
+        }
+        let tag = name
+            .split('.')
+            .find_map(|part| part.strip_prefix("cpython-").or_else(|| part.strip_prefix("cp")));
+        if let Some(version) = tag.and_then(abi_tag_version) {
+            return Some(version);
+        }
+    }
+    if depth == 0 {
+        return None;
+    }
+    subdirectories
+        .iter()
+        .find_map(|subdirectory| version_from_extension_modules(subdirectory, depth - 1))
+}
+
+/**
+ * "312-x86_64-linux-gnu" や "312-win_amd64" を (3, 12) にする
+ */
+fn abi_tag_version(tag: &str) -> Option<(u32, u32)> {
+    let digits: String = tag.chars().take_while(|c| c.is_ascii_digit()).collect();
+    if digits.len() < 2 {
+        return None;
+    }
+    let (major, minor) = digits.split_at(1);
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+fn abi_mismatch(what: &Path, found: (u32, u32), interpreter: (u32, u32)) -> String {
+    format!(
+        "'{}' targets Python {}.{} but Vinsert runs Python {}.{}",
+        what.display(),
+        found.0,
+        found.1,
+        interpreter.0,
+        interpreter.1
+    )
+}
+
+/**
+ * 設定を検証し、sys.pathへ追加するディレクトリを決める
+ * 問題のあるディレクトリはstatus.errorsに理由を記録して除外する
+ */
+fn resolve(settings: &PythonEnvironmentSettings, interpreter: (u32, u32)) -> (Vec<PathBuf>, PythonEnvironmentStatus) {
+    let mut paths = Vec::new();
+    let mut status = PythonEnvironmentStatus {
+        venv: settings.venv.clone(),
+        ..Default::default()
+    };
+
+    if let Some(venv) = &settings.venv {
+        let venv = Path::new(venv);
+        let checked = read_venv_version(venv).and_then(|version| {
+            status.venv_version = Some(version.clone());
+            let found = parse_major_minor(&version)
+                .ok_or_else(|| format!("Invalid Python version '{}' in '{}'", version, venv.join(PYVENV_CFG).display()))?;
+            if found != interpreter {
+                return Err(abi_mismatch(venv, found, interpreter));
+            }
+            let site_packages = venv_site_packages(venv, found);
+            if !site_packages.is_dir() {
+                return Err(format!("site-packages not found in '{}'", venv.display()));
+            }
+            Ok(site_packages)
+        });
+        match checked {
+            Ok(site_packages) => paths.push(site_packages),
+            Err(e) => status.errors.push(e),
+        }
+    }
+
+    for dir in &settings.site_packages {
+        let dir = Path::new(dir);
+        if !dir.is_dir() {
+            status.errors.push(format!("'{}' is not a directory", dir.display()));
+            continue;
+        }
+        let found = version_from_path(dir).or_else(|| version_from_extension_modules(dir, ABI_SCAN_DEPTH));
+        match found {
+            Some(found) if found != interpreter => status.errors.push(abi_mismatch(dir, found, interpreter)),
+            _ => paths.push(dir.to_path_buf()),
+        }
+    }
+
+    (paths, status)
+}
+
+/**
+ * site.addsitedirでsys.pathへ追加する（.pthファイルも処理される）
+ */
+#[cfg(feature = "python")]
+fn add_site_dirs(py: Python<'_>, paths: &[PathBuf], status: &mut PythonEnvironmentStatus) {
+    let site = match py.import_bound("site") {
+        Ok(site) => site,
+        Err(e) => {
+            status.errors.push(format!("Failed to import site: {}", e));
+            return;
+        }
+    };
+    for path in paths {
+        let path = path.to_string_lossy().to_string();
+        match site.call_method1("addsitedir", (&path,)) {
+            Ok(_) => status.added_paths.push(path),
+            Err(e) => status.errors.push(format!("Failed to add '{}' to sys.path: {}", path, e)),
+        }
+    }
+}
+
+/**
+ * 保存された設定をPythonに適用する（起動時、実行環境の検出前に一度だけ呼ぶ）
+ */
+pub fn apply(app: &tauri::AppHandle) -> PythonEnvironmentStatus {
+    match settings_path(app) {
+        Ok(path) => apply_file(&path),
+        Err(e) => {
+            println!("⚠️ {}", e);
+            PythonEnvironmentStatus::default()
+        }
+    }
+}
+
+/**
+ * 設定ファイルを読んでPythonに適用する（ファイルがない・読めない場合は何もしない）
+ */
+pub fn apply_file(path: &Path) -> PythonEnvironmentStatus {
+    apply_with(&load_settings(path))
+}
+
+/**
+ * 設定ファイルを読んでPythonに適用する（--python-envで明示された場合など、読めなければエラー）
+ */
+pub fn apply_file_strict(path: &Path) -> Result<PythonEnvironmentStatus, String> {
+    Ok(apply_with(&read_settings(path)?))
+}
+
+fn apply_with(settings: &PythonEnvironmentSettings) -> PythonEnvironmentStatus {
+    if settings.is_empty() {
+        return PythonEnvironmentStatus::default();
+    }
+
+    let status = apply_settings(settings);
+    for path in &status.added_paths {
+        println!("🐍 Extension Python path added: {}", path);
+    }
+    for error in &status.errors {
+        println!("⚠️ Extension Python environment: {}", error);
+    }
+    status
+}
+
+#[cfg(feature = "python")]
+fn apply_settings(settings: &PythonEnvironmentSettings) -> PythonEnvironmentStatus {
+    Python::with_gil(|py| {
+        let version = py.version_info();
+        let (paths, mut status) = resolve(settings, (version.major as u32, version.minor as u32));
+        add_site_dirs(py, &paths, &mut status);
+        status
+    })
+}
+
+#[cfg(not(feature = "python"))]
+fn apply_settings(settings: &PythonEnvironmentSettings) -> PythonEnvironmentStatus {
+    PythonEnvironmentStatus {
+        venv: settings.venv.clone(),
+        errors: vec![crate::python_exec::PYTHON_UNAVAILABLE.to_string()],
+        ..Default::default()
+    }
+}
+
+// =====================================================
+// Tauriコマンド
+// =====================================================
+
+/**
+ * 拡張機能用のPython環境の設定を返す
+ */
+#[tauri::command]
+pub fn get_python_environment(app: tauri::AppHandle) -> Result<PythonEnvironmentSettings, String> {
+    Ok(load_settings(&settings_path(&app)?))
+}
+
+/**
+ * 拡張機能用のPython環境の設定を検証して保存する
+ * sys.pathから取り除くことはできないため、変更は次回起動時に反映される
+ */
+#[tauri::command]
+pub fn set_python_environment(
+    app: tauri::AppHandle,
+    runtime: tauri::State<'_, crate::python_runtime::PythonRuntime>,
+    settings: PythonEnvironmentSettings,
+) -> Result<(), String> {
+    crate::python_exec::ensure_available()?;
+    let settings = PythonEnvironmentSettings {
+        venv: settings
+            .venv
+            .map(|venv| venv.trim().to_string())
+            .filter(|venv| !venv.is_empty()),
+        site_packages: settings
+            .site_packages
+            .iter()
+            .map(|dir| dir.trim().to_string())
+            .filter(|dir| !dir.is_empty())
+            .collect(),
+    };
+
+    let (major, minor, _) = runtime
+        .version
+        .ok_or_else(|| "Python version is unknown".to_string())?;
+    let (_, status) = resolve(&settings, (major, minor));
+    if !status.errors.is_empty() {
+        return Err(status.errors.join("\n"));
+    }
+
+    save_settings(&settings_path(&app)?, &settings)?;
+    println!("🐍 Extension Python environment saved (applies on next launch)");
+    Ok(())
+}
@@ -0,0 +1,209 @@
+/*
+ * =====================================================
+ * Vinsert Editor - 選択範囲の評価（計算メモ用）
+ * 選択範囲（または現在行）をPythonの式として評価し、置き換えるテキストを返す
+ * 名前空間はアプリ起動中保持し、math・datetime・decimal・statisticsを読み込んでおく
+ * =====================================================
+ */
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3::types::PyDict;
+#[cfg(feature = "python")]
+use serde::Deserialize;
+use serde::Serialize;
+#[cfg(feature = "python")]
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "python")]
+use crate::python_exec::PythonJobs;
+use crate::python_exec::PythonExecError;
+
+/// 評価の制限時間
+#[cfg(feature = "python")]
+const EVALUATE_TIMEOUT_MS: u64 = 5_000;
+
+/// トレースバックに表示するファイル名
+#[cfg(feature = "python")]
+const SELECTION_FILENAME: &str = "<selection>";
+
+/// 名前空間に最初に読み込んでおくモジュールと名前
+#[cfg(feature = "python")]
+const PRELUDE: &str = "\
+import math
+import datetime
+import decimal
+import statistics
+from math import *
+from datetime import date, time, timedelta
+from decimal import Decimal
+from statistics import mean, median, stdev
+";
+
+/**
+ * 評価結果の挿入方法
+ */
+#[cfg(feature = "python")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvaluateMode {
+    /// 選択範囲を結果で置き換える
+    Replace,
+    /// 選択範囲の後ろに " = 結果" を追加する
+    #[default]
+    Append,
+    /// 選択範囲の次の行に結果を挿入する
+    InsertBelow,
+}
+
+/**
+ * evaluate_selectionの戻り値
+ * textは選択範囲と置き換えるテキスト、valueは式の値（文の場合はNone）
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct EvaluationResult {
+    pub text: String,
+    pub value: Option<String>,
+}
+
+/**
+ * 評価用の名前空間（Tauriのmanaged stateとして保持、初回の評価時に作成）
+ */
+#[derive(Clone, Default)]
+pub struct EvaluationNamespace {
+    #[cfg(feature = "python")]
+    globals: Arc<Mutex<Option<Py<PyDict>>>>,
+}
+
+impl EvaluationNamespace {
+    pub fn new() -> Self {
+        EvaluationNamespace::default()
+    }
+
+    #[cfg(feature = "python")]
+    fn globals<'py>(&self, py: Python<'py>) -> Result<Bound<'py, PyDict>, String> {
+        let mut globals = self.globals.lock().map_err(|e| e.to_string())?;
+        if let Some(globals) = globals.as_ref() {
+            return Ok(globals.bind(py).clone());
+        }
+
+        let created = PyDict::new_bound(py);
+        py.run_bound(PRELUDE, Some(&created), None)
+            .map_err(|e| format!("Failed to prepare evaluation namespace: {}", e))?;
+        *globals = Some(created.clone().unbind());
+        Ok(created)
+    }
+}
+
+/**
+ * 評価する式（末尾の "=" は計算メモの書きかけとして取り除く）
+ */
+#[cfg(feature = "python")]
+fn expression_of(text: &str) -> &str {
+    text.trim().trim_end_matches('=').trim_end()
+}
+
+/**
+ * 挿入方法に応じて置き換えるテキストを作る
+ */
+#[cfg(feature = "python")]
+fn replacement(text: &str, value: &str, mode: EvaluateMode) -> String {
+    match mode {
+        EvaluateMode::Replace => value.to_string(),
+        EvaluateMode::Append => {
+            let trimmed = text.trim_end();
+            let expression = trimmed.trim_end_matches('=').trim_end();
+            format!("{} = {}", expression, value)
+        }
+        EvaluateMode::InsertBelow => {
+            // 1行目のインデントに揃える
+            let indent: String = text.chars().take_while(|c| *c == ' ' || *c == '\t').collect();
+            format!("{}\n{}{}", text.trim_end_matches(['\r', '\n']), indent, value)
+        }
+    }
+}
+
+/**
+ * 式として評価できれば値を返し、文であれば実行だけ行う（代入した変数は次の評価で使える）
+ */
+#[cfg(feature = "python")]
+fn evaluate(py: Python<'_>, globals: &Bound<'_, PyDict>, expression: &str) -> Result<Option<String>, String> {
+    let builtins = py
+        .import_bound("builtins")
+        .map_err(|e| format!("Failed to import builtins: {}", e))?;
+
+    let describe = |e: PyErr| -> String {
+        let name = e
+            .get_type_bound(py)
+            .name()
+            .map(|name| name.to_string())
+            .unwrap_or_else(|_| "Error".to_string());
+        format!("{}: {}", name, e.value_bound(py))
+    };
+
+    match builtins.call_method1("compile", (expression, SELECTION_FILENAME, "eval")) {
+        Ok(compiled) => {
+            let value = builtins
+                .call_method1("eval", (compiled, globals))
+                .map_err(describe)?;
+            // 対話型インタープリターと同じく直前の値を _ で参照できるようにする
+            globals.set_item("_", &value).map_err(describe)?;
+            if value.is_none() {
+                return Ok(None);
+            }
+            value.str().map(|value| Some(value.to_string())).map_err(describe)
+        }
+        Err(e) if e.is_instance_of::<pyo3::exceptions::PySyntaxError>(py) => {
+            let compiled = builtins
+                .call_method1("compile", (expression, SELECTION_FILENAME, "exec"))
+                .map_err(describe)?;
+            builtins
+                .call_method1("exec", (compiled, globals))
+                .map_err(describe)?;
+            Ok(None)
+        }
+        Err(e) => Err(describe(e)),
+    }
+}
+
+// =====================================================
+// Tauriコマンド
+// =====================================================
+
+/**
+ * 選択範囲のテキストを評価し、modeに応じた置き換え後のテキストを返す
+ * 値のない文（代入など）の場合はtextをそのまま返す
+ */
+#[cfg(feature = "python")]
+#[tauri::command]
+pub async fn evaluate_selection(
+    namespace: tauri::State<'_, EvaluationNamespace>,
+    jobs: tauri::State<'_, PythonJobs>,
+    text: String,
+    mode: Option<EvaluateMode>,
+) -> Result<EvaluationResult, PythonExecError> {
+    let expression = expression_of(&text).to_string();
+    if expression.is_empty() {
+        return Err(PythonExecError::Error("Nothing to evaluate".to_string()));
+    }
+
+    let namespace = namespace.inner().clone();
+    let value = jobs.run(None, Some(EVALUATE_TIMEOUT_MS), move |py| {
+        let globals = namespace.globals(py)?;
+        evaluate(py, &globals, &expression)
+    })
+    .await?;
+
+    let text = match &value {
+        Some(value) => replacement(&text, value, mode.unwrap_or_default()),
+        None => text,
+    };
+    Ok(EvaluationResult { text, value })
+}
+
+#[cfg(not(feature = "python"))]
+#[tauri::command]
+pub async fn evaluate_selection() -> Result<EvaluationResult, PythonExecError> {
+    Err(crate::python_exec::PYTHON_UNAVAILABLE.to_string().into())
+}
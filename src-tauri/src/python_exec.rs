@@ -0,0 +1,810 @@
+/*
+ * =====================================================
+ * Vinsert Editor - Python実行ジョブ管理
+ * Pythonコードをワーカースレッドで実行し、期限切れやキャンセル時に
+ * インタープリターへKeyboardInterruptを送って中断する
+ * =====================================================
+ */
+
+#[cfg(feature = "python")]
+use pyo3::ffi;
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3::types::PyDict;
+use serde::Serialize;
+#[cfg(feature = "python")]
+use std::cell::RefCell;
+#[cfg(feature = "python")]
+use std::collections::HashMap;
+#[cfg(feature = "python")]
+use std::os::raw::c_long;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+#[cfg(feature = "python")]
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::time::timeout;
+#[cfg(feature = "python")]
+use std::time::Instant;
+
+/// execute_python / run_python_file の既定の制限時間
+pub const DEFAULT_TIMEOUT_MS: u64 = 30_000;
+
+/// タイムアウト後、中断の要求とワーカースレッドの終了を待つ時間
+#[cfg(feature = "python")]
+const INTERRUPT_GRACE_MS: u64 = 2_000;
+
+/// Pythonサポートなし（pythonフィーチャー無効）でビルドした場合のエラー
+pub const PYTHON_UNAVAILABLE: &str = "Python support not compiled in";
+
+/// ストリーミング実行中の出力イベント
+#[cfg(feature = "python")]
+pub const PYTHON_OUTPUT_EVENT: &str = "python://output";
+
+/// ストリーミング実行の完了イベント
+#[cfg(feature = "python")]
+pub const PYTHON_COMPLETE_EVENT: &str = "python://complete";
+
+/**
+ * Python実行のエラー
+ * フロントエンドには { kind: "Timeout", message: "..." } の形で渡る
+ */
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", content = "message")]
+pub enum PythonExecError {
+    Timeout(String),
+    // Pythonサポートなしのビルドではキャンセルできない
+    #[cfg_attr(not(feature = "python"), allow(dead_code))]
+    Cancelled(String),
+    Error(String),
+}
+
+impl std::fmt::Display for PythonExecError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PythonExecError::Timeout(message) => write!(f, "Timeout: {}", message),
+            PythonExecError::Cancelled(message) => write!(f, "Cancelled: {}", message),
+            PythonExecError::Error(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<String> for PythonExecError {
+    fn from(message: String) -> Self {
+        PythonExecError::Error(message)
+    }
+}
+
+/**
+ * Pythonサポート付きでビルドされているか確認する
+ */
+pub fn ensure_available() -> Result<(), String> {
+    if cfg!(feature = "python") {
+        Ok(())
+    } else {
+        Err(PYTHON_UNAVAILABLE.to_string())
+    }
+}
+
+#[cfg(feature = "python")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Interrupt {
+    Timeout,
+    Cancelled,
+}
+
+/**
+ * 実行中のジョブ
+ * thread_identはワーカースレッドがGILを取得した時点で設定される
+ */
+#[cfg(feature = "python")]
+#[derive(Debug, Default)]
+struct RunningJob {
+    thread_ident: Option<c_long>,
+    interrupted: Option<Interrupt>,
+}
+
+/**
+ * 実行中のPythonジョブ一覧（Tauriのmanaged stateとして保持）
+ */
+#[derive(Clone, Default)]
+pub struct PythonJobs {
+    #[cfg(feature = "python")]
+    jobs: Arc<Mutex<HashMap<String, RunningJob>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl PythonJobs {
+    pub fn new() -> Self {
+        PythonJobs::default()
+    }
+
+    /**
+     * 新しいジョブIDを払い出す
+     */
+    pub fn next_job_id(&self) -> String {
+        format!("python-job-{}", self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    /**
+     * ワーカースレッドでPythonの処理を実行する
+     * timeout_msを過ぎた場合はKeyboardInterruptで中断してTimeoutを返す
+     * 結果は非同期に待つため、asyncコマンドから呼んでもランタイムのスレッドを塞がない
+     */
+    #[cfg(feature = "python")]
+    pub async fn run<T, F>(
+        &self,
+        job_id: Option<String>,
+        timeout_ms: Option<u64>,
+        task: F,
+    ) -> Result<T, PythonExecError>
+    where
+        T: Send + 'static,
+        F: FnOnce(Python<'_>) -> Result<T, String> + Send + 'static,
+    {
+        let job_id = job_id.unwrap_or_else(|| self.next_job_id());
+        let timeout_ms = timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS);
+
+        {
+            let mut jobs = self.jobs.lock().map_err(|e| e.to_string())?;
+            if jobs.contains_key(&job_id) {
+                return Err(PythonExecError::Error(format!("Python job '{}' is already running", job_id)));
+            }
+            jobs.insert(job_id.clone(), RunningJob::default());
+        }
+
+        let (sender, mut receiver) = oneshot::channel();
+        let jobs = Arc::clone(&self.jobs);
+        let worker_job_id = job_id.clone();
+
+        let spawned = std::thread::Builder::new()
+            .name(format!("vinsert-{}", job_id))
+            .spawn(move || {
+                // ジョブの実行はエディタ本体の処理として扱う（拡張機能の呼び出しは権限のコンテキストを設定する）
+                crate::vinsert_api::mark_host_thread();
+                let outcome = Python::with_gil(|py| {
+                    // GIL待ちの間に中断された場合は実行しない
+                    let interrupted = register_thread(py, &jobs, &worker_job_id);
+                    let result = match interrupted {
+                        Some(_) => Err("Python job was interrupted before it started".to_string()),
+                        None => task(py),
+                    };
+                    let interrupted = unregister_thread(py, &jobs, &worker_job_id);
+                    (result, interrupted)
+                });
+                let _ = sender.send(outcome);
+            });
+
+        if let Err(e) = spawned {
+            self.remove(&job_id);
+            return Err(PythonExecError::Error(format!("Failed to start Python worker thread: {}", e)));
+        }
+
+        let outcome = match timeout(Duration::from_millis(timeout_ms), &mut receiver).await {
+            Ok(Ok(outcome)) => outcome,
+            Ok(Err(_)) => {
+                self.remove(&job_id);
+                return Err(PythonExecError::Error("Python worker thread terminated unexpectedly".to_string()));
+            }
+            Err(_) => {
+                println!("⏱️ Python job timed out: {} ({} ms)", job_id, timeout_ms);
+                // 中断にはGILが必要なため、ランタイムのスレッドでは待たない
+                // GILを保持したままC拡張で止まっている場合は中断自体が終わらないため、まとめて制限時間を設ける
+                let jobs = self.clone();
+                let interrupted_job_id = job_id.clone();
+                let stopped = async {
+                    let _ = tauri::async_runtime::spawn_blocking(move || {
+                        jobs.interrupt(&interrupted_job_id, Interrupt::Timeout)
+                    })
+                    .await;
+                    (&mut receiver).await
+                };
+                match timeout(Duration::from_millis(INTERRUPT_GRACE_MS), stopped).await {
+                    Ok(Ok(outcome)) => outcome,
+                    _ => {
+                        return Err(PythonExecError::Timeout(format!(
+                            "Python execution exceeded {} ms and did not stop after interruption",
+                            timeout_ms
+                        )));
+                    }
+                }
+            }
+        };
+
+        // 中断した場合は処理結果（例外を含む）より中断理由を優先する
+        match outcome {
+            (_, Some(Interrupt::Timeout)) => Err(PythonExecError::Timeout(format!(
+                "Python execution exceeded {} ms",
+                timeout_ms
+            ))),
+            (_, Some(Interrupt::Cancelled)) => Err(PythonExecError::Cancelled(format!(
+                "Python job '{}' was cancelled",
+                job_id
+            ))),
+            (result, _) => result.map_err(PythonExecError::Error),
+        }
+    }
+
+    /**
+     * Pythonサポートなしのビルドではワーカースレッドで実行するだけ（中断はできない）
+     * Python以外の拡張機能（wasm）の呼び出しに使う
+     */
+    #[cfg(not(feature = "python"))]
+    pub async fn run<T, F>(
+        &self,
+        job_id: Option<String>,
+        timeout_ms: Option<u64>,
+        task: F,
+    ) -> Result<T, PythonExecError>
+    where
+        T: Send + 'static,
+        F: FnOnce(()) -> Result<T, String> + Send + 'static,
+    {
+        let job_id = job_id.unwrap_or_else(|| self.next_job_id());
+        let timeout_ms = timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS);
+
+        let (sender, receiver) = oneshot::channel();
+        std::thread::Builder::new()
+            .name(format!("vinsert-{}", job_id))
+            .spawn(move || {
+                let _ = sender.send(task(()));
+            })
+            .map_err(|e| PythonExecError::Error(format!("Failed to start worker thread: {}", e)))?;
+
+        match timeout(Duration::from_millis(timeout_ms), receiver).await {
+            Ok(Ok(result)) => result.map_err(PythonExecError::Error),
+            Ok(Err(_)) => Err(PythonExecError::Error("Worker thread terminated unexpectedly".to_string())),
+            Err(_) => {
+                println!("⏱️ Job timed out: {} ({} ms)", job_id, timeout_ms);
+                Err(PythonExecError::Timeout(format!("Execution exceeded {} ms", timeout_ms)))
+            }
+        }
+    }
+
+    /**
+     * runの完了を待つ（asyncランタイムの外のスレッド用。拡張機能の監視やヘッドレステストなど）
+     */
+    #[cfg(feature = "python")]
+    pub fn run_blocking<T, F>(
+        &self,
+        job_id: Option<String>,
+        timeout_ms: Option<u64>,
+        task: F,
+    ) -> Result<T, PythonExecError>
+    where
+        T: Send + 'static,
+        F: FnOnce(Python<'_>) -> Result<T, String> + Send + 'static,
+    {
+        tauri::async_runtime::block_on(self.run(job_id, timeout_ms, task))
+    }
+
+    #[cfg(not(feature = "python"))]
+    pub fn run_blocking<T, F>(
+        &self,
+        job_id: Option<String>,
+        timeout_ms: Option<u64>,
+        task: F,
+    ) -> Result<T, PythonExecError>
+    where
+        T: Send + 'static,
+        F: FnOnce(()) -> Result<T, String> + Send + 'static,
+    {
+        tauri::async_runtime::block_on(self.run(job_id, timeout_ms, task))
+    }
+
+    /**
+     * 実行中のジョブをキャンセルする
+     * 該当するジョブが無い場合はfalseを返す
+     */
+    #[cfg(feature = "python")]
+    pub fn cancel(&self, job_id: &str) -> bool {
+        self.interrupt(job_id, Interrupt::Cancelled)
+    }
+
+    /**
+     * Pythonサポートなしのビルドでは中断できないため、常にfalseを返す
+     */
+    #[cfg(not(feature = "python"))]
+    pub fn cancel(&self, _job_id: &str) -> bool {
+        false
+    }
+
+    /**
+     * ジョブを実行しているスレッドにKeyboardInterruptを送る
+     */
+    #[cfg(feature = "python")]
+    fn interrupt(&self, job_id: &str, reason: Interrupt) -> bool {
+        Python::with_gil(|_py| {
+            let mut jobs = match self.jobs.lock() {
+                Ok(jobs) => jobs,
+                Err(_) => return false,
+            };
+            let job = match jobs.get_mut(job_id) {
+                Some(job) => job,
+                None => return false,
+            };
+
+            job.interrupted.get_or_insert(reason);
+            if let Some(thread_ident) = job.thread_ident {
+                // GIL保持中に呼び出す必要がある
+                unsafe {
+                    ffi::PyThreadState_SetAsyncExc(thread_ident, ffi::PyExc_KeyboardInterrupt);
+                }
+            }
+            true
+        })
+    }
+
+    #[cfg(feature = "python")]
+    fn remove(&self, job_id: &str) {
+        if let Ok(mut jobs) = self.jobs.lock() {
+            jobs.remove(job_id);
+        }
+    }
+}
+
+/**
+ * ワーカースレッドのスレッドIDをジョブに登録する
+ * 既に中断要求が出ている場合はその理由を返す
+ */
+#[cfg(feature = "python")]
+fn register_thread(
+    py: Python<'_>,
+    jobs: &Mutex<HashMap<String, RunningJob>>,
+    job_id: &str,
+) -> Option<Interrupt> {
+    let thread_ident = py
+        .import_bound("threading")
+        .and_then(|threading| threading.call_method0("get_ident"))
+        .and_then(|ident| ident.extract::<u64>())
+        .ok()
+        .map(|ident| ident as c_long);
+
+    let mut jobs = jobs.lock().ok()?;
+    let job = jobs.get_mut(job_id)?;
+    job.thread_ident = thread_ident;
+    job.interrupted
+}
+
+/**
+ * ジョブの登録を解除し、未配送の非同期例外を取り消す
+ */
+#[cfg(feature = "python")]
+fn unregister_thread(
+    py: Python<'_>,
+    jobs: &Mutex<HashMap<String, RunningJob>>,
+    job_id: &str,
+) -> Option<Interrupt> {
+    let job = jobs.lock().ok()?.remove(job_id)?;
+    if let Some(thread_ident) = job.thread_ident {
+        unsafe {
+            ffi::PyThreadState_SetAsyncExc(thread_ident, std::ptr::null_mut());
+        }
+    }
+    // 中断用のKeyboardInterruptがエラー表示に残らないようにする
+    if job.interrupted.is_some() {
+        let _ = PyErr::take(py);
+    }
+    job.interrupted
+}
+
+// =====================================================
+// 実行結果のキャプチャ
+// =====================================================
+
+/**
+ * トレースバックの1フレーム
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct TracebackFrame {
+    pub file: String,
+    pub line: Option<usize>,
+    pub function: String,
+    pub source: Option<String>,
+}
+
+/**
+ * Pythonコードの実行結果
+ * 例外はErrではなくexception_type/tracebackとして返す
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct ExecutionResult {
+    pub stdout: String,
+    pub stderr: String,
+    pub result_repr: Option<String>,
+    pub exception_type: Option<String>,
+    pub exception_message: Option<String>,
+    pub traceback: Vec<TracebackFrame>,
+    pub duration_ms: u64,
+}
+
+/**
+ * 書き込みごとに呼ばれるリスナー（引数はストリーム名 "stdout"/"stderr" と書き込まれた文字列）
+ */
+#[cfg(feature = "python")]
+pub type OutputListener = Arc<dyn Fn(&str, &str) + Send + Sync>;
+
+/**
+ * sys.stdout/sys.stderrの差し替え用ストリーム
+ * 書き込まれた内容を蓄積し、リスナーがあれば書き込みごとに通知する
+ */
+#[cfg(feature = "python")]
+#[pyclass(frozen)]
+struct CapturedStream {
+    name: &'static str,
+    buffer: Mutex<String>,
+    listener: Option<OutputListener>,
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl CapturedStream {
+    fn write(&self, text: &str) -> usize {
+        if let Ok(mut buffer) = self.buffer.lock() {
+            buffer.push_str(text);
+        }
+        if let Some(listener) = &self.listener {
+            if !text.is_empty() {
+                listener(self.name, text);
+            }
+        }
+        text.chars().count()
+    }
+
+    fn flush(&self) {}
+
+    fn isatty(&self) -> bool {
+        false
+    }
+
+    fn writable(&self) -> bool {
+        true
+    }
+
+    #[getter]
+    fn encoding(&self) -> &'static str {
+        "utf-8"
+    }
+}
+
+#[cfg(feature = "python")]
+impl CapturedStream {
+    fn new(name: &'static str, listener: Option<OutputListener>) -> Self {
+        CapturedStream {
+            name,
+            buffer: Mutex::new(String::new()),
+            listener,
+        }
+    }
+
+    fn contents(&self) -> String {
+        self.buffer.lock().map(|buffer| buffer.clone()).unwrap_or_default()
+    }
+}
+
+/**
+ * 実行中のスレッドのキャプチャ先（stdout, stderr）
+ */
+#[cfg(feature = "python")]
+struct ThreadCapture {
+    stdout: Py<CapturedStream>,
+    stderr: Py<CapturedStream>,
+}
+
+#[cfg(feature = "python")]
+thread_local! {
+    // ワーカースレッドごとのキャプチャ先（run_compiledの実行中のみ設定される）
+    static THREAD_CAPTURE: RefCell<Option<ThreadCapture>> = const { RefCell::new(None) };
+}
+
+/**
+ * 出力の振り分け
+ * clippy::useless_conversionの抑制はvinsert_api.rsのfunctionsと同じ理由
+ */
+#[cfg(feature = "python")]
+mod dispatch {
+    #![allow(clippy::useless_conversion)]
+
+    use super::*;
+
+    /**
+     * sys.stdout/sys.stderrに一度だけ設定する振り分け用ストリーム
+     * 書き込んだスレッドにキャプチャ先があればそこへ、なければ元のストリームへ書き込む
+     * （ジョブごとにsys.stdoutを差し替えると、同時に実行したジョブの出力が混ざるため）
+     */
+    #[pyclass(frozen)]
+    struct DispatchingStream {
+        name: &'static str,
+        original: PyObject,
+    }
+
+    #[pymethods]
+    impl DispatchingStream {
+        fn write(&self, py: Python<'_>, text: &str) -> PyResult<PyObject> {
+            match self.capture(py) {
+                Some(stream) => Ok(stream.get().write(text).into_py(py)),
+                None if self.original.is_none(py) => Ok(text.chars().count().into_py(py)),
+                None => self.original.call_method1(py, "write", (text,)),
+            }
+        }
+
+        fn flush(&self, py: Python<'_>) -> PyResult<()> {
+            if self.capture(py).is_none() && !self.original.is_none(py) {
+                self.original.call_method0(py, "flush")?;
+            }
+            Ok(())
+        }
+
+        fn isatty(&self, py: Python<'_>) -> PyResult<bool> {
+            if self.capture(py).is_some() || self.original.is_none(py) {
+                return Ok(false);
+            }
+            self.original.call_method0(py, "isatty")?.extract(py)
+        }
+
+        fn writable(&self) -> bool {
+            true
+        }
+
+        // fileno、bufferなどは元のストリームのものを返す
+        fn __getattr__(&self, py: Python<'_>, name: &str) -> PyResult<PyObject> {
+            self.original.getattr(py, name)
+        }
+    }
+
+    impl DispatchingStream {
+        fn capture(&self, py: Python<'_>) -> Option<Py<CapturedStream>> {
+            THREAD_CAPTURE.with(|capture| {
+                capture.borrow().as_ref().map(|capture| match self.name {
+                    "stderr" => capture.stderr.clone_ref(py),
+                    _ => capture.stdout.clone_ref(py),
+                })
+            })
+        }
+    }
+
+    /**
+     * sys.stdout/sys.stderrが振り分け用ストリームでなければ設定する
+     * ユーザーのコードがsys.stdoutを置き換えた場合は、置き換え後のストリームを元のストリームとして包み直す
+     */
+    pub(super) fn install_dispatching_streams(py: Python<'_>) -> PyResult<()> {
+        let sys = py.import_bound("sys")?;
+        for name in ["stdout", "stderr"] {
+            let current = sys.getattr(name)?;
+            if current.is_instance_of::<DispatchingStream>() {
+                continue;
+            }
+            let stream = Bound::new(
+                py,
+                DispatchingStream {
+                    name,
+                    original: current.unbind(),
+                },
+            )?;
+            sys.setattr(name, stream)?;
+        }
+        Ok(())
+    }
+}
+
+/**
+ * stdout/stderrをキャプチャしながらコードを実行する
+ * filenameはトレースバックに表示されるファイル名
+ * register_sourceがtrueの場合、filenameに対応するソースをlinecacheに登録する
+ * （実在しないファイル名でもトレースバックに該当行を表示するため）
+ */
+#[cfg(feature = "python")]
+pub fn run_captured(
+    py: Python<'_>,
+    code: &str,
+    filename: &str,
+    register_source: bool,
+    globals: &Bound<'_, PyDict>,
+) -> Result<ExecutionResult, String> {
+    run_with_listener(py, code, filename, register_source, globals, None)
+}
+
+/**
+ * run_capturedと同じだが、stdout/stderrへの書き込みをその都度listenerへ通知する
+ */
+#[cfg(feature = "python")]
+pub fn run_with_listener(
+    py: Python<'_>,
+    code: &str,
+    filename: &str,
+    register_source: bool,
+    globals: &Bound<'_, PyDict>,
+    listener: Option<OutputListener>,
+) -> Result<ExecutionResult, String> {
+    run_compiled(py, code, filename, "exec", register_source, globals, listener)
+}
+
+/**
+ * 対話モード（"single"）で1つの文を実行する
+ * 式の値は対話型インタープリターと同じくsys.displayhook経由で標準出力に表示される
+ */
+#[cfg(feature = "python")]
+pub fn run_interactive(
+    py: Python<'_>,
+    code: &str,
+    filename: &str,
+    globals: &Bound<'_, PyDict>,
+) -> Result<ExecutionResult, String> {
+    run_compiled(py, code, filename, "single", true, globals, None)
+}
+
+/**
+ * compile()のmode（"exec" / "single"）を指定してキャプチャしながら実行する
+ */
+#[cfg(feature = "python")]
+fn run_compiled(
+    py: Python<'_>,
+    code: &str,
+    filename: &str,
+    mode: &str,
+    register_source: bool,
+    globals: &Bound<'_, PyDict>,
+    listener: Option<OutputListener>,
+) -> Result<ExecutionResult, String> {
+    let builtins = py.import_bound("builtins").map_err(|e| format!("Failed to import builtins: {}", e))?;
+
+    if register_source {
+        register_linecache(py, code, filename).map_err(|e| format!("Failed to register source: {}", e))?;
+    }
+
+    let stdout_buffer = Bound::new(py, CapturedStream::new("stdout", listener.clone()))
+        .map_err(|e| format!("Failed to create stdout capture: {}", e))?;
+    let stderr_buffer = Bound::new(py, CapturedStream::new("stderr", listener))
+        .map_err(|e| format!("Failed to create stderr capture: {}", e))?;
+
+    // 標準出力・標準エラー出力のうち、このスレッドからの書き込みだけをキャプチャする
+    dispatch::install_dispatching_streams(py).map_err(|e| format!("Failed to redirect output: {}", e))?;
+    let previous = THREAD_CAPTURE.with(|capture| {
+        capture.replace(Some(ThreadCapture {
+            stdout: stdout_buffer.clone().unbind(),
+            stderr: stderr_buffer.clone().unbind(),
+        }))
+    });
+
+    let started = Instant::now();
+    let outcome = builtins
+        .call_method1("compile", (code, filename, mode))
+        .and_then(|compiled| builtins.call_method1("exec", (compiled, globals)));
+    let duration_ms = started.elapsed().as_millis() as u64;
+
+    THREAD_CAPTURE.with(|capture| {
+        capture.replace(previous);
+    });
+
+    let stdout = stdout_buffer.get().contents();
+    let stderr = stderr_buffer.get().contents();
+
+    let mut result = ExecutionResult {
+        stdout,
+        stderr,
+        result_repr: None,
+        exception_type: None,
+        exception_message: None,
+        traceback: Vec::new(),
+        duration_ms,
+    };
+
+    match outcome {
+        Ok(_) if mode == "exec" => {
+            // result変数が定義されていればその値を返す
+            if let Ok(Some(value)) = globals.get_item("result") {
+                result.result_repr = value.repr().map(|r| r.to_string()).ok();
+            }
+        }
+        Ok(_) => {}
+        Err(e) => {
+            result.exception_type = e.get_type_bound(py).name().map(|n| n.to_string()).ok();
+            result.exception_message = e.value_bound(py).str().map(|m| m.to_string()).ok();
+            result.traceback = traceback_frames(py, &e);
+        }
+    }
+
+    Ok(result)
+}
+
+/**
+ * ソースをlinecacheに登録する（保存されていない内容でもトレースバックに該当行を表示するため）
+ */
+#[cfg(feature = "python")]
+pub fn register_linecache(py: Python<'_>, code: &str, filename: &str) -> PyResult<()> {
+    let linecache = py.import_bound("linecache")?;
+    let lines = py
+        .import_bound("builtins")?
+        .getattr("str")?
+        .call_method1("splitlines", (code, true))?;
+    linecache
+        .getattr("cache")?
+        .set_item(filename, (code.len(), py.None(), lines, filename))
+}
+
+/**
+ * 例外からトレースバックのフレーム一覧を取り出す
+ * SyntaxErrorはトレースバックを持たないため、例外自身の位置情報を使う
+ */
+#[cfg(feature = "python")]
+fn traceback_frames(py: Python<'_>, error: &PyErr) -> Vec<TracebackFrame> {
+    let mut frames = Vec::new();
+
+    if let Some(traceback) = error.traceback_bound(py) {
+        let summaries = py
+            .import_bound("traceback")
+            .and_then(|module| module.call_method1("extract_tb", (traceback,)))
+            .and_then(|summaries| summaries.extract::<Vec<Bound<'_, PyAny>>>())
+            .unwrap_or_default();
+
+        for summary in summaries {
+            frames.push(TracebackFrame {
+                file: string_attr(&summary, "filename").unwrap_or_default(),
+                line: summary.getattr("lineno").and_then(|l| l.extract()).ok(),
+                function: string_attr(&summary, "name").unwrap_or_default(),
+                source: string_attr(&summary, "line").filter(|line| !line.is_empty()),
+            });
+        }
+    }
+
+    if error.is_instance_of::<pyo3::exceptions::PySyntaxError>(py) {
+        let value = error.value_bound(py);
+        frames.push(TracebackFrame {
+            file: string_attr(value.as_any(), "filename").unwrap_or_default(),
+            line: value.getattr("lineno").and_then(|l| l.extract()).ok(),
+            function: "<module>".to_string(),
+            source: string_attr(value.as_any(), "text").map(|text| text.trim_end().to_string()),
+        });
+    }
+
+    frames
+}
+
+#[cfg(feature = "python")]
+fn string_attr(obj: &Bound<'_, PyAny>, name: &str) -> Option<String> {
+    obj.getattr(name).ok()?.extract::<String>().ok()
+}
+
+/**
+ * python://output イベントのペイロード
+ */
+#[cfg(feature = "python")]
+#[derive(Debug, Clone, Serialize)]
+pub struct PythonOutputEvent {
+    pub job_id: String,
+    pub stream: String,
+    pub text: String,
+}
+
+/**
+ * python://complete イベントのペイロード
+ * 正常終了時はresult、タイムアウト・キャンセル等の場合はerrorが入る
+ */
+#[cfg(feature = "python")]
+#[derive(Debug, Clone, Serialize)]
+pub struct PythonCompleteEvent {
+    pub job_id: String,
+    pub result: Option<ExecutionResult>,
+    pub error: Option<PythonExecError>,
+}
+
+// =====================================================
+// Tauriコマンド
+// =====================================================
+
+/**
+ * 実行中のPythonジョブをキャンセルする（UIの「停止」ボタン用）
+ */
+#[tauri::command]
+pub async fn cancel_python_execution(
+    jobs: tauri::State<'_, PythonJobs>,
+    job_id: String,
+) -> Result<bool, String> {
+    let cancelled = jobs.cancel(&job_id);
+    if cancelled {
+        println!("🛑 Python job cancelled: {}", job_id);
+    }
+    Ok(cancelled)
+}
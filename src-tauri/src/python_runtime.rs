@@ -0,0 +1,246 @@
+/*
+ * =====================================================
+ * Vinsert Editor - Python実行環境の情報
+ * 起動時に一度だけ検出し、Tauriのmanaged stateとして保持する
+ * 組み込みPython（python-build-standalone等）かシステムのPythonかを判定する
+ * =====================================================
+ */
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use serde::Serialize;
+
+use crate::python_environment::PythonEnvironmentStatus;
+
+/**
+ * Pythonの種類
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PythonKind {
+    /// python-build-standaloneによる組み込みPython
+    #[cfg_attr(not(feature = "python"), allow(dead_code))]
+    Embedded,
+    /// システム環境のPython
+    #[cfg_attr(not(feature = "python"), allow(dead_code))]
+    System,
+    /// 検出に失敗した（またはPythonサポートなしのビルド）
+    Unknown,
+}
+
+/**
+ * インストール済みのパッケージ（importlib.metadataの結果）
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct PythonPackage {
+    pub name: String,
+    pub version: String,
+}
+
+/**
+ * Python実行環境
+ * indicatorsは組み込みPythonと判定した根拠（build_flag, executable_path など）
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct PythonRuntime {
+    pub kind: PythonKind,
+    /// (major, minor, micro)
+    pub version: Option<(u32, u32, u32)>,
+    pub executable: Option<String>,
+    pub prefix: Option<String>,
+    pub sys_path: Vec<String>,
+    pub indicators: Vec<String>,
+    pub packages: Vec<PythonPackage>,
+    /// 拡張機能用に追加したPython環境（venv・site-packages）
+    pub environment: PythonEnvironmentStatus,
+    /// 検出に失敗した場合の理由
+    pub error: Option<String>,
+}
+
+impl PythonRuntime {
+    fn unknown(error: String) -> Self {
+        PythonRuntime {
+            kind: PythonKind::Unknown,
+            version: None,
+            executable: None,
+            prefix: None,
+            sys_path: Vec::new(),
+            indicators: Vec::new(),
+            packages: Vec::new(),
+            environment: PythonEnvironmentStatus::default(),
+            error: Some(error),
+        }
+    }
+
+    /**
+     * Python環境を検出する（Pythonの初期化も兼ねる）
+     */
+    #[cfg(feature = "python")]
+    pub fn detect() -> Self {
+        match Python::with_gil(inspect) {
+            Ok(runtime) => runtime,
+            Err(e) => {
+                println!("❌ Python環境検出エラー: {}", e);
+                PythonRuntime::unknown(e.to_string())
+            }
+        }
+    }
+
+    #[cfg(not(feature = "python"))]
+    pub fn detect() -> Self {
+        PythonRuntime::unknown(crate::python_exec::PYTHON_UNAVAILABLE.to_string())
+    }
+
+    /**
+     * 起動時のログに検出結果を表示する
+     */
+    pub fn log_summary(&self) {
+        println!("=== PYTHON環境検出結果 ===");
+        match self.kind {
+            PythonKind::Embedded => println!("🟢 使用中のPython: 【組み込みPython】"),
+            PythonKind::System => println!("🔵 使用中のPython: 【ユーザー環境Python】"),
+            PythonKind::Unknown => println!("🔴 使用中のPython: 【不明・エラー】"),
+        }
+        if let Some((major, minor, micro)) = self.version {
+            println!("   バージョン: {}.{}.{}", major, minor, micro);
+        }
+        if let Some(executable) = &self.executable {
+            println!("   実行ファイル: {}", executable);
+        }
+        if !self.indicators.is_empty() {
+            println!("   判定根拠: {}", self.indicators.join(", "));
+        }
+        println!("   パッケージ: {}件", self.packages.len());
+        if let Some(venv) = &self.environment.venv {
+            println!("   拡張機能用venv: {}", venv);
+        }
+        for path in &self.environment.added_paths {
+            println!("   追加パス: {}", path);
+        }
+        for error in &self.environment.errors {
+            println!("   環境エラー: {}", error);
+        }
+        if let Some(error) = &self.error {
+            println!("   エラー: {}", error);
+        }
+        println!("========================");
+    }
+}
+
+/**
+ * sysモジュールから実行環境の情報を集める
+ */
+#[cfg(feature = "python")]
+fn inspect(py: Python<'_>) -> PyResult<PythonRuntime> {
+    let sys = py.import_bound("sys")?;
+    let executable = sys.getattr("executable")?.extract::<String>()?;
+    let prefix = sys.getattr("prefix")?.extract::<String>()?;
+    let sys_path = sys.getattr("path")?.extract::<Vec<String>>()?;
+    let (major, minor, micro, _, _) = sys
+        .getattr("version_info")?
+        .extract::<(u32, u32, u32, String, u32)>()?;
+
+    let indicators = embedded_indicators(&sys, &executable, &sys_path);
+    let kind = if indicators.is_empty() {
+        PythonKind::System
+    } else {
+        PythonKind::Embedded
+    };
+
+    Ok(PythonRuntime {
+        kind,
+        version: Some((major, minor, micro)),
+        executable: Some(executable),
+        prefix: Some(prefix),
+        sys_path,
+        indicators,
+        packages: installed_packages(py),
+        environment: PythonEnvironmentStatus::default(),
+        error: None,
+    })
+}
+
+/**
+ * 組み込みPythonと判定できる根拠を集める（空ならシステムのPython）
+ */
+#[cfg(feature = "python")]
+fn embedded_indicators(sys: &Bound<'_, PyModule>, executable: &str, sys_path: &[String]) -> Vec<String> {
+    // ビルド時の組み込みPythonフラグ（実行時の環境変数があればそちらを優先）
+    let build_flag = |name: &str, build_value: Option<&str>| {
+        std::env::var(name)
+            .ok()
+            .or_else(|| build_value.map(|value| value.to_string()))
+            .unwrap_or_default()
+    };
+    let is_embedded_build = build_flag("VINSERT_EMBEDDED_PYTHON", option_env!("VINSERT_EMBEDDED_PYTHON")) == "1";
+    let embedded_python_path = build_flag("VINSERT_PYTHON_PATH", option_env!("VINSERT_PYTHON_PATH"));
+
+    let mut indicators = Vec::new();
+
+    if is_embedded_build {
+        indicators.push("build_flag".to_string());
+    }
+
+    if executable.contains("python-standalone")
+        || (!embedded_python_path.is_empty() && executable.contains(&embedded_python_path))
+    {
+        indicators.push("executable_path".to_string());
+    }
+
+    if let Some(exe_dir) = std::env::current_exe().ok().and_then(|exe| exe.parent().map(|dir| dir.to_path_buf())) {
+        if executable.starts_with(exe_dir.to_string_lossy().as_ref()) {
+            indicators.push("app_directory".to_string());
+        }
+    }
+
+    if sys
+        .getattr("modules")
+        .and_then(|modules| modules.contains("oxidized_importer"))
+        .unwrap_or(false)
+    {
+        indicators.push("pyoxidizer".to_string());
+    }
+
+    if sys.getattr("frozen").is_ok_and(|frozen| !frozen.is_none()) {
+        indicators.push("frozen".to_string());
+    }
+
+    if sys_path.iter().any(|path| path.contains("python-standalone")) {
+        indicators.push("site_packages".to_string());
+    }
+
+    indicators
+}
+
+/**
+ * インストール済みパッケージの一覧（名前順）
+ * 取得に失敗した場合は空にする
+ */
+#[cfg(feature = "python")]
+fn installed_packages(py: Python<'_>) -> Vec<PythonPackage> {
+    let code = "sorted({(d.metadata['Name'] or '', d.version or '') for d in __import__('importlib.metadata').metadata.distributions() if d.metadata['Name']}, key=lambda p: p[0].lower())";
+    py.eval_bound(code, None, None)
+        .and_then(|packages| packages.extract::<Vec<(String, String)>>())
+        .map(|packages| {
+            packages
+                .into_iter()
+                .map(|(name, version)| PythonPackage { name, version })
+                .collect()
+        })
+        .unwrap_or_else(|e| {
+            println!("⚠️ Failed to list Python packages: {}", e);
+            Vec::new()
+        })
+}
+
+// =====================================================
+// Tauriコマンド
+// =====================================================
+
+/**
+ * Python実行環境の情報を返す（アバウトダイアログ・拡張機能管理用）
+ */
+#[tauri::command]
+pub fn python_runtime_info(runtime: tauri::State<'_, PythonRuntime>) -> PythonRuntime {
+    runtime.inner().clone()
+}
@@ -0,0 +1,401 @@
+/*
+ * =====================================================
+ * Vinsert Editor - 対話型Pythonコンソール（REPLセッション）
+ * セッションごとに名前空間（globals）を保持し、続けて入力したコードで
+ * 前の入力で定義した変数を使えるようにする
+ * 入力履歴はアプリデータディレクトリ（python_history.json）に保存する
+ * =====================================================
+ */
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3::types::PyDict;
+use serde::Serialize;
+#[cfg(feature = "python")]
+use std::collections::HashMap;
+#[cfg(feature = "python")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "python")]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "python")]
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "python")]
+use tauri::Manager;
+
+#[cfg(feature = "python")]
+use crate::python_exec::PythonJobs;
+use crate::python_exec::{self, ExecutionResult, PythonExecError};
+
+/// 保存する入力履歴の最大件数
+#[cfg(feature = "python")]
+const MAX_HISTORY: usize = 1_000;
+
+/// 補完候補の最大件数
+#[cfg(feature = "python")]
+const MAX_COMPLETIONS: usize = 500;
+
+/// 補完（属性アクセスの評価を含む）の制限時間
+#[cfg(feature = "python")]
+const COMPLETE_TIMEOUT_MS: u64 = 2_000;
+
+/// トレースバックに表示するファイル名
+#[cfg(feature = "python")]
+const CONSOLE_FILENAME: &str = "<console>";
+
+/**
+ * REPLセッション一覧（Tauriのmanaged stateとして保持）
+ */
+#[derive(Clone, Default)]
+pub struct PythonSessions {
+    #[cfg(feature = "python")]
+    sessions: Arc<Mutex<HashMap<String, Py<PyDict>>>>,
+    #[cfg(feature = "python")]
+    next_id: Arc<AtomicU64>,
+    /// 履歴ファイルの読み書きをまとめて行うためのロック
+    #[cfg(feature = "python")]
+    history_lock: Arc<Mutex<()>>,
+}
+
+/**
+ * python_session_createの戻り値
+ * historyは古い順（上矢印キーでの呼び出し用）
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct PythonSessionInfo {
+    pub session_id: String,
+    pub history: Vec<String>,
+}
+
+/**
+ * python_session_execの戻り値
+ * completeがfalseの場合は入力の続き（複数行の文）が必要で、何も実行していない
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionExecResult {
+    pub complete: bool,
+    pub result: Option<ExecutionResult>,
+}
+
+impl PythonSessions {
+    pub fn new() -> Self {
+        PythonSessions::default()
+    }
+
+    /**
+     * 空の名前空間でセッションを作成し、IDを返す
+     */
+    #[cfg(feature = "python")]
+    pub fn create(&self) -> Result<String, String> {
+        let session_id = format!("python-session-{}", self.next_id.fetch_add(1, Ordering::Relaxed) + 1);
+        let globals = Python::with_gil(new_globals)?;
+        self.sessions
+            .lock()
+            .map_err(|e| e.to_string())?
+            .insert(session_id.clone(), globals);
+        Ok(session_id)
+    }
+
+    /**
+     * セッションの名前空間（GIL取得済みの状態で参照する）
+     */
+    #[cfg(feature = "python")]
+    pub fn globals<'py>(&self, py: Python<'py>, session_id: &str) -> Result<Bound<'py, PyDict>, String> {
+        self.sessions
+            .lock()
+            .map_err(|e| e.to_string())?
+            .get(session_id)
+            .map(|globals| globals.bind(py).clone())
+            .ok_or_else(|| format!("Python session '{}' not found", session_id))
+    }
+
+    /**
+     * 指定したIDのセッションの名前空間（無ければそのIDで作成する）
+     * ドキュメントごとのセッションのように、呼び出し側でIDを決める場合に使う
+     */
+    #[cfg(feature = "python")]
+    pub fn globals_or_create<'py>(&self, py: Python<'py>, session_id: &str) -> Result<Bound<'py, PyDict>, String> {
+        let mut sessions = self.sessions.lock().map_err(|e| e.to_string())?;
+        if let Some(globals) = sessions.get(session_id) {
+            return Ok(globals.bind(py).clone());
+        }
+
+        let globals = new_globals(py)?;
+        let bound = globals.bind(py).clone();
+        sessions.insert(session_id.to_string(), globals);
+        Ok(bound)
+    }
+
+    /**
+     * 名前空間を空に戻す
+     */
+    #[cfg(feature = "python")]
+    pub fn reset(&self, session_id: &str) -> Result<(), String> {
+        let globals = Python::with_gil(new_globals)?;
+        match self.sessions.lock().map_err(|e| e.to_string())?.get_mut(session_id) {
+            Some(session) => {
+                *session = globals;
+                Ok(())
+            }
+            None => Err(format!("Python session '{}' not found", session_id)),
+        }
+    }
+
+    /**
+     * セッションを破棄する（該当するセッションが無い場合はfalse）
+     */
+    #[cfg(feature = "python")]
+    pub fn close(&self, session_id: &str) -> bool {
+        self.sessions
+            .lock()
+            .map(|mut sessions| sessions.remove(session_id).is_some())
+            .unwrap_or(false)
+    }
+
+    #[cfg(not(feature = "python"))]
+    pub fn close(&self, _session_id: &str) -> bool {
+        false
+    }
+
+    /**
+     * 入力を履歴に追加して保存する（直前と同じ入力は追加しない）
+     */
+    #[cfg(feature = "python")]
+    fn append_history(&self, app: &tauri::AppHandle, source: &str) -> Result<(), String> {
+        let entry = source.trim_end();
+        if entry.trim().is_empty() {
+            return Ok(());
+        }
+
+        let _guard = self.history_lock.lock().map_err(|e| e.to_string())?;
+        let path = history_path(app)?;
+        let mut history = load_history(&path);
+        if history.last().map(String::as_str) == Some(entry) {
+            return Ok(());
+        }
+        history.push(entry.to_string());
+        if history.len() > MAX_HISTORY {
+            history.drain(..history.len() - MAX_HISTORY);
+        }
+        save_history(&path, &history)
+    }
+}
+
+/**
+ * __name__を設定した新しい名前空間
+ */
+#[cfg(feature = "python")]
+fn new_globals(py: Python<'_>) -> Result<Py<PyDict>, String> {
+    let globals = PyDict::new_bound(py);
+    globals
+        .set_item("__name__", "__console__")
+        .map_err(|e| format!("Failed to create Python session: {}", e))?;
+    Ok(globals.unbind())
+}
+
+#[cfg(feature = "python")]
+fn history_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    Ok(app_data.join("vinsert").join("python_history.json"))
+}
+
+#[cfg(feature = "python")]
+fn load_history(path: &Path) -> Vec<String> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(feature = "python")]
+fn save_history(path: &Path, history: &[String]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create '{}': {}", parent.display(), e))?;
+    }
+    let content = serde_json::to_string_pretty(history)
+        .map_err(|e| format!("Failed to serialize Python history: {}", e))?;
+    std::fs::write(path, content).map_err(|e| format!("Failed to write '{}': {}", path.display(), e))
+}
+
+/**
+ * 入力が文として完結しているか（codeop.compile_commandと同じ判定）
+ * 構文エラーの場合も完結として扱い、実行時にエラーを返す
+ */
+#[cfg(feature = "python")]
+fn is_complete(py: Python<'_>, source: &str) -> Result<bool, String> {
+    let codeop = py
+        .import_bound("codeop")
+        .map_err(|e| format!("Failed to import codeop: {}", e))?;
+    match codeop.call_method1("compile_command", (source, CONSOLE_FILENAME, "single")) {
+        Ok(compiled) => Ok(!compiled.is_none()),
+        Err(_) => Ok(true),
+    }
+}
+
+/**
+ * rlcompleterで補完候補を集める
+ */
+#[cfg(feature = "python")]
+fn completions(py: Python<'_>, globals: &Bound<'_, PyDict>, text: &str) -> Result<Vec<String>, String> {
+    let completer = py
+        .import_bound("rlcompleter")
+        .and_then(|rlcompleter| rlcompleter.call_method1("Completer", (globals,)))
+        .map_err(|e| format!("Failed to create completer: {}", e))?;
+
+    let mut matches: Vec<String> = Vec::new();
+    for state in 0..MAX_COMPLETIONS {
+        let candidate = completer
+            .call_method1("complete", (text, state))
+            .map_err(|e| format!("Completion failed: {}", e))?;
+        if candidate.is_none() {
+            break;
+        }
+        let candidate: String = candidate
+            .extract()
+            .map_err(|e| format!("Completion failed: {}", e))?;
+        if !matches.contains(&candidate) {
+            matches.push(candidate);
+        }
+    }
+    Ok(matches)
+}
+
+// =====================================================
+// Tauriコマンド
+// =====================================================
+
+/**
+ * REPLセッションを作成し、保存済みの入力履歴と一緒に返す
+ */
+#[cfg(feature = "python")]
+#[tauri::command]
+pub fn python_session_create(
+    app: tauri::AppHandle,
+    sessions: tauri::State<'_, PythonSessions>,
+) -> Result<PythonSessionInfo, String> {
+    let session_id = sessions.create()?;
+    let history = load_history(&history_path(&app)?);
+    println!("🐍 Python session created: {}", session_id);
+    Ok(PythonSessionInfo { session_id, history })
+}
+
+/**
+ * セッションの名前空間で入力を実行する
+ * 複数行の文の途中であればcomplete: falseを返し、続きの入力を待つ
+ * job_idを指定するとcancel_python_executionで停止できる
+ */
+#[cfg(feature = "python")]
+#[tauri::command]
+pub async fn python_session_exec(
+    app: tauri::AppHandle,
+    sessions: tauri::State<'_, PythonSessions>,
+    jobs: tauri::State<'_, PythonJobs>,
+    session_id: String,
+    source: String,
+    timeout_ms: Option<u64>,
+    job_id: Option<String>,
+) -> Result<SessionExecResult, PythonExecError> {
+    let sessions = sessions.inner().clone();
+    let worker_sessions = sessions.clone();
+    let worker_source = source.clone();
+    let outcome = jobs.run(job_id, timeout_ms, move |py| {
+        let globals = worker_sessions.globals(py, &session_id)?;
+        if !is_complete(py, &worker_source)? {
+            return Ok(SessionExecResult {
+                complete: false,
+                result: None,
+            });
+        }
+        // 複合文は最後に空行が必要なため改行を補う
+        let code = format!("{}\n", worker_source);
+        let result = python_exec::run_interactive(py, &code, CONSOLE_FILENAME, &globals)?;
+        Ok(SessionExecResult {
+            complete: true,
+            result: Some(result),
+        })
+    })
+    .await;
+
+    // タイムアウトやキャンセルで中断した入力も履歴には残す
+    if !matches!(&outcome, Ok(SessionExecResult { complete: false, .. })) {
+        if let Err(e) = sessions.append_history(&app, &source) {
+            println!("⚠️ Failed to save Python history: {}", e);
+        }
+    }
+    outcome
+}
+
+/**
+ * セッションの名前空間を使って補完候補を返す
+ * textはカーソル直前の識別子（属性アクセスを含む、例: "os.pa"）
+ */
+#[cfg(feature = "python")]
+#[tauri::command]
+pub async fn python_session_complete(
+    sessions: tauri::State<'_, PythonSessions>,
+    jobs: tauri::State<'_, PythonJobs>,
+    session_id: String,
+    text: String,
+) -> Result<Vec<String>, PythonExecError> {
+    let sessions = sessions.inner().clone();
+    jobs.run(None, Some(COMPLETE_TIMEOUT_MS), move |py| {
+        let globals = sessions.globals(py, &session_id)?;
+        completions(py, &globals, &text)
+    })
+    .await
+}
+
+/**
+ * セッションの名前空間を空に戻す（履歴は残す）
+ */
+#[cfg(feature = "python")]
+#[tauri::command]
+pub fn python_session_reset(sessions: tauri::State<'_, PythonSessions>, session_id: String) -> Result<(), String> {
+    sessions.reset(&session_id)?;
+    println!("🐍 Python session reset: {}", session_id);
+    Ok(())
+}
+
+/**
+ * セッションを破棄する（コンソールを閉じたとき）
+ */
+#[tauri::command]
+pub fn python_session_close(sessions: tauri::State<'_, PythonSessions>, session_id: String) -> bool {
+    let closed = sessions.close(&session_id);
+    if closed {
+        println!("🐍 Python session closed: {}", session_id);
+    }
+    closed
+}
+
+// =====================================================
+// Pythonサポートなしのビルド（pythonフィーチャー無効）
+// =====================================================
+
+#[cfg(not(feature = "python"))]
+#[tauri::command]
+pub fn python_session_create() -> Result<PythonSessionInfo, String> {
+    Err(python_exec::PYTHON_UNAVAILABLE.to_string())
+}
+
+#[cfg(not(feature = "python"))]
+#[tauri::command]
+pub async fn python_session_exec() -> Result<SessionExecResult, PythonExecError> {
+    Err(python_exec::PYTHON_UNAVAILABLE.to_string().into())
+}
+
+#[cfg(not(feature = "python"))]
+#[tauri::command]
+pub async fn python_session_complete() -> Result<Vec<String>, PythonExecError> {
+    Err(python_exec::PYTHON_UNAVAILABLE.to_string().into())
+}
+
+#[cfg(not(feature = "python"))]
+#[tauri::command]
+pub fn python_session_reset() -> Result<(), String> {
+    Err(python_exec::PYTHON_UNAVAILABLE.to_string())
+}
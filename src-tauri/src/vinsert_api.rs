@@ -0,0 +1,512 @@
+/*
+ * =====================================================
+ * Vinsert Editor - 拡張機能向けエディタAPI（vinsertモジュール）
+ * 拡張機能から `import vinsert` で利用するネイティブモジュール
+ * 操作はTauriイベント（extension://action）としてWebViewへ転送する
+ * =====================================================
+ */
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3::types::PyString;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+#[cfg(feature = "python")]
+use std::cell::{Cell, RefCell};
+#[cfg(feature = "python")]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "python")]
+use std::sync::Arc;
+use std::sync::{Mutex, OnceLock};
+use tauri::Emitter;
+
+#[cfg(feature = "python")]
+use crate::extension_config;
+#[cfg(feature = "python")]
+use crate::extension_permissions::Permission;
+#[cfg(feature = "python")]
+use crate::extension_permissions::PermissionSet;
+
+/// 拡張機能の操作を通知するイベント名
+pub const EXTENSION_ACTION_EVENT: &str = "extension://action";
+
+static APP_HANDLE: OnceLock<tauri::AppHandle> = OnceLock::new();
+
+// ヘッドレスのテスト実行（extension_test）でWebViewの代わりに記録したイベント
+static CAPTURED_EVENTS: Mutex<Option<Vec<(String, serde_json::Value)>>> = Mutex::new(None);
+
+// 拡張機能のコードをこのプロセス内で一度でも実行したかどうか
+// （拡張機能が起動したスレッドはアンロード後も残るため、一度設定したら戻さない）
+#[cfg(feature = "python")]
+static EXTENSION_CODE_RAN: AtomicBool = AtomicBool::new(false);
+
+#[cfg(feature = "python")]
+thread_local! {
+    // 現在実行中の拡張機能とエディタの状態（Pythonを呼び出すスレッドごと）
+    static CURRENT_CONTEXT: RefCell<Option<ExtensionContext>> = const { RefCell::new(None) };
+    // エディタ本体がPythonを実行するスレッド（メインスレッドとPythonJobsのワーカー）かどうか
+    static HOST_THREAD: Cell<bool> = const { Cell::new(false) };
+}
+
+/**
+ * Pythonの処理をどの権限で検査するか
+ */
+#[cfg(feature = "python")]
+pub enum Caller {
+    /// 拡張機能のコンテキスト内（その拡張機能の権限で検査する）
+    Extension(Arc<PermissionSet>),
+    /// エディタ本体のスレッド（execute_python等）。制限しない
+    Host,
+    /// どちらでもないスレッド（拡張機能がthreadingやタイマーで起動したスレッドなど）。権限なしとして検査する
+    Untrusted,
+}
+
+/**
+ * イベント送信時点のエディタの状態
+ * selection_start/selection_endは文字（Unicodeのコードポイント）単位の位置で、Pythonの文字列の添字と同じ
+ * WebViewからはtextarea.selectionStart/selectionEnd（UTF-16単位）で受け取り、読み込み時に変換する
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(from = "WebViewSnapshot")]
+pub struct EditorSnapshot {
+    pub text: String,
+    pub selection_start: usize,
+    pub selection_end: usize,
+}
+
+/**
+ * WebViewから受け取るエディタの状態（位置はUTF-16単位）
+ */
+#[derive(Deserialize)]
+struct WebViewSnapshot {
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    selection_start: usize,
+    #[serde(default)]
+    selection_end: usize,
+}
+
+impl From<WebViewSnapshot> for EditorSnapshot {
+    fn from(snapshot: WebViewSnapshot) -> Self {
+        EditorSnapshot {
+            selection_start: char_offset(&snapshot.text, snapshot.selection_start),
+            selection_end: char_offset(&snapshot.text, snapshot.selection_end),
+            text: snapshot.text,
+        }
+    }
+}
+
+/**
+ * UTF-16単位の位置を文字単位の位置に変換する
+ * サロゲートペアの途中を指す場合はその文字の後ろ、範囲外の場合は末尾とする
+ */
+fn char_offset(text: &str, utf16_offset: usize) -> usize {
+    let mut units = 0;
+    for (index, c) in text.chars().enumerate() {
+        if units >= utf16_offset {
+            return index;
+        }
+        units += c.len_utf16();
+    }
+    text.chars().count()
+}
+
+#[cfg(feature = "python")]
+#[derive(Debug, Clone)]
+struct ExtensionContext {
+    permissions: Arc<PermissionSet>,
+    editor: EditorSnapshot,
+}
+
+/**
+ * アプリケーション起動時にAppHandleを登録する
+ */
+pub fn set_app_handle(app: tauri::AppHandle) {
+    let _ = APP_HANDLE.set(app);
+}
+
+/**
+ * WebViewへ送るイベントを記録するようにする（ウィンドウを作らないテスト実行用）
+ */
+pub fn capture_events() {
+    if let Ok(mut captured) = CAPTURED_EVENTS.lock() {
+        captured.get_or_insert_with(Vec::new);
+    }
+}
+
+/**
+ * 記録したイベント（イベント名とペイロード）を取り出す
+ */
+pub fn take_captured_events() -> Vec<(String, serde_json::Value)> {
+    CAPTURED_EVENTS
+        .lock()
+        .ok()
+        .and_then(|mut captured| captured.as_mut().map(std::mem::take))
+        .unwrap_or_default()
+}
+
+/**
+ * 現在のスレッドをエディタ本体のスレッドとして登録する
+ * 拡張機能のコンテキスト外で制限なくPythonを実行できるのは、登録したスレッドだけ
+ */
+#[cfg(feature = "python")]
+pub fn mark_host_thread() {
+    HOST_THREAD.with(|host| host.set(true));
+}
+
+/**
+ * 拡張機能の実行コンテキストを設定してクロージャを実行する
+ * permissionsはvinsert APIと監査フックの両方で参照される
+ */
+#[cfg(feature = "python")]
+pub fn with_context<T>(
+    permissions: Arc<PermissionSet>,
+    editor: EditorSnapshot,
+    f: impl FnOnce() -> T,
+) -> T {
+    EXTENSION_CODE_RAN.store(true, Ordering::SeqCst);
+    let previous = CURRENT_CONTEXT.with(|context| {
+        context.replace(Some(ExtensionContext { permissions, editor }))
+    });
+    let result = f();
+    CURRENT_CONTEXT.with(|context| {
+        context.replace(previous);
+    });
+    result
+}
+
+#[cfg(feature = "python")]
+fn current_editor() -> EditorSnapshot {
+    CURRENT_CONTEXT.with(|context| {
+        context
+            .borrow()
+            .as_ref()
+            .map(|context| context.editor.clone())
+            .unwrap_or_default()
+    })
+}
+
+#[cfg(feature = "python")]
+fn current_extension_id() -> Option<String> {
+    current_permissions().map(|permissions| permissions.extension_id().to_string())
+}
+
+/**
+ * 実行中の拡張機能の権限（拡張機能の外ではNone）
+ */
+#[cfg(feature = "python")]
+pub fn current_permissions() -> Option<Arc<PermissionSet>> {
+    CURRENT_CONTEXT.with(|context| {
+        context
+            .borrow()
+            .as_ref()
+            .map(|context| context.permissions.clone())
+    })
+}
+
+/**
+ * 現在のスレッドで実行中のPythonコードの呼び出し元
+ * 拡張機能のコードを実行する前は、どのスレッドもエディタ本体として扱う
+ */
+#[cfg(feature = "python")]
+pub fn current_caller() -> Caller {
+    if let Some(permissions) = current_permissions() {
+        return Caller::Extension(permissions);
+    }
+    if HOST_THREAD.with(|host| host.get()) || !EXTENSION_CODE_RAN.load(Ordering::SeqCst) {
+        Caller::Host
+    } else {
+        Caller::Untrusted
+    }
+}
+
+/**
+ * 呼び出し元の権限（エディタ本体の場合はNone）
+ */
+#[cfg(feature = "python")]
+fn caller_permissions() -> Option<Arc<PermissionSet>> {
+    match current_caller() {
+        Caller::Extension(permissions) => Some(permissions),
+        Caller::Host => None,
+        Caller::Untrusted => Some(PermissionSet::untrusted()),
+    }
+}
+
+/**
+ * 実行中の拡張機能が権限を持つか確認する
+ * エディタ本体のスレッド（execute_python等）からの呼び出しは制限しない
+ */
+#[cfg(feature = "python")]
+fn require(permission: Permission) -> PyResult<()> {
+    match caller_permissions() {
+        Some(permissions) => permissions.require(&permission),
+        None => Ok(()),
+    }
+}
+
+/**
+ * 初期化済みのAppHandle（拡張機能ホストからメニューを更新する場合など）
+ */
+pub fn try_app_handle() -> Option<&'static tauri::AppHandle> {
+    APP_HANDLE.get()
+}
+
+/**
+ * 拡張機能の操作をWebViewへ送信する
+ * payloadはon_eventの戻り値と同じ形式（actionキーを含むJSON）
+ */
+#[cfg(feature = "python")]
+fn emit_action(mut payload: serde_json::Value) -> PyResult<()> {
+    if let (Some(object), Some(extension_id)) = (payload.as_object_mut(), current_extension_id()) {
+        object.insert("extension_id".to_string(), json!(extension_id));
+    }
+
+    emit_event(EXTENSION_ACTION_EVENT, payload).map_err(pyo3::exceptions::PyRuntimeError::new_err)
+}
+
+/**
+ * on_eventの戻り値をWebViewへ転送する（拡張機能の実行コンテキスト外から使う）
+ */
+pub fn forward_action(extension_id: &str, mut payload: serde_json::Value) -> Result<(), String> {
+    if let Some(object) = payload.as_object_mut() {
+        object.insert("extension_id".to_string(), json!(extension_id));
+    }
+
+    emit_event(EXTENSION_ACTION_EVENT, payload)
+}
+
+/**
+ * WebViewへイベントを送る（拡張機能ホストからの通知用）
+ * capture_events()の後はWebViewへ送らずに記録する
+ */
+pub fn emit_event<S: Serialize + Clone>(event: &str, payload: S) -> Result<(), String> {
+    if let Ok(mut captured) = CAPTURED_EVENTS.lock() {
+        if let Some(events) = captured.as_mut() {
+            let payload = serde_json::to_value(payload)
+                .map_err(|e| format!("Failed to serialize {}: {}", event, e))?;
+            events.push((event.to_string(), payload));
+            return Ok(());
+        }
+    }
+
+    let app = APP_HANDLE
+        .get()
+        .ok_or_else(|| "vinsert editor API is not available".to_string())?;
+
+    app.emit(event, payload)
+        .map_err(|e| format!("Failed to emit {}: {}", event, e))
+}
+
+/**
+ * クリップボードのテキストを読み取る（権限の確認は呼び出し側で行う）
+ */
+pub fn read_clipboard() -> Result<String, String> {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+
+    let app = APP_HANDLE
+        .get()
+        .ok_or_else(|| "vinsert editor API is not available".to_string())?;
+    app.clipboard()
+        .read_text()
+        .map_err(|e| format!("Failed to read clipboard: {}", e))
+}
+
+/**
+ * クリップボードにテキストを書き込む（権限の確認は呼び出し側で行う）
+ */
+pub fn write_clipboard(text: String) -> Result<(), String> {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+
+    let app = APP_HANDLE
+        .get()
+        .ok_or_else(|| "vinsert editor API is not available".to_string())?;
+    app.clipboard()
+        .write_text(text)
+        .map_err(|e| format!("Failed to write clipboard: {}", e))
+}
+
+/**
+ * PythonオブジェクトをJSON値に変換する
+ */
+#[cfg(feature = "python")]
+fn to_json_value(obj: &Bound<'_, PyAny>) -> PyResult<serde_json::Value> {
+    let dumped = obj
+        .py()
+        .import_bound("json")?
+        .call_method1("dumps", (obj,))?
+        .extract::<String>()?;
+    serde_json::from_str(&dumped)
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("Invalid value: {}", e)))
+}
+
+/**
+ * JSON値をPythonオブジェクトに変換する
+ */
+#[cfg(feature = "python")]
+fn from_json_value(py: Python<'_>, value: &serde_json::Value) -> PyResult<PyObject> {
+    let dumped = serde_json::to_string(value)
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("Invalid value: {}", e)))?;
+    Ok(py.import_bound("json")?.call_method1("loads", (dumped,))?.unbind())
+}
+
+// =====================================================
+// vinsertモジュールの関数
+// =====================================================
+
+/**
+ * エディタの選択範囲
+ */
+#[cfg(feature = "python")]
+#[pyclass(module = "vinsert", frozen, get_all)]
+pub struct Selection {
+    start: usize,
+    end: usize,
+    text: String,
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl Selection {
+    fn __repr__(&self) -> String {
+        format!("Selection(start={}, end={}, text={:?})", self.start, self.end, self.text)
+    }
+}
+
+/**
+ * #[pyfunction]で公開する関数
+ * PyO3 0.22は関数とは別にラッパー関数を生成し、そのPyResultの変換がclippy::useless_conversionに該当する
+ * 関数の属性はラッパーに引き継がれないため、このモジュールに限って抑制する
+ */
+#[cfg(feature = "python")]
+mod functions {
+    #![allow(clippy::useless_conversion)]
+
+    use super::*;
+
+    /// エディタの全テキストを返す（read_document権限が必要）
+    #[pyfunction]
+    pub(super) fn get_text() -> PyResult<String> {
+        require(Permission::ReadDocument)?;
+        Ok(current_editor().text)
+    }
+
+    /// 現在の選択範囲を返す（位置は文字単位、read_document権限が必要）
+    #[pyfunction]
+    pub(super) fn get_selection() -> PyResult<Selection> {
+        require(Permission::ReadDocument)?;
+        let editor = current_editor();
+        let start = editor.selection_start.min(editor.selection_end);
+        let end = editor.selection_start.max(editor.selection_end);
+        let text = editor.text.chars().skip(start).take(end - start).collect();
+        Ok(Selection { start, end, text })
+    }
+
+    /// カーソル位置にテキストを挿入し、末尾からmove_cursor_back文字戻る（write_document権限が必要）
+    #[pyfunction]
+    #[pyo3(signature = (text, move_cursor_back = 0))]
+    pub(super) fn insert(text: String, move_cursor_back: usize) -> PyResult<()> {
+        require(Permission::WriteDocument)?;
+        emit_action(json!({
+            "action": "insert_text",
+            "text": text,
+            "move_cursor_back": move_cursor_back,
+        }))
+    }
+
+    /// 補完候補を表示する（文字列または {"tag", "display"} の辞書のリスト、positionは文字単位）
+    #[pyfunction]
+    #[pyo3(signature = (suggestions, position = None))]
+    pub(super) fn show_suggestions(suggestions: Vec<Bound<'_, PyAny>>, position: Option<usize>) -> PyResult<()> {
+        let mut items = Vec::with_capacity(suggestions.len());
+        for suggestion in &suggestions {
+            if let Ok(text) = suggestion.downcast::<PyString>() {
+                let text = text.to_str()?;
+                items.push(json!({ "tag": text, "display": text }));
+            } else {
+                items.push(to_json_value(suggestion)?);
+            }
+        }
+
+        let position = position.unwrap_or_else(|| current_editor().selection_start);
+        emit_action(json!({
+            "action": "show_suggestions",
+            "suggestions": items,
+            "position": position,
+        }))
+    }
+
+    /// ステータスバーにメッセージを表示する
+    #[pyfunction]
+    pub(super) fn set_status(message: String) -> PyResult<()> {
+        emit_action(json!({
+            "action": "set_status",
+            "message": message,
+        }))
+    }
+
+    /// 指定したパスのファイルをエディタで開く（そのパスへのfilesystem権限が必要）
+    #[pyfunction]
+    pub(super) fn open_file(path: String) -> PyResult<()> {
+        if let Some(permissions) = caller_permissions() {
+            permissions.require_path(std::path::Path::new(&path), false)?;
+        }
+        emit_action(json!({
+            "action": "open_file",
+            "path": path,
+        }))
+    }
+
+    /// クリップボードのテキストを返す（clipboard権限が必要）
+    #[pyfunction]
+    pub(super) fn get_clipboard() -> PyResult<String> {
+        require(Permission::Clipboard)?;
+        read_clipboard().map_err(pyo3::exceptions::PyRuntimeError::new_err)
+    }
+
+    /// クリップボードにテキストを書き込む（clipboard権限が必要）
+    #[pyfunction]
+    pub(super) fn set_clipboard(text: String) -> PyResult<()> {
+        require(Permission::Clipboard)?;
+        write_clipboard(text).map_err(pyo3::exceptions::PyRuntimeError::new_err)
+    }
+
+    /// 拡張機能の設定値を返す（keyを省略するとすべての設定の辞書）
+    #[pyfunction]
+    #[pyo3(signature = (key = None, default = None))]
+    pub(super) fn get_config(py: Python<'_>, key: Option<String>, default: Option<PyObject>) -> PyResult<PyObject> {
+        let extension_id = current_extension_id().ok_or_else(|| {
+            pyo3::exceptions::PyRuntimeError::new_err("get_config can only be called from an extension")
+        })?;
+        let values = extension_config::current_values(&extension_id).unwrap_or_default();
+
+        match key {
+            None => from_json_value(py, &serde_json::Value::Object(values)),
+            Some(key) => match values.get(&key) {
+                Some(value) => from_json_value(py, value),
+                None => Ok(default.unwrap_or_else(|| py.None())),
+            },
+        }
+    }
+}
+
+/**
+ * vinsertモジュール本体
+ * main()でPython初期化前にappend_to_inittab!で登録する
+ */
+#[cfg(feature = "python")]
+#[pymodule]
+pub fn vinsert(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Selection>()?;
+    m.add_function(wrap_pyfunction!(functions::get_text, m)?)?;
+    m.add_function(wrap_pyfunction!(functions::get_selection, m)?)?;
+    m.add_function(wrap_pyfunction!(functions::insert, m)?)?;
+    m.add_function(wrap_pyfunction!(functions::show_suggestions, m)?)?;
+    m.add_function(wrap_pyfunction!(functions::set_status, m)?)?;
+    m.add_function(wrap_pyfunction!(functions::open_file, m)?)?;
+    m.add_function(wrap_pyfunction!(functions::get_clipboard, m)?)?;
+    m.add_function(wrap_pyfunction!(functions::set_clipboard, m)?)?;
+    m.add_function(wrap_pyfunction!(functions::get_config, m)?)?;
+    Ok(())
+}
@@ -16,6 +16,7 @@ import {
 import { showExitDialog } from './dialog-utils.js';
 import { updateWindowTitle } from './ui-updater.js';
 import { t } from './locales.js';
+import { writeFileWithExtensions } from './file-operations.js';
 
 // グローバルフラグを削除し、ローカル変数で管理
 let isExitInProgress = false;
@@ -103,13 +104,10 @@ export async function exitApp() {
  */
 async function saveFileBeforeExit() {
     if (currentFilePath) {
-        if (window.__TAURI__ && window.__TAURI__.fs) {
+        if (tauriInvoke) {
+            await writeFileWithExtensions(currentFilePath);
+        } else if (window.__TAURI__ && window.__TAURI__.fs) {
             await window.__TAURI__.fs.writeTextFile(currentFilePath, editor.value);
-        } else if (tauriInvoke) {
-            await tauriInvoke('write_file', { 
-                path: currentFilePath, 
-                content: editor.value 
-            });
         } else {
             throw new Error(t('messages.tauriOnly'));
         }
@@ -146,13 +144,10 @@ async function saveAsFileForExit() {
             });
             
             if (filePath) {
-                if (window.__TAURI__ && window.__TAURI__.fs) {
-                    await window.__TAURI__.fs.writeTextFile(filePath, editor.value);
+                if (tauriInvoke) {
+                    await writeFileWithExtensions(filePath);
                 } else {
-                    await tauriInvoke('write_file', { 
-                        path: filePath, 
-                        content: editor.value 
-                    });
+                    await window.__TAURI__.fs.writeTextFile(filePath, editor.value);
                 }
                 
                 setCurrentFilePath(filePath);
@@ -26,28 +26,33 @@ async function checkPythonEnvironmentOnStartup() {
     
     try {
         if (window.__TAURI__ && window.__TAURI__.core) {
-            const pythonInfo = await window.__TAURI__.core.invoke('get_python_info');
-            
-            // 組み込みPython判定
-            const isEmbedded = pythonInfo.includes('EMBEDDED') || pythonInfo.includes('組み込みPython');
+            const runtime = await window.__TAURI__.core.invoke('python_runtime_info');
+            const version = runtime.version ? runtime.version.join('.') : '?';
             
             console.log('\n' + '='.repeat(80));
-            if (isEmbedded) {
-                console.log('🟢 【組み込みPython環境】でアプリが動作中');
+            if (runtime.kind === 'embedded') {
+                console.log(`🟢 【組み込みPython ${version}】でアプリが動作中`);
                 console.log('   ✓ アプリケーション内蔵のPython環境を使用');
                 console.log('   ✓ ユーザーのPython環境に依存しない独立動作');
                 console.log('   ✓ 拡張機能は組み込み環境で実行');
-            } else {
-                console.log('🔵 【ユーザー環境Python】でアプリが動作中');
+            } else if (runtime.kind === 'system') {
+                console.log(`🔵 【ユーザー環境Python ${version}】でアプリが動作中`);
                 console.log('   ✓ システムまたはユーザーインストールのPython環境を使用');
                 console.log('   ✓ 拡張機能はユーザー環境のライブラリを利用可能');
                 console.log('   ✓ ユーザーのPython環境に依存');
+            } else {
+                console.log('🔴 Python環境を利用できません');
+                console.log(`   ✗ ${runtime.error || '不明なエラー'}`);
+            }
+            if (runtime.environment.venv || runtime.environment.added_paths.length > 0) {
+                console.log(`   ✓ 拡張機能用の追加パス: ${runtime.environment.added_paths.join(', ') || 'なし'}`);
             }
+            runtime.environment.errors.forEach(error => console.warn(`   ✗ ${error}`));
             console.log('='.repeat(80) + '\n');
             
             // 詳細情報も表示（折りたたみ可能な形式）
             console.groupCollapsed('📋 Python環境詳細情報 (クリックして展開)');
-            console.log(pythonInfo);
+            console.log(runtime);
             console.groupEnd();
             
         } else {
@@ -294,6 +294,7 @@ export async function showAboutDialog() {
                     <div class="about-app-name">${t('about.appName')}</div>
                     <div class="about-description">${t('about.description')}</div>
                     <div class="about-version">${t('about.version')}</div>
+                    <div class="about-python" id="about-python-runtime"></div>
                     <div class="about-author">${t('about.author')}</div>
                     <div class="about-support-url">
                         <span>Support URL : </span>
@@ -311,12 +312,37 @@ export async function showAboutDialog() {
         document.body.appendChild(dialogOverlay);
         
         setupAboutDialogEvents(dialogOverlay, resolve);
+        showPythonRuntime();
         
         // ダイアログをドラッグ可能にする
         makeDraggable(dialog);
     });
 }
 
+/**
+ * アバウトダイアログにPython実行環境を表示
+ */
+async function showPythonRuntime() {
+    const element = document.getElementById('about-python-runtime');
+    if (!element || !window.__TAURI__?.core) {
+        return;
+    }
+    
+    try {
+        const runtime = await window.__TAURI__.core.invoke('python_runtime_info');
+        const version = runtime.version ? runtime.version.join('.') : '';
+        if (runtime.kind === 'embedded') {
+            element.textContent = t('about.pythonEmbedded', { version });
+        } else if (runtime.kind === 'system') {
+            element.textContent = t('about.pythonSystem', { version });
+        } else {
+            element.textContent = t('about.pythonUnavailable');
+        }
+    } catch (error) {
+        console.warn('⚠️ Could not get Python runtime info:', error);
+    }
+}
+
 /**
  * アバウトダイアログのイベント設定
  */
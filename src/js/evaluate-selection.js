@@ -0,0 +1,199 @@
+/*
+ * =====================================================
+ * Vinsert Editor - 選択範囲の評価（計算メモ用）
+ * 選択範囲（なければ現在行）をPythonで評価し、結果をエディタに反映する
+ * =====================================================
+ */
+
+import { editor, tauriInvoke } from './globals.js';
+import { closeAllMenus } from './menu-controller.js';
+import { makeDraggable } from './dialog-utils.js';
+import { saveToUndoStack } from './undo-redo.js';
+import { t } from './locales.js';
+
+// 評価結果の挿入方法の保存先
+const EVALUATE_MODE_KEY = 'vinsert-evaluate-mode';
+
+// 選択できる挿入方法（Rust側のEvaluateModeと同じ値）
+const EVALUATE_MODES = ['replace', 'append', 'insert_below'];
+
+/**
+ * 保存されている挿入方法を取得
+ */
+function getEvaluateMode() {
+    try {
+        const saved = localStorage.getItem(EVALUATE_MODE_KEY);
+        if (EVALUATE_MODES.includes(saved)) {
+            return saved;
+        }
+    } catch (error) {
+        console.warn('⚠️ Could not load evaluate mode:', error);
+    }
+    return 'append';
+}
+
+/**
+ * 挿入方法を保存
+ */
+function setEvaluateMode(mode) {
+    try {
+        localStorage.setItem(EVALUATE_MODE_KEY, mode);
+        console.log('💾 Evaluate mode saved:', mode);
+    } catch (error) {
+        console.warn('⚠️ Could not save evaluate mode:', error);
+    }
+}
+
+/**
+ * 評価する範囲（選択がなければカーソルのある行全体）
+ */
+function getEvaluationRange() {
+    const value = editor.value;
+    let start = editor.selectionStart;
+    let end = editor.selectionEnd;
+
+    if (start === end) {
+        start = value.lastIndexOf('\n', start - 1) + 1;
+        const lineEnd = value.indexOf('\n', end);
+        end = lineEnd === -1 ? value.length : lineEnd;
+    }
+
+    return { start, end };
+}
+
+/**
+ * ステータスバーにメッセージを表示
+ */
+function showEvaluateStatus(message) {
+    const extensionStatus = document.getElementById('extension-status');
+    if (!extensionStatus) {
+        if (message) alert(message);
+        return;
+    }
+
+    extensionStatus.textContent = message;
+    extensionStatus.style.display = message ? 'inline' : 'none';
+}
+
+/**
+ * 選択範囲を評価して結果を反映
+ */
+export async function evaluateSelection() {
+    closeAllMenus();
+
+    if (!tauriInvoke) {
+        console.warn('⚠️ Tauri API not available for evaluation');
+        return;
+    }
+
+    const { start, end } = getEvaluationRange();
+    const text = editor.value.substring(start, end);
+    if (!text.trim()) {
+        return;
+    }
+
+    try {
+        const result = await tauriInvoke('evaluate_selection', { text, mode: getEvaluateMode() });
+        console.log('🧮 Evaluated:', text, '→', result.value);
+
+        if (result.text !== text) {
+            saveToUndoStack(editor.value, start);
+
+            const value = editor.value;
+            editor.value = value.substring(0, start) + result.text + value.substring(end);
+
+            const newCursorPos = start + result.text.length;
+            editor.setSelectionRange(newCursorPos, newCursorPos);
+
+            // inputイベントを発火
+            editor.dispatchEvent(new Event('input', { bubbles: true }));
+        }
+
+        showEvaluateStatus(result.value !== null ? `= ${result.value}` : '');
+    } catch (error) {
+        const message = error && error.message ? error.message : String(error);
+        console.error('❌ Evaluation failed:', message);
+        showEvaluateStatus(`⚠️ ${message}`);
+    }
+
+    editor.focus();
+}
+
+/**
+ * 評価結果の挿入方法を選ぶダイアログを表示
+ */
+export function showEvaluateSettingsDialog() {
+    console.log('🧮 Opening evaluate settings dialog');
+    closeAllMenus();
+
+    const existingDialog = document.getElementById('evaluate-dialog-overlay');
+    if (existingDialog) {
+        document.body.removeChild(existingDialog);
+    }
+
+    const currentMode = getEvaluateMode();
+
+    const dialogOverlay = document.createElement('div');
+    dialogOverlay.id = 'evaluate-dialog-overlay';
+    dialogOverlay.className = 'search-dialog-overlay';
+
+    const dialog = document.createElement('div');
+    dialog.className = 'search-dialog';
+
+    dialog.innerHTML = `
+        <div class="search-dialog-header">${t('evaluate.title')}</div>
+        <div class="search-dialog-content">
+            ${EVALUATE_MODES.map(mode => `
+                <div class="search-input-group">
+                    <label>
+                        <input type="radio" name="evaluate-mode" value="${mode}" ${mode === currentMode ? 'checked' : ''}>
+                        ${t(`evaluate.modes.${mode}`)}
+                    </label>
+                </div>
+            `).join('')}
+
+            <div class="search-button-group">
+                <button id="evaluate-apply-btn" class="search-button search-button-primary">${t('fonts.buttons.apply')}</button>
+                <button id="evaluate-cancel-btn" class="search-button search-button-cancel">${t('fonts.buttons.cancel')}</button>
+            </div>
+        </div>
+    `;
+
+    dialogOverlay.appendChild(dialog);
+    document.body.appendChild(dialogOverlay);
+    // ダイアログをドラッグ可能にする
+    makeDraggable(dialog);
+
+    function closeDialog() {
+        document.removeEventListener('keydown', handleKeyDown);
+        if (dialogOverlay.parentNode) {
+            document.body.removeChild(dialogOverlay);
+        }
+        setTimeout(() => editor.focus(), 100);
+    }
+
+    // ESCキーでキャンセル
+    function handleKeyDown(e) {
+        if (e.key === 'Escape') {
+            closeDialog();
+        }
+    }
+
+    document.getElementById('evaluate-apply-btn').addEventListener('click', () => {
+        const selected = dialog.querySelector('input[name="evaluate-mode"]:checked');
+        if (selected) {
+            setEvaluateMode(selected.value);
+        }
+        closeDialog();
+    });
+
+    document.getElementById('evaluate-cancel-btn').addEventListener('click', closeDialog);
+    document.addEventListener('keydown', handleKeyDown);
+
+    // オーバーレイクリックでキャンセル
+    dialogOverlay.addEventListener('click', (e) => {
+        if (e.target === dialogOverlay) {
+            closeDialog();
+        }
+    });
+}
@@ -8,11 +8,17 @@ import { editor, tauriInvoke } from './globals.js';
 import { closeAllMenus } from './menu-controller.js';
 import { t } from './locales.js';
 import { makeDraggable } from './dialog-utils.js';
+import { openFile } from './file-operations.js';
+
+// 選択範囲の変更をまとめて通知するまでの待ち時間
+const SELECTION_EVENT_DELAY_MS = 300;
 
 // 拡張機能の状態管理
 let extensionState = {
     extensions: [],
     enabledExtensions: [],
+    invalidExtensions: [], // 検証エラーで読み込めない拡張機能（list_extensionsの結果）
+    signatures: {}, // 拡張機能IDごとの署名の検証結果（list_extensionsの結果）
     suggestionBox: null,
     isInitialized: false,
     extensionsDirectory: null,
@@ -123,7 +129,30 @@ async function createSampleExtension() {
             version: "1.0.0",
             author: "Vinsert Team",
             main_file: "main.py",
-            enabled: false
+            enabled: false,
+            configuration: [
+                {
+                    key: "tags",
+                    type: "array",
+                    items: "string",
+                    default: [
+                        "div", "span", "p", "a", "img", "ul", "ol", "li",
+                        "h1", "h2", "h3", "h4", "h5", "h6",
+                        "header", "footer", "nav", "main", "section", "article",
+                        "table", "tr", "td", "th", "thead", "tbody",
+                        "form", "input", "button", "select", "option", "textarea",
+                        "script", "style", "link", "meta"
+                    ],
+                    title: { ja: "補完するタグ", en: "Tags to complete" },
+                    description: { ja: "「<」を入力したときに表示するタグ", en: "Tags suggested after typing '<'" }
+                },
+                {
+                    key: "auto_close",
+                    type: "boolean",
+                    default: true,
+                    title: { ja: "閉じタグを自動で追加する", en: "Insert closing tags automatically" }
+                }
+            ]
         };
         
         const settingsPath = await join(sampleExtDir, 'setting.json');
@@ -147,6 +176,14 @@ HTML_TAGS = [
 # 自己完結型タグ
 SELF_CLOSING_TAGS = ['img', 'input', 'br', 'hr', 'meta', 'link']
 
+def get_config(key, default):
+    """setting.jsonのconfigurationで宣言した設定値（古いエディタでは既定値）"""
+    try:
+        import vinsert
+        return vinsert.get_config(key, default)
+    except Exception:
+        return default
+
 def on_event(event_type, event_data):
     """エディタからのイベントを処理"""
     try:
@@ -166,7 +203,7 @@ def on_event(event_type, event_data):
                     # cursor_posは'<'の後の位置を指すため、1つ前をチェック
                     if cursor_pos > 0 and cursor_pos <= len(text):
                         if text[cursor_pos - 1] == '<':
-                            suggestions = [{"tag": tag, "display": tag} for tag in HTML_TAGS]
+                            suggestions = [{"tag": tag, "display": tag} for tag in get_config("tags", HTML_TAGS)]
                             return json.dumps({
                                 "action": "show_suggestions",
                                 "suggestions": suggestions,
@@ -174,7 +211,7 @@ def on_event(event_type, event_data):
                             })
             
             # '>'が入力された場合、閉じタグを追加（重複チェック付き）
-            elif input_data == '>' and cursor_pos > 0:
+            elif input_data == '>' and cursor_pos > 0 and get_config("auto_close", True):
                 # カーソル位置が範囲内かチェック
                 if cursor_pos <= len(text) and text[cursor_pos - 1] == '>':
                     # カーソル位置の後をチェックして重複を防ぐ
@@ -187,7 +224,7 @@ def on_event(event_type, event_data):
                     
                     if match:
                         tag_name = match.group(1).lower()
-                        if tag_name in HTML_TAGS and tag_name not in SELF_CLOSING_TAGS:
+                        if tag_name in get_config("tags", HTML_TAGS) and tag_name not in SELF_CLOSING_TAGS:
                             # 既に対応する閉じタグが存在するかチェック
                             closing_tag = f"</{tag_name}>"
                             if not text_after_cursor.startswith(closing_tag):
@@ -263,45 +300,49 @@ function loadExtensionStates() {
 }
 
 /**
- * 拡張機能をスキャン
+ * 拡張機能をスキャン（Rust側でsetting.jsonを検証）
  */
 async function scanExtensions() {
-    if (!window.__TAURI__?.fs || !extensionState.extensionsDirectory) {
+    if (!window.__TAURI__?.core || !extensionState.extensionsDirectory) {
         console.log('⚠️ Using empty extension list');
         extensionState.extensions = [];
+        extensionState.invalidExtensions = [];
         return;
     }
     
     try {
-        const { readDir, readTextFile } = window.__TAURI__.fs;
-        const { join } = window.__TAURI__.path;
-        
-        const entries = await readDir(extensionState.extensionsDirectory);
+        const entries = await tauriInvoke('list_extensions');
+        const savedStates = loadExtensionStates();
         const extensions = [];
+        const invalidExtensions = [];
+        const signatures = {};
         
         for (const entry of entries) {
-            if (entry.isDirectory || entry.children) {
-                const extDir = await join(extensionState.extensionsDirectory, entry.name);
-                const settingsPath = await join(extDir, 'setting.json');
-                
-                try {
-                    const content = await readTextFile(settingsPath);
-                    const settings = JSON.parse(content);
-                    
-                    // 保存された状態を適用
-                    const savedStates = loadExtensionStates();
-                    if (settings.id in savedStates) {
-                        settings.enabled = savedStates[settings.id];
-                    }
-                    
-                    extensions.push(settings);
-                } catch (e) {
-                    console.warn(`Failed to load extension ${entry.name}:`, e);
-                }
+            entry.diagnostics.forEach(diagnostic => {
+                const location = diagnostic.line ? ` (${diagnostic.line}:${diagnostic.column})` : '';
+                const log = diagnostic.severity === 'error' ? console.warn : console.log;
+                log(`⚠️ Extension ${entry.directory}${location}: ${diagnostic.message}`);
+            });
+            
+            if (!entry.valid) {
+                invalidExtensions.push(entry);
+                continue;
             }
+            
+            const settings = entry.manifest;
+            signatures[settings.id] = entry.signature;
+            
+            // 保存された状態を適用
+            if (settings.id in savedStates) {
+                settings.enabled = savedStates[settings.id];
+            }
+            
+            extensions.push(settings);
         }
         
         extensionState.extensions = extensions;
+        extensionState.invalidExtensions = invalidExtensions;
+        extensionState.signatures = signatures;
         extensionState.enabledExtensions = extensions
             .filter(ext => ext.enabled)
             .map(ext => ext.id);
@@ -311,9 +352,55 @@ async function scanExtensions() {
     } catch (error) {
         console.error('❌ Failed to scan extensions:', error);
         extensionState.extensions = [];
+        extensionState.invalidExtensions = [];
     }
 }
 
+/**
+ * 拡張機能を実行できるPython環境があるか
+ * （Pythonサポートなしのビルドや初期化に失敗した場合はkindがunknownになる）
+ */
+async function isPythonAvailable() {
+    if (!tauriInvoke) {
+        return false;
+    }
+    try {
+        const runtime = await tauriInvoke('python_runtime_info');
+        return runtime.kind !== 'unknown';
+    } catch (error) {
+        console.warn('⚠️ Could not check Python support:', error);
+        return false;
+    }
+}
+
+/**
+ * このビルドで使える拡張機能のランタイム（python / wasm）
+ */
+async function getExtensionRuntimes() {
+    if (!tauriInvoke) {
+        return [];
+    }
+    try {
+        return await tauriInvoke('extension_runtimes');
+    } catch (error) {
+        console.warn('⚠️ Could not check extension runtimes:', error);
+        return [];
+    }
+}
+
+/**
+ * HTMLメニューの拡張機能の項目を無効にする（ネイティブメニューはRust側で無効化済み）
+ * actionsを省略した場合は拡張機能の項目すべて
+ */
+function disableExtensionMenuOptions(actions = ['showExtensionSettingsDialog', 'installExtension', 'showPythonEnvironmentDialog']) {
+    actions.forEach(action => {
+        const option = document.querySelector(`.menu-option[data-action="${action}"]`);
+        if (option) {
+            option.classList.add('menu-option-disabled');
+        }
+    });
+}
+
 /**
  * 拡張機能システムの初期化
  */
@@ -321,6 +408,17 @@ export async function initializeExtensionSystem() {
     console.log('🧩 Initializing extension system...');
     
     try {
+        // PythonもWebAssemblyも使えない場合は拡張機能を使えない
+        const pythonAvailable = await isPythonAvailable();
+        const wasmAvailable = (await getExtensionRuntimes()).includes('wasm');
+        if (!pythonAvailable && !wasmAvailable) {
+            disableExtensionMenuOptions();
+            throw new Error('Extension runtime is not available');
+        }
+        if (!pythonAvailable) {
+            disableExtensionMenuOptions(['showPythonEnvironmentDialog']);
+        }
+        
         // 拡張機能ディレクトリを取得
         extensionState.extensionsDirectory = await getExtensionsDirectory();
         if (!extensionState.extensionsDirectory) {
@@ -336,6 +434,12 @@ export async function initializeExtensionSystem() {
         // 拡張機能をスキャン
         await scanExtensions();
         
+        // vinsertモジュールからの操作を受信
+        await setupExtensionActionListener();
+        
+        // 有効な拡張機能を読み込む
+        await loadEnabledExtensions();
+        
         // エディタイベントリスナーを設定
         setupEditorEventListeners();
         
@@ -394,22 +498,40 @@ function createExtensionDialog() {
             <label class="extension-checkbox-label">
                 <input type="checkbox" 
                        class="extension-checkbox" 
-                       data-extension-id="${ext.id}"
+                       data-extension-id="${escapeHtml(ext.id)}"
                        ${ext.enabled ? 'checked' : ''}>
                 <div class="extension-info">
-                    <div class="extension-name">${ext.name} (v${ext.version})</div>
-                    <div class="extension-summary">${ext.summary}</div>
-                    <div class="extension-author">作者: ${ext.author}</div>
+                    <div class="extension-name">${escapeHtml(ext.name)} (v${escapeHtml(ext.version)})</div>
+                    <div class="extension-summary">${escapeHtml(ext.summary)}</div>
+                    <div class="extension-author">作者: ${escapeHtml(ext.author)}</div>
+                    ${formatSignatureHTML(extensionState.signatures[ext.id])}
                 </div>
             </label>
+            <div class="extension-item-actions">
+                <button class="search-button" data-reset-permissions="${escapeHtml(ext.id)}">権限をリセット</button>
+            </div>
+        </div>
+    `).join('');
+    
+    // 検証エラーのある拡張機能は有効化できないため理由のみ表示
+    const invalidListHTML = extensionState.invalidExtensions.map(entry => `
+        <div class="extension-item extension-item-invalid">
+            <div class="extension-info">
+                <div class="extension-name">${escapeHtml(entry.manifest?.name || entry.directory)}</div>
+                ${entry.diagnostics
+                    .filter(diagnostic => diagnostic.severity === 'error')
+                    .map(diagnostic => `<div class="extension-diagnostic">${escapeHtml(diagnostic.message)}${diagnostic.line ? ` (${diagnostic.line}:${diagnostic.column})` : ''}</div>`)
+                    .join('')}
+            </div>
         </div>
     `).join('');
+    const allListHTML = extensionListHTML + invalidListHTML;
     
     dialog.innerHTML = `
         <div class="search-dialog-header">拡張機能設定</div>
         <div class="search-dialog-content">
             <div class="extension-list">
-                ${extensionListHTML.length > 0 ? extensionListHTML : '<div class="no-extensions">拡張機能が見つかりません</div>'}
+                ${allListHTML.length > 0 ? allListHTML : '<div class="no-extensions">拡張機能が見つかりません</div>'}
             </div>
             
             <div class="search-button-group">
@@ -427,6 +549,38 @@ function createExtensionDialog() {
     setupExtensionDialogEvents(dialogOverlay);
 }
 
+/**
+ * 署名の検証結果の表示
+ */
+function formatSignature(signature) {
+    switch (signature?.status) {
+        case 'signed':
+            return `🔏 署名済み（${signature.key}）`;
+        case 'untrusted':
+            return '⚠️ 信頼されていない鍵で署名されています';
+        case 'tampered':
+            return `❌ 署名が一致しません: ${signature.reason}`;
+        case 'unverifiable':
+            return `❌ 署名を確認できません: ${signature.reason}`;
+        default:
+            return '署名なし';
+    }
+}
+
+function formatSignatureHTML(signature) {
+    const className = ['tampered', 'unverifiable'].includes(signature?.status) ? 'extension-diagnostic' : 'extension-author';
+    return `<div class="${className}">${escapeHtml(formatSignature(signature))}</div>`;
+}
+
+/**
+ * HTMLに埋め込む文字列をエスケープ（属性値にも使えるよう引用符も置き換える）
+ */
+function escapeHtml(text) {
+    const div = document.createElement('div');
+    div.textContent = text ?? '';
+    return div.innerHTML.replace(/"/g, '&quot;').replace(/'/g, '&#39;');
+}
+
 /**
  * 拡張機能ダイアログのイベント設定
  */
@@ -453,8 +607,10 @@ function setupExtensionDialogEvents(dialogOverlay) {
             if (extension) {
                 extension.enabled = isEnabled;
                 
-                // setting.jsonに保存
-                if (extensionState.isInitialized && window.__TAURI__?.fs) {
+                // setting.jsonに保存（署名付きの拡張機能は書き換えると署名が一致しなくなるため保存しない）
+                const signature = extensionState.signatures[extensionId];
+                const isSigned = signature && signature.status !== 'unsigned';
+                if (extensionState.isInitialized && window.__TAURI__?.fs && !isSigned) {
                     try {
                         const { writeTextFile } = window.__TAURI__.fs;
                         const { join } = window.__TAURI__.path;
@@ -471,8 +627,12 @@ function setupExtensionDialogEvents(dialogOverlay) {
             if (isEnabled) {
                 if (!extensionState.enabledExtensions.includes(extensionId)) {
                     extensionState.enabledExtensions.push(extensionId);
+                    await loadExtension(extensionId);
                 }
             } else {
+                if (extensionState.enabledExtensions.includes(extensionId)) {
+                    await unloadExtension(extensionId);
+                }
                 extensionState.enabledExtensions = extensionState.enabledExtensions.filter(id => id !== extensionId);
             }
         }
@@ -484,6 +644,30 @@ function setupExtensionDialogEvents(dialogOverlay) {
         console.log('✅ Extension settings applied and saved');
     });
     
+    // 権限をリセット（拒否した権限も次の読み込み時に改めて確認する）
+    dialogOverlay.querySelector('.extension-list').addEventListener('click', async (e) => {
+        const button = e.target.closest('[data-reset-permissions]');
+        if (!button) return;
+        
+        const extensionId = button.dataset.resetPermissions;
+        button.disabled = true;
+        try {
+            await tauriInvoke('reset_extension_permissions', { id: extensionId });
+            console.log(`🔐 Extension permissions reset: ${extensionId}`);
+            showExtensionStatus(`🔐 ${extensionId} の権限をリセットしました`);
+            
+            // 有効な拡張機能はすぐに読み込み直し、権限の確認ダイアログを表示する
+            if (extensionState.enabledExtensions.includes(extensionId)) {
+                await loadExtension(extensionId);
+            }
+        } catch (error) {
+            console.error(`❌ Failed to reset permissions of ${extensionId}:`, error);
+            alert(`権限をリセットできませんでした。\n\nエラー: ${error}`);
+        } finally {
+            button.disabled = false;
+        }
+    });
+    
     // キャンセルボタン
     cancelBtn.addEventListener('click', () => {
         closeExtensionDialog(dialogOverlay);
@@ -556,7 +740,7 @@ function setupEditorEventListeners() {
         for (const extensionId of extensionState.enabledExtensions) {
             const result = await executeExtensionEvent(extensionId, 'text_input', {
                 text: editor.value,
-                cursor_position: editor.selectionStart,
+                cursor_position: toCharOffset(editor.value, editor.selectionStart),
                 input_type: e.inputType,
                 data: e.data
             });
@@ -569,117 +753,221 @@ function setupEditorEventListeners() {
     });
     
     // keydownイベントは削除（問題の原因だったため）
+    
+    // 選択範囲の変更（selection_changedを購読している拡張機能へRust側から配信）
+    let selectionTimer = null;
+    let lastSelection = { start: editor.selectionStart, end: editor.selectionEnd };
+    document.addEventListener('selectionchange', () => {
+        if (extensionState.enabledExtensions.length === 0 || document.activeElement !== editor) return;
+        
+        clearTimeout(selectionTimer);
+        selectionTimer = setTimeout(() => {
+            const selection = { start: editor.selectionStart, end: editor.selectionEnd };
+            if (selection.start === lastSelection.start && selection.end === lastSelection.end) return;
+            lastSelection = selection;
+            emitLifecycleEvent('selection_changed', selection);
+        }, SELECTION_EVENT_DELAY_MS);
+    });
+    
+    // 表示言語の変更
+    window.addEventListener('languageChanged', (e) => {
+        if (extensionState.enabledExtensions.length === 0) return;
+        emitLifecycleEvent('language_changed', { language: e.detail.language });
+    });
 }
 
 /**
- * 拡張機能のPythonコードを実行
+ * WebView側で発生したライフサイクルイベントをRust側の拡張機能ホストへ送る
+ */
+async function emitLifecycleEvent(eventType, payload) {
+    try {
+        await tauriInvoke('emit_extension_event', {
+            eventType,
+            payload,
+            editor: getEditorSnapshot()
+        });
+    } catch (error) {
+        console.error(`❌ Failed to emit ${eventType}:`, error);
+    }
+}
+
+/**
+ * 拡張機能にイベントを送信（Rust側の拡張機能ホストで常駐実行）
  */
 async function executeExtensionEvent(extensionId, eventType, eventData) {
     try {
-        if (!window.__TAURI__?.fs || !tauriInvoke) {
+        if (!tauriInvoke) {
             console.warn('⚠️ Tauri APIs not available for extension execution');
             return;
         }
         
-        const { readTextFile } = window.__TAURI__.fs;
-        const { join } = window.__TAURI__.path;
-        
-        // 拡張機能のmain.pyパスを構築
-        const extensionDir = await join(extensionState.extensionsDirectory, extensionId);
-        const mainPyPath = await join(extensionDir, 'main.py');
+        console.log(`🐍 Dispatching event ${eventType} to extension ${extensionId}`);
         
-        console.log(`🐍 Executing extension ${extensionId} with event ${eventType}`);
-        console.log(`📁 Extension path: ${mainPyPath}`);
+        const response = await tauriInvoke('dispatch_extension_event', {
+            id: extensionId,
+            eventType: eventType,
+            payload: eventData,
+            editor: getEditorSnapshot()
+        });
         
-        // main.pyを直接読み込む
-        let pythonScript;
-        try {
-            pythonScript = await readTextFile(mainPyPath);
-            console.log('📄 Extension script loaded, length:', pythonScript.length);
-        } catch (error) {
-            console.error(`❌ Failed to read extension file: ${mainPyPath}`, error);
+        if (!response) {
             return;
         }
         
-        // PythonスクリプトをBase64エンコード（エスケープ問題を回避）
-        const encodedScript = btoa(unescape(encodeURIComponent(pythonScript)));
+        if (response.error) {
+            console.error('❌ Extension error:', response.error);
+            if (response.traceback) {
+                console.error('Traceback:', response.traceback);
+            }
+        } else if (response.action) {
+            await handleExtensionResponse(response);
+        }
         
-        // イベントデータもBase64エンコード（改行文字の問題を回避）
-        const encodedEventData = btoa(unescape(encodeURIComponent(JSON.stringify(eventData))));
+        return response;
         
-        // Pythonコードを実行
-        const pythonCode = `
-import json
-import sys
-import re
-import traceback
-import base64
+    } catch (error) {
+        console.error(`❌ Failed to execute extension ${extensionId}:`, error);
+    }
+}
 
-# 拡張機能のコードをBase64デコード
-try:
-    extension_code = base64.b64decode("${encodedScript}").decode('utf-8')
-except Exception as e:
-    print(json.dumps({"error": "Failed to decode extension: " + str(e)}))
-    sys.exit(1)
+/**
+ * 拡張機能のメニューコマンドを実行（ネイティブメニューから呼ばれる）
+ */
+export async function runExtensionCommand(extensionId, commandId) {
+    if (!tauriInvoke) {
+        console.warn('⚠️ Tauri APIs not available for extension commands');
+        return;
+    }
+    
+    console.log(`🐍 Running command ${commandId} of extension ${extensionId}`);
+    
+    try {
+        const response = await tauriInvoke('run_extension_command', {
+            extensionId: extensionId,
+            commandId: commandId,
+            editor: getEditorSnapshot()
+        });
+        
+        if (response && response.action) {
+            await handleExtensionResponse(response);
+        }
+    } catch (error) {
+        console.error(`❌ Failed to run command ${commandId} of extension ${extensionId}:`, error);
+        showExtensionStatus(`⚠️ ${extensionId}: ${error.message || error}`);
+    }
+}
 
-# 拡張機能のコードを実行
-try:
-    exec(extension_code, globals())
-except Exception as e:
-    print(json.dumps({"error": "Failed to exec extension: " + str(e), "traceback": traceback.format_exc()}))
-    sys.exit(1)
+/**
+ * vinsertモジュール（get_text/get_selection）に渡すエディタの状態
+ * 選択範囲はUTF-16単位のまま送り、Rust側で文字単位に変換する
+ */
+function getEditorSnapshot() {
+    if (!editor) {
+        return null;
+    }
+    
+    return {
+        text: editor.value,
+        selection_start: editor.selectionStart,
+        selection_end: editor.selectionEnd
+    };
+}
 
-# イベントデータをBase64デコード
-try:
-    event_data_json = base64.b64decode("${encodedEventData}").decode('utf-8')
-except Exception as e:
-    print(json.dumps({"error": "Failed to decode event data: " + str(e)}))
-    sys.exit(1)
+/**
+ * vinsertモジュールから送られる操作イベントを受信する
+ */
+async function setupExtensionActionListener() {
+    if (!window.__TAURI__?.event) {
+        console.warn('⚠️ Tauri event API not available for extension actions');
+        return;
+    }
+    
+    const { listen } = window.__TAURI__.event;
+    await listen('extension://action', async (event) => {
+        console.log(`📥 Extension action from ${event.payload.extension_id}:`, event.payload);
+        await handleExtensionResponse(event.payload);
+    });
+    
+    // ファイル変更による自動再読み込みの結果（Rust側の監視から通知）
+    await listen('extension://reloaded', (event) => {
+        console.log(`🔄 Extension reloaded: ${event.payload.id}`);
+        showExtensionStatus(`🔄 ${event.payload.id} を再読み込みしました`);
+    });
+    await listen('extension://error', (event) => {
+        const { id, diagnostics } = event.payload;
+        diagnostics.forEach(diagnostic => {
+            const location = diagnostic.line ? ` (${diagnostic.line}:${diagnostic.column})` : '';
+            console.error(`❌ Extension ${id}${location}: ${diagnostic.message}`);
+        });
+        showExtensionStatus(`⚠️ ${id} の再読み込みに失敗しました`);
+    });
 
-# イベントタイプ
-event_type = "${eventType}"
+    // 別プロセスの拡張機能ホストの異常終了（Rust側で自動再起動を試みる）
+    await listen('extension://host-crashed', (event) => {
+        const { host, extensions, message, restarted } = event.payload;
+        console.error(`💥 Extension host ${host} crashed (${extensions.join(', ')}): ${message}`);
+        const result = restarted ? '再起動しました' : '停止しました';
+        showExtensionStatus(`💥 拡張機能ホスト（${extensions.join(', ')}）が異常終了し、${result}`);
+    });
 
-# on_event関数が定義されているか確認して実行
-if 'on_event' in globals():
-    try:
-        result = on_event(event_type, event_data_json)
-        if result and result.strip():
-            print(result)
-    except Exception as e:
-        print(json.dumps({"error": str(e), "traceback": traceback.format_exc()}))
-else:
-    print(json.dumps({"error": "on_event function not found in extension"}))
-`;
-        
-        console.log('📝 Executing Python code...');
-        const result = await tauriInvoke('execute_python', { code: pythonCode });
-        console.log('📤 Python execution result:', result);
-        
-        if (result && result !== 'Code executed successfully') {
-            // 複数行の結果を処理
-            const lines = result.split('\n').filter(line => line.trim());
-            for (const line of lines) {
-                try {
-                    const response = JSON.parse(line);
-                    if (response.error) {
-                        console.error('❌ Extension error:', response.error);
-                        if (response.traceback) {
-                            console.error('Traceback:', response.traceback);
-                        }
-                    } else if (response.action) {
-                        await handleExtensionResponse(response);
-                    }
-                } catch (e) {
-                    // JSON以外の行は無視
-                    if (line.trim()) {
-                        console.log('Non-JSON output:', line);
-                    }
-                }
-            }
+    // イベント処理が続けて目安の時間を超えた拡張機能（Rust側で計測し、超え続けた場合は無効化済み）
+    await listen('extension://slow', (event) => {
+        const { extension_id, event: eventType, elapsed_ms, budget_ms, disabled } = event.payload;
+        const elapsed = Math.round(elapsed_ms);
+        console.warn(`🐢 Extension ${extension_id} is slow in ${eventType}: ${elapsed} ms (budget ${budget_ms} ms)`);
+        if (disabled) {
+            disableSlowExtension(extension_id);
+            showExtensionStatus(`🐢 ${extension_id} の処理が遅いため無効にしました（${eventType}: ${elapsed}ms）`);
+        } else {
+            showExtensionStatus(`🐢 ${extension_id} の処理が遅くなっています（${eventType}: ${elapsed}ms）`);
         }
-        
+    });
+}
+
+/**
+ * 処理が遅いためRust側でアンロードされた拡張機能を無効の状態にする
+ */
+function disableSlowExtension(extensionId) {
+    const extension = extensionState.extensions.find(ext => ext.id === extensionId);
+    if (extension) {
+        extension.enabled = false;
+    }
+    extensionState.enabledExtensions = extensionState.enabledExtensions.filter(id => id !== extensionId);
+    saveExtensionStates();
+}
+
+/**
+ * 有効な拡張機能をRust側の拡張機能ホストに読み込む
+ */
+async function loadEnabledExtensions() {
+    for (const extensionId of extensionState.enabledExtensions) {
+        await loadExtension(extensionId);
+    }
+}
+
+/**
+ * 拡張機能をRust側の拡張機能ホストに読み込む
+ */
+async function loadExtension(extensionId) {
+    if (!tauriInvoke) return;
+    
+    try {
+        await tauriInvoke('load_extension', { id: extensionId });
     } catch (error) {
-        console.error(`❌ Failed to execute extension ${extensionId}:`, error);
+        console.error(`❌ Failed to load extension ${extensionId}:`, error);
+    }
+}
+
+/**
+ * 拡張機能をRust側の拡張機能ホストからアンロード
+ */
+async function unloadExtension(extensionId) {
+    if (!tauriInvoke) return;
+    
+    try {
+        await tauriInvoke('unload_extension', { id: extensionId });
+    } catch (error) {
+        console.error(`❌ Failed to unload extension ${extensionId}:`, error);
     }
 }
 
@@ -697,13 +985,27 @@ async function handleExtensionResponse(response) {
     switch (response.action) {
         case 'show_suggestions':
             if (response.suggestions && response.suggestions.length > 0) {
-                showSuggestions(response.suggestions, response.position);
+                const position = typeof response.position === 'number'
+                    ? toEditorOffset(editor.value, response.position)
+                    : response.position;
+                showSuggestions(response.suggestions, position);
             }
             break;
             
         case 'insert_text':
             if (response.text) {
-                insertTextAtCursor(response.text, response.move_cursor_back || 0);
+                const moveCursorBack = toEditorLength(response.text, response.move_cursor_back || 0);
+                insertTextAtCursor(response.text, moveCursorBack);
+            }
+            break;
+            
+        case 'set_status':
+            showExtensionStatus(response.message || '');
+            break;
+            
+        case 'open_file':
+            if (response.path) {
+                await openFile(response.path);
             }
             break;
             
@@ -712,6 +1014,44 @@ async function handleExtensionResponse(response) {
     }
 }
 
+/**
+ * 文字（コードポイント）単位の位置をtextareaのUTF-16単位の位置に変換する
+ * 拡張機能とやり取りする位置はPythonの文字列の添字と同じ文字単位
+ */
+function toEditorOffset(text, charOffset) {
+    let index = 0;
+    for (let count = 0; count < charOffset && index < text.length; count++) {
+        index += text.codePointAt(index) > 0xFFFF ? 2 : 1;
+    }
+    return index;
+}
+
+/**
+ * textareaのUTF-16単位の位置を文字単位の位置に変換する
+ */
+function toCharOffset(text, editorOffset) {
+    return Array.from(text.substring(0, editorOffset)).length;
+}
+
+/**
+ * textの末尾charCount文字のUTF-16単位の長さ（move_cursor_backの変換用）
+ */
+function toEditorLength(text, charCount) {
+    const chars = Array.from(text);
+    return chars.slice(Math.max(0, chars.length - charCount)).join('').length;
+}
+
+/**
+ * 拡張機能からのメッセージをステータスバーに表示
+ */
+function showExtensionStatus(message) {
+    const extensionStatus = document.getElementById('extension-status');
+    if (!extensionStatus) return;
+    
+    extensionStatus.textContent = message;
+    extensionStatus.style.display = message ? 'inline' : 'none';
+}
+
 /**
  * サジェスションボックスを表示（インクリメンタルサーチ対応版）
  */
@@ -933,8 +1273,8 @@ function updateSuggestionItems(suggestionBox, suggestions) {
             const matchIndex = tag.toLowerCase().indexOf(searchText);
             if (matchIndex === 0) {
                 item.innerHTML = 
-                    `<span class="suggestion-highlight">${tag.substring(0, searchText.length)}</span>` +
-                    tag.substring(searchText.length);
+                    `<span class="suggestion-highlight">${escapeHtml(tag.substring(0, searchText.length))}</span>` +
+                    escapeHtml(tag.substring(searchText.length));
             } else {
                 item.textContent = tag;
             }
@@ -1259,6 +1599,439 @@ export async function openAppFolder() {
 }
 
 
+/**
+ * アーカイブ（.zip / .vsix）から拡張機能をインストール
+ */
+export async function installExtension() {
+    console.log('📦 Installing extension from archive...');
+    closeAllMenus();
+    
+    if (!window.__TAURI__?.dialog) {
+        alert('拡張機能のインストールは利用できません');
+        return;
+    }
+    
+    const archivePath = await window.__TAURI__.dialog.open({
+        title: '拡張機能をインストール',
+        multiple: false,
+        filters: [
+            { name: '拡張機能アーカイブ', extensions: ['zip', 'vsix'] }
+        ]
+    });
+    if (!archivePath) {
+        return;
+    }
+    
+    try {
+        let outcome = await tauriInvoke('install_extension_from_archive', { path: archivePath });
+        
+        // インストール済みの方が新しい場合は確認してから上書き
+        if (outcome.status === 'needs_confirmation') {
+            const message = `拡張機能「${outcome.id}」はより新しいバージョン（v${outcome.installed_version}）がインストールされています。\n\nv${outcome.version} で上書きしますか？`;
+            if (!confirm(message)) {
+                console.log('📦 Extension install cancelled by user');
+                return;
+            }
+            outcome = await tauriInvoke('install_extension_from_archive', { path: archivePath, allowDowngrade: true });
+        }
+        
+        console.log('✅ Extension installed:', outcome);
+        await scanExtensions();
+        
+        // 有効な拡張機能を更新した場合は新しいコードを読み込み直す
+        if (extensionState.enabledExtensions.includes(outcome.id)) {
+            await loadExtension(outcome.id);
+        }
+        
+        alert(`拡張機能「${outcome.id}」v${outcome.version} をインストールしました。\n${formatSignature(outcome.signature)}\n拡張機能設定から有効にできます。`);
+    } catch (error) {
+        console.error('❌ Failed to install extension:', error);
+        alert(`拡張機能をインストールできませんでした。\n\nエラー: ${error}`);
+    }
+}
+
+/**
+ * レジストリの比較結果の表示名
+ */
+const REGISTRY_STATUS_LABELS = {
+    not_installed: '未インストール',
+    up_to_date: '最新',
+    upgrade_available: '更新あり',
+    installed_newer: 'インストール済みの方が新しい'
+};
+
+/**
+ * ローカルのレジストリ（index.jsonを置いたディレクトリ）から拡張機能を追加・更新するダイアログ
+ */
+export async function showExtensionRegistryDialog() {
+    console.log('📚 Opening extension registry dialog...');
+    closeAllMenus();
+    
+    let settings = { directories: [] };
+    try {
+        settings = await tauriInvoke('get_extension_registries');
+    } catch (error) {
+        console.warn('⚠️ Could not load extension registries:', error);
+    }
+    
+    const existingDialog = document.getElementById('extension-registry-dialog-overlay');
+    if (existingDialog) {
+        document.body.removeChild(existingDialog);
+    }
+    
+    const dialogOverlay = document.createElement('div');
+    dialogOverlay.id = 'extension-registry-dialog-overlay';
+    dialogOverlay.className = 'search-dialog-overlay';
+    
+    const dialog = document.createElement('div');
+    dialog.className = 'search-dialog extension-registry-dialog';
+    dialog.innerHTML = `
+        <div class="search-dialog-header">拡張機能レジストリ</div>
+        <div class="search-dialog-content">
+            <div class="search-input-group">
+                <label for="extension-registry-input">レジストリのフォルダ（1行に1つ、index.jsonのあるフォルダ）</label>
+                <textarea id="extension-registry-input" class="search-input python-site-packages-input" rows="3">${escapeHtml(settings.directories.join('\n'))}</textarea>
+            </div>
+            <div class="search-button-group">
+                <button id="extension-registry-browse-btn" class="search-button">フォルダを追加…</button>
+                <button id="extension-registry-save-btn" class="search-button">保存して再読み込み</button>
+            </div>
+            
+            <div id="extension-registry-list" class="extension-list"></div>
+            
+            <div class="search-button-group">
+                <button id="extension-registry-close-btn" class="search-button search-button-cancel">閉じる</button>
+            </div>
+        </div>
+    `;
+    
+    dialogOverlay.appendChild(dialog);
+    document.body.appendChild(dialogOverlay);
+    makeDraggable(dialog);
+    
+    const directoriesInput = document.getElementById('extension-registry-input');
+    const list = document.getElementById('extension-registry-list');
+    
+    async function refreshRegistryList() {
+        list.innerHTML = '<div class="no-extensions">読み込み中...</div>';
+        try {
+            const [listing, diffs] = await Promise.all([
+                tauriInvoke('list_registry_extensions'),
+                tauriInvoke('diff_registry_extensions')
+            ]);
+            renderRegistryList(list, listing, diffs);
+        } catch (error) {
+            console.error('❌ Failed to read extension registries:', error);
+            list.innerHTML = `<div class="no-extensions">${escapeHtml(String(error))}</div>`;
+        }
+    }
+    
+    document.getElementById('extension-registry-browse-btn').addEventListener('click', async () => {
+        const directory = await window.__TAURI__?.dialog?.open({
+            title: 'レジストリのフォルダを選択',
+            directory: true,
+            multiple: false
+        });
+        if (directory) {
+            const current = directoriesInput.value.trim();
+            directoriesInput.value = current ? `${current}\n${directory}` : directory;
+        }
+    });
+    
+    document.getElementById('extension-registry-save-btn').addEventListener('click', async () => {
+        const directories = directoriesInput.value
+            .split('\n')
+            .map(line => line.trim())
+            .filter(line => line.length > 0);
+        
+        try {
+            await tauriInvoke('set_extension_registries', { directories });
+            console.log('✅ Extension registries saved:', directories);
+            await refreshRegistryList();
+        } catch (error) {
+            console.error('❌ Failed to save extension registries:', error);
+            alert(`レジストリを保存できませんでした。\n\nエラー: ${error}`);
+        }
+    });
+    
+    list.addEventListener('click', async (e) => {
+        const button = e.target.closest('[data-registry-install]');
+        if (!button) return;
+        
+        button.disabled = true;
+        await installFromRegistry(button.dataset.registryInstall);
+        await refreshRegistryList();
+    });
+    
+    document.getElementById('extension-registry-close-btn').addEventListener('click', () => {
+        closeExtensionDialog(dialogOverlay);
+    });
+    
+    dialogOverlay.addEventListener('click', (e) => {
+        if (e.target === dialogOverlay) {
+            closeExtensionDialog(dialogOverlay);
+        }
+    });
+    
+    await refreshRegistryList();
+}
+
+/**
+ * レジストリの拡張機能一覧を表示
+ */
+function renderRegistryList(list, listing, diffs) {
+    const extensions = new Map(listing.extensions.map(ext => [ext.id, ext]));
+    
+    const itemsHTML = diffs.map(diff => {
+        const ext = extensions.get(diff.id);
+        const installed = diff.installed_version ? `v${diff.installed_version} → ` : '';
+        const action = diff.status === 'not_installed' ? 'インストール'
+            : diff.status === 'upgrade_available' ? '更新'
+            : null;
+        
+        return `
+            <div class="extension-item extension-registry-item">
+                <div class="extension-info">
+                    <div class="extension-name">${escapeHtml(diff.name)} (${installed}v${diff.latest_version})</div>
+                    ${ext?.description ? `<div class="extension-summary">${escapeHtml(ext.description)}</div>` : ''}
+                    <div class="extension-author">${REGISTRY_STATUS_LABELS[diff.status]} ・ ${escapeHtml(diff.registry)}</div>
+                </div>
+                ${action ? `<button class="search-button" data-registry-install="${escapeHtml(diff.id)}">${action}</button>` : ''}
+            </div>
+        `;
+    }).join('');
+    
+    const errorsHTML = listing.errors.map(error => `
+        <div class="extension-item extension-item-invalid">
+            <div class="extension-diagnostic">${escapeHtml(error)}</div>
+        </div>
+    `).join('');
+    
+    const allHTML = itemsHTML + errorsHTML;
+    list.innerHTML = allHTML.length > 0 ? allHTML : '<div class="no-extensions">レジストリに拡張機能が見つかりません</div>';
+}
+
+/**
+ * レジストリから拡張機能の最新バージョンをインストール（SHA-256はRust側で確認）
+ */
+async function installFromRegistry(extensionId) {
+    try {
+        let outcome = await tauriInvoke('install_registry_extension', { id: extensionId });
+        
+        if (outcome.status === 'needs_confirmation') {
+            const message = `拡張機能「${outcome.id}」はより新しいバージョン（v${outcome.installed_version}）がインストールされています。\n\nv${outcome.version} で上書きしますか？`;
+            if (!confirm(message)) {
+                return;
+            }
+            outcome = await tauriInvoke('install_registry_extension', { id: extensionId, version: outcome.version, allowDowngrade: true });
+        }
+        
+        console.log('✅ Extension installed from registry:', outcome);
+        await scanExtensions();
+        
+        if (extensionState.enabledExtensions.includes(outcome.id)) {
+            await loadExtension(outcome.id);
+        }
+        showExtensionStatus(`📦 ${outcome.id} v${outcome.version} をインストールしました（${formatSignature(outcome.signature)}）`);
+    } catch (error) {
+        console.error(`❌ Failed to install ${extensionId} from registry:`, error);
+        alert(`拡張機能をインストールできませんでした。\n\nエラー: ${error}`);
+    }
+}
+
+/**
+ * 拡張機能の署名の検証設定ダイアログ（信頼する公開鍵と、署名のない拡張機能を拒否するかどうか）
+ */
+export async function showExtensionTrustDialog() {
+    console.log('🔏 Opening extension trust dialog...');
+    closeAllMenus();
+    
+    let settings = { require_signature: false, keys: [] };
+    try {
+        settings = await tauriInvoke('get_extension_trust');
+    } catch (error) {
+        console.warn('⚠️ Could not load trusted keys:', error);
+    }
+    
+    const existingDialog = document.getElementById('extension-trust-dialog-overlay');
+    if (existingDialog) {
+        document.body.removeChild(existingDialog);
+    }
+    
+    const dialogOverlay = document.createElement('div');
+    dialogOverlay.id = 'extension-trust-dialog-overlay';
+    dialogOverlay.className = 'search-dialog-overlay';
+    
+    const keysText = settings.keys.map(key => `${key.name} ${key.public_key}`).join('\n');
+    const dialog = document.createElement('div');
+    dialog.className = 'search-dialog extension-registry-dialog';
+    dialog.innerHTML = `
+        <div class="search-dialog-header">拡張機能の署名</div>
+        <div class="search-dialog-content">
+            <div class="search-input-group">
+                <label for="extension-trust-keys-input">信頼する公開鍵（1行に「名前 公開鍵（Base64）」）</label>
+                <textarea id="extension-trust-keys-input" class="search-input python-site-packages-input" rows="4">${escapeHtml(keysText)}</textarea>
+            </div>
+            
+            <div class="search-input-group">
+                <label class="extension-checkbox-label">
+                    <input type="checkbox" id="extension-trust-require-input" class="extension-checkbox" ${settings.require_signature ? 'checked' : ''}>
+                    信頼する鍵で署名された拡張機能のみ許可する
+                </label>
+            </div>
+            
+            <div class="python-environment-note">署名が一致しない拡張機能は常に拒否されます。変更はこの後に読み込む拡張機能から反映されます。</div>
+            
+            <div class="search-button-group">
+                <button id="extension-trust-save-btn" class="search-button search-button-primary">保存</button>
+                <button id="extension-trust-cancel-btn" class="search-button search-button-cancel">キャンセル</button>
+            </div>
+        </div>
+    `;
+    
+    dialogOverlay.appendChild(dialog);
+    document.body.appendChild(dialogOverlay);
+    makeDraggable(dialog);
+    
+    document.getElementById('extension-trust-save-btn').addEventListener('click', async () => {
+        // 行の最後の項目を公開鍵、それより前を名前とする
+        const keys = document.getElementById('extension-trust-keys-input').value
+            .split('\n')
+            .map(line => line.trim())
+            .filter(line => line.length > 0)
+            .map(line => {
+                const separator = line.lastIndexOf(' ');
+                return separator === -1
+                    ? { name: '', public_key: line }
+                    : { name: line.substring(0, separator).trim(), public_key: line.substring(separator + 1) };
+            });
+        const newSettings = {
+            require_signature: document.getElementById('extension-trust-require-input').checked,
+            keys
+        };
+        
+        try {
+            await tauriInvoke('set_extension_trust', { settings: newSettings });
+            console.log('✅ Extension trust settings saved:', newSettings);
+            closeExtensionDialog(dialogOverlay);
+            await scanExtensions();
+        } catch (error) {
+            console.error('❌ Failed to save extension trust settings:', error);
+            alert(`署名の設定を保存できませんでした。\n\nエラー: ${error}`);
+        }
+    });
+    
+    document.getElementById('extension-trust-cancel-btn').addEventListener('click', () => {
+        closeExtensionDialog(dialogOverlay);
+    });
+    
+    dialogOverlay.addEventListener('click', (e) => {
+        if (e.target === dialogOverlay) {
+            closeExtensionDialog(dialogOverlay);
+        }
+    });
+}
+
+/**
+ * 拡張機能用のPython環境（venv・追加のsite-packages）の設定ダイアログ
+ * 変更は次回起動時にsys.pathへ反映される
+ */
+export async function showPythonEnvironmentDialog() {
+    console.log('🐍 Opening Python environment dialog...');
+    closeAllMenus();
+    
+    let settings = { venv: null, site_packages: [] };
+    try {
+        settings = await tauriInvoke('get_python_environment');
+    } catch (error) {
+        console.warn('⚠️ Could not load Python environment settings:', error);
+    }
+    
+    const existingDialog = document.getElementById('python-environment-dialog-overlay');
+    if (existingDialog) {
+        document.body.removeChild(existingDialog);
+    }
+    
+    const dialogOverlay = document.createElement('div');
+    dialogOverlay.id = 'python-environment-dialog-overlay';
+    dialogOverlay.className = 'search-dialog-overlay';
+    
+    const dialog = document.createElement('div');
+    dialog.className = 'search-dialog python-environment-dialog';
+    dialog.innerHTML = `
+        <div class="search-dialog-header">拡張機能のPython環境</div>
+        <div class="search-dialog-content">
+            <div class="search-input-group">
+                <label for="python-venv-input">仮想環境（venv）</label>
+                <div class="python-venv-controls">
+                    <input type="text" id="python-venv-input" class="search-input" placeholder="未設定" value="${escapeHtml(settings.venv || '')}">
+                    <button id="python-venv-browse-btn" class="search-button">参照…</button>
+                </div>
+            </div>
+            
+            <div class="search-input-group">
+                <label for="python-site-packages-input">追加のsite-packages（1行に1つ）</label>
+                <textarea id="python-site-packages-input" class="search-input python-site-packages-input" rows="4">${escapeHtml(settings.site_packages.join('\n'))}</textarea>
+            </div>
+            
+            <div class="python-environment-note">組み込みPythonと同じバージョンの環境のみ使用できます。変更は再起動後に反映されます。</div>
+            
+            <div class="search-button-group">
+                <button id="python-environment-save-btn" class="search-button search-button-primary">保存</button>
+                <button id="python-environment-cancel-btn" class="search-button search-button-cancel">キャンセル</button>
+            </div>
+        </div>
+    `;
+    
+    dialogOverlay.appendChild(dialog);
+    document.body.appendChild(dialogOverlay);
+    makeDraggable(dialog);
+    
+    const venvInput = document.getElementById('python-venv-input');
+    const sitePackagesInput = document.getElementById('python-site-packages-input');
+    
+    document.getElementById('python-venv-browse-btn').addEventListener('click', async () => {
+        const directory = await window.__TAURI__?.dialog?.open({
+            title: '仮想環境のフォルダを選択',
+            directory: true,
+            multiple: false
+        });
+        if (directory) {
+            venvInput.value = directory;
+        }
+    });
+    
+    document.getElementById('python-environment-save-btn').addEventListener('click', async () => {
+        const newSettings = {
+            venv: venvInput.value.trim() || null,
+            site_packages: sitePackagesInput.value
+                .split('\n')
+                .map(line => line.trim())
+                .filter(line => line.length > 0)
+        };
+        
+        try {
+            await tauriInvoke('set_python_environment', { settings: newSettings });
+            console.log('✅ Python environment saved:', newSettings);
+            closeExtensionDialog(dialogOverlay);
+            alert('Python環境を保存しました。\nアプリを再起動すると反映されます。');
+        } catch (error) {
+            console.error('❌ Failed to save Python environment:', error);
+            alert(`Python環境を保存できませんでした。\n\nエラー: ${error}`);
+        }
+    });
+    
+    document.getElementById('python-environment-cancel-btn').addEventListener('click', () => {
+        closeExtensionDialog(dialogOverlay);
+    });
+    
+    dialogOverlay.addEventListener('click', (e) => {
+        if (e.target === dialogOverlay) {
+            closeExtensionDialog(dialogOverlay);
+        }
+    });
+}
+
 /**
  * 有効な拡張機能一覧を取得
  */
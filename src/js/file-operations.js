@@ -89,8 +89,9 @@ export async function newFile() {
 /**
  * ファイルを開く
  * 変更がある場合は保存確認ダイアログを表示
+ * filePathを指定した場合はファイル選択ダイアログを省略する（拡張機能のopen_file用）
  */
-export async function openFile() {
+export async function openFile(filePath = null) {
     try {
         console.log('📂 Opening file, isModified:', isModified);
         
@@ -124,7 +125,11 @@ export async function openFile() {
             }
         }
         
-        await showFileOpenDialog();
+        if (filePath) {
+            await loadFileIntoEditor(filePath);
+        } else {
+            await showFileOpenDialog();
+        }
         
     } catch (error) {
         console.error('File open error:', error);
@@ -155,41 +160,69 @@ async function showFileOpenDialog() {
         });
         
         if (filePath) {
-            console.log('📂 Opening file:', filePath);
-            
-            let content;
-            if (window.__TAURI__ && window.__TAURI__.fs) {
-                content = await window.__TAURI__.fs.readTextFile(filePath);
-            } else {
-                content = await tauriInvoke('read_file', { path: filePath });
-            }
-            
-            // エディタに設定してアンドゥスタックを完全リセット
-            editor.value = content;
-            setCurrentFilePath(filePath);
-            setIsModified(false);
-            setCurrentContent(content);
-            
-            // アンドゥ・リドゥスタックを完全にクリア
-            undoStack.length = 0;
-            redoStack.length = 0;
-            
-            // ファイル内容で初期化
-            initializeUndoStack();
-            updateLineNumbers();
-            updateStatus();
-            
-            // タイトル更新を追加
-            console.log('🏷️ Updating title for opened file...');
-            await updateWindowTitle();
-            
-            console.log('✅ File opened successfully:', filePath);
+            await loadFileIntoEditor(filePath);
         }
     } else {
         alert(t('messages.tauriOnly'));
     }
 }
 
+/**
+ * 指定したファイルをエディタに読み込む
+ */
+async function loadFileIntoEditor(filePath) {
+    console.log('📂 Opening file:', filePath);
+    
+    // read_file経由で読み込み、拡張機能にfile_openedを通知する
+    let content;
+    if (tauriInvoke) {
+        content = await tauriInvoke('read_file', { path: filePath });
+    } else {
+        content = await window.__TAURI__.fs.readTextFile(filePath);
+    }
+    
+    // エディタに設定してアンドゥスタックを完全リセット
+    editor.value = content;
+    setCurrentFilePath(filePath);
+    setIsModified(false);
+    setCurrentContent(content);
+    
+    // アンドゥ・リドゥスタックを完全にクリア
+    undoStack.length = 0;
+    redoStack.length = 0;
+    
+    // ファイル内容で初期化
+    initializeUndoStack();
+    updateLineNumbers();
+    updateStatus();
+    
+    // タイトル更新を追加
+    console.log('🏷️ Updating title for opened file...');
+    await updateWindowTitle();
+    
+    console.log('✅ File opened successfully:', filePath);
+}
+
+/**
+ * エディタの内容をwrite_file経由で保存する
+ * 拡張機能のbefore_saveが内容を書き換えた場合はエディタにも反映する
+ */
+export async function writeFileWithExtensions(filePath) {
+    const modifiedContent = await tauriInvoke('write_file', {
+        path: filePath,
+        content: editor.value
+    });
+    
+    if (typeof modifiedContent === 'string') {
+        console.log('✏️ Content modified by extensions before save');
+        const cursorPosition = Math.min(editor.selectionStart, modifiedContent.length);
+        editor.value = modifiedContent;
+        editor.selectionStart = editor.selectionEnd = cursorPosition;
+        updateLineNumbers();
+        updateStatus();
+    }
+}
+
 /**
  * ファイル保存
  */
@@ -202,17 +235,14 @@ export async function saveFile() {
         if (currentFilePath) {
             console.log('Saving to existing file:', currentFilePath);
             
-            if (window.__TAURI__ && window.__TAURI__.fs) {
+            if (tauriInvoke) {
+                console.log('Using Tauri invoke');
+                await writeFileWithExtensions(currentFilePath);
+                console.log('File saved successfully with Tauri invoke');
+            } else if (window.__TAURI__ && window.__TAURI__.fs) {
                 console.log('Using Tauri fs API');
                 await window.__TAURI__.fs.writeTextFile(currentFilePath, editor.value);
                 console.log('File saved successfully with Tauri fs');
-            } else if (tauriInvoke) {
-                console.log('Using Tauri invoke');
-                await tauriInvoke('write_file', { 
-                    path: currentFilePath, 
-                    content: editor.value 
-                });
-                console.log('File saved successfully with Tauri invoke');
             } else {
                 console.error('No Tauri API available');
                 alert(t('messages.tauriOnly'));
@@ -270,17 +300,14 @@ export async function saveAsFile() {
             if (filePath) {
                 console.log('User selected file path:', filePath);
                 
-                if (window.__TAURI__ && window.__TAURI__.fs) {
+                if (tauriInvoke) {
+                    console.log('Using Tauri invoke for saveAs');
+                    await writeFileWithExtensions(filePath);
+                    console.log('File saved successfully with Tauri invoke');
+                } else if (window.__TAURI__ && window.__TAURI__.fs) {
                     console.log('Using Tauri fs API for saveAs');
                     await window.__TAURI__.fs.writeTextFile(filePath, editor.value);
                     console.log('File saved successfully with Tauri fs');
-                } else if (tauriInvoke) {
-                    console.log('Using Tauri invoke for saveAs');
-                    await tauriInvoke('write_file', { 
-                        path: filePath, 
-                        content: editor.value 
-                    });
-                    console.log('File saved successfully with Tauri invoke');
                 } else {
                     console.error('No Tauri API available for file writing');
                     alert(t('messages.tauriOnly'));
@@ -317,13 +344,10 @@ export async function saveAsFile() {
  */
 async function saveFileBeforeNew() {
     if (currentFilePath) {
-        if (window.__TAURI__ && window.__TAURI__.fs) {
+        if (tauriInvoke) {
+            await writeFileWithExtensions(currentFilePath);
+        } else if (window.__TAURI__ && window.__TAURI__.fs) {
             await window.__TAURI__.fs.writeTextFile(currentFilePath, editor.value);
-        } else if (tauriInvoke) {
-            await tauriInvoke('write_file', { 
-                path: currentFilePath, 
-                content: editor.value 
-            });
         } else {
             throw new Error(t('messages.tauriOnly'));
         }
@@ -360,13 +384,10 @@ async function saveAsFileForNew() {
             });
             
             if (filePath) {
-                if (window.__TAURI__ && window.__TAURI__.fs) {
-                    await window.__TAURI__.fs.writeTextFile(filePath, editor.value);
+                if (tauriInvoke) {
+                    await writeFileWithExtensions(filePath);
                 } else {
-                    await tauriInvoke('write_file', { 
-                        path: filePath, 
-                        content: editor.value 
-                    });
+                    await window.__TAURI__.fs.writeTextFile(filePath, editor.value);
                 }
                 
                 setCurrentFilePath(filePath);
@@ -394,13 +415,10 @@ async function saveAsFileForNew() {
  */
 async function saveFileBeforeOpen() {
     if (currentFilePath) {
-        if (window.__TAURI__ && window.__TAURI__.fs) {
+        if (tauriInvoke) {
+            await writeFileWithExtensions(currentFilePath);
+        } else if (window.__TAURI__ && window.__TAURI__.fs) {
             await window.__TAURI__.fs.writeTextFile(currentFilePath, editor.value);
-        } else if (tauriInvoke) {
-            await tauriInvoke('write_file', { 
-                path: currentFilePath, 
-                content: editor.value 
-            });
         } else {
             throw new Error(t('messages.tauriOnly'));
         }
@@ -437,13 +455,10 @@ async function saveAsFileForOpen() {
             });
             
             if (filePath) {
-                if (window.__TAURI__ && window.__TAURI__.fs) {
-                    await window.__TAURI__.fs.writeTextFile(filePath, editor.value);
+                if (tauriInvoke) {
+                    await writeFileWithExtensions(filePath);
                 } else {
-                    await tauriInvoke('write_file', { 
-                        path: filePath, 
-                        content: editor.value 
-                    });
+                    await window.__TAURI__.fs.writeTextFile(filePath, editor.value);
                 }
                 
                 setCurrentFilePath(filePath);
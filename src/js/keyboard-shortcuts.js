@@ -11,6 +11,8 @@ import { undo, redo } from './undo-redo.js';
 import { selectAll, copy, cut, paste } from './edit-operations.js';
 import { showSearchDialog, showReplaceDialog, findNext, findPrevious } from './search-replace.js';
 import { updateStatus } from './ui-updater.js';
+import { evaluateSelection } from './evaluate-selection.js';
+import { runCell, runAllCells, isPythonDocument } from './python-cells.js';
 
 /**
  * キーボードイベントの処理
@@ -101,6 +103,27 @@ export async function handleKeydown(e) {    // Windows/Linuxでは Ctrl、macOS
         return;
     }
     
+    // 選択範囲を評価 (Ctrl/Cmd+Shift+E)
+    if (isMainModifier && e.shiftKey && (e.key === 'E' || e.key === 'e')) {
+        e.preventDefault();
+        console.log('Evaluate selection shortcut pressed');
+        await evaluateSelection();
+        return;
+    }
+    
+    // セルを実行 (Ctrl/Cmd+Enter)、すべてのセルを実行 (Ctrl/Cmd+Shift+Enter)
+    // Pythonのドキュメント以外では横取りしない
+    if (isMainModifier && e.key === 'Enter' && isPythonDocument()) {
+        e.preventDefault();
+        console.log('Run cell shortcut pressed, Shift:', e.shiftKey);
+        if (e.shiftKey) {
+            await runAllCells();
+        } else {
+            await runCell();
+        }
+        return;
+    }
+    
     // 次を検索 (F3 または Ctrl/Cmd+G)
     if (e.key === 'F3' || (isMainModifier && e.key === 'g' && !e.shiftKey)) {
         e.preventDefault();
@@ -44,7 +44,26 @@ const FALLBACK_LANGUAGES = {
             cut: '切り取り',
             copy: 'コピー',
             paste: '貼り付け',
-            selectAll: 'すべて選択'
+            selectAll: 'すべて選択',
+            evaluateSelection: '選択範囲を評価',
+            evaluateSettings: '評価結果の挿入方法…',
+            runCell: 'セルを実行',
+            runAllCells: 'すべてのセルを実行'
+        },
+        evaluate: {
+            title: '評価結果の挿入方法',
+            modes: {
+                replace: '選択範囲を結果で置き換える',
+                append: '選択範囲の後ろに「= 結果」を追加する',
+                insert_below: '次の行に結果を挿入する'
+            }
+        },
+        cells: {
+            running: 'セルを実行中...',
+            editedDuringRun: '実行中に編集されたため、出力を書き込みませんでした',
+            lineError: '{line}行目: {message}',
+            completed: '{count}個のセルを実行しました',
+            notPython: 'セルを実行できるのはPythonファイル（.py）のみです'
         },
         viewMenu: {
             fontSettings: 'フォント設定',
@@ -62,6 +81,10 @@ const FALLBACK_LANGUAGES = {
         },
         extensionsMenu: {
             extensionSettings: '拡張機能設定',
+            installExtension: '拡張機能をインストール…',
+            extensionRegistry: 'レジストリから追加・更新…',
+            extensionTrust: '拡張機能の署名…',
+            pythonEnvironment: 'Python環境…',
             languageSettings: '言語設定',
             theme: 'テーマ',
             openAppFolder: 'アプリフォルダを開く'
@@ -154,7 +177,10 @@ const FALLBACK_LANGUAGES = {
             appName: 'Vinsert',
             description: 'Vinsert Is New Simple Editor by Rust and Tauri',
             version: 'Version 1.00',
-            author: 'Author : Akihiko Ouchi a.k.a 如月 翔也（from 歳月堂）'
+            author: 'Author : Akihiko Ouchi a.k.a 如月 翔也（from 歳月堂）',
+            pythonEmbedded: 'Python {version}（組み込み）',
+            pythonSystem: 'Python {version}（システム）',
+            pythonUnavailable: 'Python: 利用できません'
         },
         whitespace: {
             enable: '空白文字の可視化を有効にする',
@@ -210,7 +236,26 @@ const FALLBACK_LANGUAGES = {
             cut: 'Cut',
             copy: 'Copy',
             paste: 'Paste',
-            selectAll: 'Select All'
+            selectAll: 'Select All',
+            evaluateSelection: 'Evaluate Selection',
+            evaluateSettings: 'Evaluation Output…',
+            runCell: 'Run Cell',
+            runAllCells: 'Run All Cells'
+        },
+        evaluate: {
+            title: 'Evaluation Output',
+            modes: {
+                replace: 'Replace the selection with the result',
+                append: 'Append "= result" after the selection',
+                insert_below: 'Insert the result on the next line'
+            }
+        },
+        cells: {
+            running: 'Running cells...',
+            editedDuringRun: 'The document was edited while running, so the output was not written',
+            lineError: 'Line {line}: {message}',
+            completed: 'Ran {count} cell(s)',
+            notPython: 'Cells can only be run in Python files (.py)'
         },
         viewMenu: {
             fontSettings: 'Font Settings',
@@ -228,6 +273,10 @@ const FALLBACK_LANGUAGES = {
         },
         extensionsMenu: {
             extensionSettings: 'Extension Settings',
+            installExtension: 'Install Extension…',
+            extensionRegistry: 'Add or Update from Registry…',
+            extensionTrust: 'Extension Signatures…',
+            pythonEnvironment: 'Python Environment…',
             languageSettings: 'Language Settings',
             theme: 'Theme',
             openAppFolder: 'Open App Folder'
@@ -256,7 +305,10 @@ const FALLBACK_LANGUAGES = {
             appName: 'Vinsert',
             description: 'Vinsert Is New Simple Editor by Rust and Tauri',
             version: 'Version 1.00',
-            author: 'Author : Akihiko Ouchi a.k.a show-ya kisaragi（from saigetsudo）'
+            author: 'Author : Akihiko Ouchi a.k.a show-ya kisaragi（from saigetsudo）',
+            pythonEmbedded: 'Python {version} (embedded)',
+            pythonSystem: 'Python {version} (system)',
+            pythonUnavailable: 'Python: not available'
         },
         whitespace: {
             enable: 'Enable whitespace visualization',
@@ -375,7 +427,26 @@ const FALLBACK_LANGUAGES = {
             cut: 'Couper',
             copy: 'Copier',
             paste: 'Coller',
-            selectAll: 'Tout sélectionner'
+            selectAll: 'Tout sélectionner',
+            evaluateSelection: 'Évaluer la sélection',
+            evaluateSettings: 'Sortie de l\'évaluation…',
+            runCell: 'Exécuter la cellule',
+            runAllCells: 'Exécuter toutes les cellules'
+        },
+        evaluate: {
+            title: 'Sortie de l\'évaluation',
+            modes: {
+                replace: 'Remplacer la sélection par le résultat',
+                append: 'Ajouter « = résultat » après la sélection',
+                insert_below: 'Insérer le résultat à la ligne suivante'
+            }
+        },
+        cells: {
+            running: 'Exécution des cellules...',
+            editedDuringRun: 'Le document a été modifié pendant l\'exécution, la sortie n\'a pas été écrite',
+            lineError: 'Ligne {line} : {message}',
+            completed: '{count} cellule(s) exécutée(s)',
+            notPython: 'Les cellules ne peuvent être exécutées que dans les fichiers Python (.py)'
         },
         viewMenu: {
             fontSettings: 'Paramètres de police',
@@ -393,6 +464,10 @@ const FALLBACK_LANGUAGES = {
         },
         extensionsMenu: {
             extensionSettings: 'Paramètres d\'extension',
+            installExtension: 'Installer une extension…',
+            extensionRegistry: 'Ajouter ou mettre à jour depuis un registre…',
+            extensionTrust: 'Signatures des extensions…',
+            pythonEnvironment: 'Environnement Python…',
             languageSettings: 'Paramètres de langue',
             theme: 'Thème',
             openAppFolder: 'Ouvrir le dossier de l\'app'
@@ -507,7 +582,10 @@ const FALLBACK_LANGUAGES = {
             appName: 'Vinsert',
             description: 'Vinsert Is New Simple Editor by Rust and Tauri',
             version: 'Version 1.00',
-            author: 'Author : Akihiko Ouchi a.k.a show-ya kisaragi（from saigetsudo）'
+            author: 'Author : Akihiko Ouchi a.k.a show-ya kisaragi（from saigetsudo）',
+            pythonEmbedded: 'Python {version} (intégré)',
+            pythonSystem: 'Python {version} (système)',
+            pythonUnavailable: 'Python : non disponible'
         },
         whitespace: {
             enable: 'Activer la visualisation des espaces',
@@ -0,0 +1,134 @@
+/*
+ * =====================================================
+ * Vinsert Editor - セル実行（# %% で区切ったPythonコード）
+ * カーソル位置のセル、またはすべてのセルを実行し、出力ブロックをエディタに反映する
+ * =====================================================
+ */
+
+import { editor, tauriInvoke, currentFilePath } from './globals.js';
+import { closeAllMenus } from './menu-controller.js';
+import { saveToUndoStack } from './undo-redo.js';
+import { t } from './locales.js';
+
+// 実行中はセル実行を受け付けない
+let isRunning = false;
+
+/**
+ * カーソルのある行（0始まり）
+ */
+function getCursorLine() {
+    return editor.value.substring(0, editor.selectionStart).split('\n').length - 1;
+}
+
+/**
+ * 指定した行（0始まり）の先頭位置
+ */
+function getLineStart(text, line) {
+    let position = 0;
+    for (let i = 0; i < line; i++) {
+        const next = text.indexOf('\n', position);
+        if (next === -1) {
+            return text.length;
+        }
+        position = next + 1;
+    }
+    return position;
+}
+
+/**
+ * セルを実行できるドキュメントか（.pyファイル、または未保存のドキュメント）
+ */
+export function isPythonDocument() {
+    return !currentFilePath || currentFilePath.toLowerCase().endsWith('.py');
+}
+
+/**
+ * ステータスバーにメッセージを表示
+ */
+function showCellStatus(message) {
+    const extensionStatus = document.getElementById('extension-status');
+    if (!extensionStatus) return;
+
+    extensionStatus.textContent = message;
+    extensionStatus.style.display = message ? 'inline' : 'none';
+}
+
+/**
+ * セルを実行して結果をエディタに反映
+ * lineがnullの場合はすべてのセルを実行する
+ */
+async function runCells(line) {
+    closeAllMenus();
+
+    if (!tauriInvoke) {
+        console.warn('⚠️ Tauri API not available for cell execution');
+        return;
+    }
+    if (!isPythonDocument()) {
+        showCellStatus(`⚠️ ${t('cells.notPython')}`);
+        return;
+    }
+    if (isRunning) {
+        console.log('⏳ Cell execution already running');
+        return;
+    }
+
+    isRunning = true;
+    showCellStatus(`▶ ${t('cells.running')}`);
+    const original = editor.value;
+
+    try {
+        const result = await tauriInvoke('run_python_cells', {
+            document: currentFilePath,
+            text: original,
+            line
+        });
+
+        // 実行中に編集された場合は出力を書き込まない
+        if (editor.value !== original) {
+            showCellStatus(`⚠️ ${t('cells.editedDuringRun')}`);
+            return;
+        }
+
+        if (result.text !== original) {
+            saveToUndoStack(original, editor.selectionStart);
+            editor.value = result.text;
+        }
+
+        const cursorPos = getLineStart(editor.value, result.cursor_line);
+        editor.setSelectionRange(cursorPos, cursorPos);
+
+        // inputイベントを発火
+        editor.dispatchEvent(new Event('input', { bubbles: true }));
+
+        if (result.error) {
+            const message = result.error.line
+                ? t('cells.lineError', { line: result.error.line, message: result.error.message })
+                : result.error.message;
+            showCellStatus(`⚠️ ${message}`);
+        } else {
+            showCellStatus(`✅ ${t('cells.completed', { count: result.cells_run })}`);
+        }
+    } catch (error) {
+        const message = error && error.message ? error.message : String(error);
+        console.error('❌ Cell execution failed:', message);
+        showCellStatus(`⚠️ ${message}`);
+    } finally {
+        isRunning = false;
+        editor.focus();
+    }
+}
+
+/**
+ * カーソル位置のセルを実行
+ */
+export async function runCell() {
+    await runCells(getCursorLine());
+}
+
+/**
+ * すべてのセルを順に実行（エラーが起きたセルで止まる）
+ */
+export async function runAllCells() {
+    await runCells(null);
+}
@@ -17,6 +17,8 @@ import { exitApp } from './js/app-exit.js';
 import { toggleLineHighlight } from './js/line-highlight.js';
 import { showThemeDialog, showLanguageSettingsDialog } from './js/theme-manager.js';
 import { toggleTypewriterMode } from './js/typewriter-mode.js';
+import { evaluateSelection, showEvaluateSettingsDialog } from './js/evaluate-selection.js';
+import { runCell, runAllCells } from './js/python-cells.js';
 
 console.log('🔧 Registering global functions...');
 
@@ -44,6 +46,10 @@ window.toggleLineHighlight = toggleLineHighlight;
 window.showThemeDialog = showThemeDialog;
 window.showLanguageSettingsDialog = showLanguageSettingsDialog;
 window.toggleTypewriterMode = toggleTypewriterMode;
+window.evaluateSelection = evaluateSelection;
+window.showEvaluateSettingsDialog = showEvaluateSettingsDialog;
+window.runCell = runCell;
+window.runAllCells = runAllCells;
 
 // タブサイズ調整機能（デバッグ用）
 window.debugTabSize = async function() {
@@ -95,10 +101,15 @@ console.log('✅ Global functions registered');
 // 拡張機能システムの遅延読み込み
 async function loadExtensionSystem() {
     try {
-        const { initializeExtensionSystem, showExtensionSettingsDialog, openAppFolder } = await import('./js/extension-manager.js');
+        const { initializeExtensionSystem, showExtensionSettingsDialog, openAppFolder, installExtension, showExtensionRegistryDialog, showExtensionTrustDialog, showPythonEnvironmentDialog, runExtensionCommand } = await import('./js/extension-manager.js');
         
         window.showExtensionSettingsDialog = showExtensionSettingsDialog;
         window.openAppFolder = openAppFolder;
+        window.installExtension = installExtension;
+        window.showExtensionRegistryDialog = showExtensionRegistryDialog;
+        window.showExtensionTrustDialog = showExtensionTrustDialog;
+        window.showPythonEnvironmentDialog = showPythonEnvironmentDialog;
+        window.runExtensionCommand = runExtensionCommand;
         
         await initializeExtensionSystem();
         console.log('✅ Extension system loaded');
@@ -106,6 +117,10 @@ async function loadExtensionSystem() {
         console.error('❌ Extension system failed:', error);
         window.showExtensionSettingsDialog = () => alert('拡張機能システムが利用できません');
         window.openAppFolder = () => alert('アプリフォルダ機能が利用できません');
+        window.installExtension = () => alert('拡張機能システムが利用できません');
+        window.showExtensionRegistryDialog = () => alert('拡張機能システムが利用できません');
+        window.showExtensionTrustDialog = () => alert('拡張機能システムが利用できません');
+        window.showPythonEnvironmentDialog = () => alert('拡張機能システムが利用できません');
     }
 }
 